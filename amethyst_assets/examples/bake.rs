@@ -0,0 +1,73 @@
+//! Baking assets ahead of time.
+//!
+//! Run with `cargo run --example bake -- [assets dir] [output dir]`; without arguments, the
+//! assets of the examples are baked into `target/baked_assets`.
+//! A game would typically ship a binary like this one, adding an importer for every format
+//! worth baking.
+
+use std::{env, process};
+
+use serde::{Deserialize, Serialize};
+
+use amethyst_assets::*;
+use amethyst_error::{format_err, Error, ResultExt};
+
+/// The mesh format of the `hl` example.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Ron;
+
+impl Format<VertexData> for Ron {
+    fn name(&self) -> &'static str {
+        "RON"
+    }
+
+    fn import_simple(&self, bytes: Vec<u8>) -> Result<VertexData, Error> {
+        use ron::de::from_str;
+        use std::str::from_utf8;
+
+        let s = from_utf8(&bytes)?;
+
+        from_str(s).with_context(|_| format_err!("Failed to decode mesh file"))
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct VertexData {
+    positions: Vec<[f32; 3]>,
+    tex_coords: Vec<[f32; 2]>,
+}
+
+fn main() {
+    let mut args = env::args().skip(1);
+    let input = args
+        .next()
+        .unwrap_or_else(|| format!("{}/examples/assets", env!("CARGO_MANIFEST_DIR")));
+    let output = args
+        .next()
+        .unwrap_or_else(|| format!("{}/../target/baked_assets", env!("CARGO_MANIFEST_DIR")));
+
+    let baker = AssetBaker::new().with_importer(FormatImporter::new(Ron, 1, &["ron"]));
+    let report = match baker.bake(&input, &output) {
+        Ok(report) => report,
+        Err(e) => {
+            eprintln!("Baking {:?} failed: {}", input, e);
+            process::exit(1);
+        }
+    };
+
+    for path in &report.baked {
+        println!("baked: {}", path);
+    }
+    for path in &report.cached {
+        println!("up to date: {}", path);
+    }
+    for (path, e) in &report.failed {
+        eprintln!("failed: {}: {}", path, e);
+    }
+    if !report.failed.is_empty() {
+        process::exit(1);
+    }
+
+    // At runtime, the loader picks up the baked meshes with
+    // `loader.set_baked(BakedAssets::open(output)?.with_format::<VertexData, _>(&Ron, 1))`.
+}
@@ -1,4 +1,6 @@
-use crate::{storage::ProcessingState, FormatRegisteredData, Handle, Reload, SingleFile, Source};
+use crate::{
+    storage::ProcessingState, AssetId, FormatRegisteredData, Handle, Reload, SingleFile, Source,
+};
 use amethyst_core::ecs::storage::UnprotectedStorage;
 use amethyst_error::{Error, ResultExt};
 use std::{fmt::Debug, ops::Deref, sync::Arc};
@@ -29,6 +31,15 @@ pub trait Asset: Send + Sync + 'static {
 
     /// The ECS storage type to be used. You'll want to use `DenseVecStorage` in most cases.
     type HandleStorage: UnprotectedStorage<Handle<Self>> + Send + Sync;
+
+    /// Estimated number of bytes this asset occupies, used for the memory budget of a
+    /// `CachePolicy`.
+    ///
+    /// The default only accounts for the size of `Self`, so assets owning heap memory, like
+    /// meshes or textures, should override it.
+    fn size_hint(&self) -> usize {
+        std::mem::size_of_val(self)
+    }
 }
 
 /// Defines a way to process asset's data into the asset. This allows
@@ -100,6 +111,15 @@ pub trait Format<D: 'static>: objekt::Clone + Debug + Send + Sync + 'static {
             Ok(FormatValue::data(self.import_simple(b)?))
         }
     }
+
+    /// Returns the assets the imported asset depends on, e.g. a texture handle
+    /// stored inside of the format.
+    ///
+    /// The `Loader` records these in its `DependencyGraph`, so the imported asset
+    /// is reloaded whenever one of its dependencies is hot reloaded.
+    fn dependencies(&self) -> Vec<AssetId> {
+        Vec::new()
+    }
 }
 
 objekt::clone_trait_object!(<D> Format<D>);
@@ -135,6 +155,10 @@ impl<D: 'static> Format<D> for Box<dyn Format<D>> {
     ) -> Result<FormatValue<D>, Error> {
         self.deref().import(name, source, create_reload)
     }
+
+    fn dependencies(&self) -> Vec<AssetId> {
+        self.deref().dependencies()
+    }
 }
 
 impl<D: 'static> Format<D> for Box<dyn SerializableFormat<D>> {
@@ -153,6 +177,10 @@ impl<D: 'static> Format<D> for Box<dyn SerializableFormat<D>> {
     ) -> Result<FormatValue<D>, Error> {
         self.deref().import(name, source, create_reload)
     }
+
+    fn dependencies(&self) -> Vec<AssetId> {
+        self.deref().dependencies()
+    }
 }
 
 impl<D: FormatRegisteredData + 'static> SerializableFormat<D> for Box<dyn SerializableFormat<D>> {}
@@ -0,0 +1,527 @@
+//! Offline baking of assets.
+//!
+//! Importing raw files like images or models can be slow, so it can be done ahead of time:
+//! an `AssetBaker` walks an assets directory, runs an `Importer` for every file it knows and
+//! writes the imported data, encoded with `BincodeFormat`, together with a `BakeManifest`.
+//!
+//! At runtime, `Loader::set_baked` makes the loader use the baked data instead of importing
+//! the raw file again, as long as the raw file didn't change since it was baked.
+
+use std::{
+    any::{Any, TypeId},
+    collections::BTreeMap,
+    fs,
+    hash::Hasher,
+    marker::PhantomData,
+    path::Path,
+    sync::Arc,
+};
+
+use fnv::{FnvHashMap, FnvHasher};
+use log::{debug, warn};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+use amethyst_error::{format_err, Error, ResultExt};
+
+use crate::{
+    source::collect_files, BincodeFormat, Directory, Format, FormatValue, Reload, RonFormat,
+    SingleFile, Source,
+};
+
+/// Name of the manifest file written into the output directory of an `AssetBaker`.
+pub const BAKE_MANIFEST: &str = "bake_manifest.ron";
+
+/// Returns the hash baked assets are keyed by.
+///
+/// Unlike the standard library's hashers, the result is stable across runs and platforms.
+pub fn content_hash(bytes: &[u8]) -> u64 {
+    let mut hasher = FnvHasher::default();
+    hasher.write(bytes);
+    hasher.finish()
+}
+
+/// Imports raw asset files ahead of time.
+pub trait Importer: Send + Sync + 'static {
+    /// Name of the importer, which has to be the name of the `Format` the baked data replaces.
+    fn name(&self) -> &'static str;
+
+    /// Version of the importer.
+    ///
+    /// Bump it whenever the imported data changes, so previously baked assets are baked again.
+    fn version(&self) -> u32;
+
+    /// Returns `true` if the importer can import the file at the given path.
+    fn accepts(&self, path: &str) -> bool;
+
+    /// Imports the file at `path` from `source` and returns the baked data.
+    fn bake(&self, path: &str, source: Arc<dyn Source>) -> Result<Vec<u8>, Error>;
+}
+
+/// `Importer` running a `Format` on all files with one of the given extensions.
+///
+/// The imported data is stored with `BincodeFormat`.
+#[derive(Clone, Debug)]
+pub struct FormatImporter<D, F> {
+    format: F,
+    version: u32,
+    extensions: Vec<String>,
+    marker: PhantomData<fn() -> D>,
+}
+
+impl<D, F> FormatImporter<D, F>
+where
+    F: Format<D>,
+    D: 'static,
+{
+    /// Creates a new importer for files with one of the given extensions.
+    pub fn new(format: F, version: u32, extensions: &[&str]) -> Self {
+        FormatImporter {
+            format,
+            version,
+            extensions: extensions.iter().map(|ext| ext.to_lowercase()).collect(),
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<D, F> Importer for FormatImporter<D, F>
+where
+    F: Format<D>,
+    D: Serialize + 'static,
+{
+    fn name(&self) -> &'static str {
+        self.format.name()
+    }
+
+    fn version(&self) -> u32 {
+        self.version
+    }
+
+    fn accepts(&self, path: &str) -> bool {
+        Path::new(path)
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| self.extensions.contains(&ext.to_lowercase()))
+            .unwrap_or(false)
+    }
+
+    fn bake(&self, path: &str, source: Arc<dyn Source>) -> Result<Vec<u8>, Error> {
+        let FormatValue { data, .. } = self
+            .format
+            .import(path.to_owned(), source, None)
+            .with_context(|_| crate::error::Error::Format(self.format.name()))?;
+        BincodeFormat::serialize(&data)
+    }
+}
+
+/// Baked version of a single asset file.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct BakedEntry {
+    /// `content_hash` of the raw file the entry was baked from.
+    pub hash: u64,
+    /// Name of the importer.
+    pub importer: String,
+    /// Version of the importer.
+    pub version: u32,
+    /// Path of the baked data, relative to the output directory.
+    pub output: String,
+}
+
+/// List of baked assets, keyed by the path of the raw file.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct BakeManifest {
+    entries: BTreeMap<String, BakedEntry>,
+}
+
+impl BakeManifest {
+    /// Reads the manifest from the given output directory.
+    ///
+    /// Returns an empty manifest if the directory doesn't contain one.
+    pub fn load<P: AsRef<Path>>(dir: P) -> Result<Self, Error> {
+        let path = dir.as_ref().join(BAKE_MANIFEST);
+        if !path.exists() {
+            return Ok(BakeManifest::default());
+        }
+
+        let bytes = fs::read(&path)
+            .with_context(|_| format_err!("Failed to read bake manifest {:?}", path))?;
+        RonFormat.import_simple(bytes)
+    }
+
+    /// Writes the manifest into the given output directory.
+    pub fn save<P: AsRef<Path>>(&self, dir: P) -> Result<(), Error> {
+        let path = dir.as_ref().join(BAKE_MANIFEST);
+        let ron = ron::ser::to_string_pretty(self, Default::default())
+            .with_context(|_| format_err!("Failed to serialize bake manifest"))?;
+        fs::write(&path, ron).with_context(|_| format_err!("Failed to write {:?}", path))
+    }
+
+    /// Returns the entry for the raw file at the given path.
+    pub fn get(&self, path: &str) -> Option<&BakedEntry> {
+        self.entries.get(path)
+    }
+
+    /// Returns all entries, keyed by the path of the raw file.
+    pub fn entries(&self) -> impl Iterator<Item = (&str, &BakedEntry)> {
+        self.entries
+            .iter()
+            .map(|(path, entry)| (path.as_str(), entry))
+    }
+}
+
+/// Result of `AssetBaker::bake`.
+#[derive(Debug, Default)]
+pub struct BakeReport {
+    /// Files which were imported.
+    pub baked: Vec<String>,
+    /// Files whose baked data was still up to date.
+    pub cached: Vec<String>,
+    /// Files which failed to import.
+    pub failed: Vec<(String, Error)>,
+}
+
+/// Bakes all files of an assets directory which an `Importer` accepts.
+///
+/// ```rust,ignore
+/// let report = AssetBaker::new()
+///     .with_importer(FormatImporter::new(ObjFormat, 1, &["obj"]))
+///     .bake("assets", "assets_baked")?;
+/// ```
+///
+/// Files are only imported again if their content, the importer or its version changed.
+#[derive(Default)]
+pub struct AssetBaker {
+    importers: Vec<Box<dyn Importer>>,
+}
+
+impl AssetBaker {
+    /// Creates a baker without any importers.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Adds an importer. If multiple importers accept a file, the first one added is used.
+    pub fn with_importer<I>(mut self, importer: I) -> Self
+    where
+        I: Importer,
+    {
+        self.add_importer(importer);
+        self
+    }
+
+    /// Adds an importer. If multiple importers accept a file, the first one added is used.
+    pub fn add_importer<I>(&mut self, importer: I)
+    where
+        I: Importer,
+    {
+        self.importers.push(Box::new(importer));
+    }
+
+    /// Bakes all accepted files of the `input` directory into the `output` directory and
+    /// updates the manifest there.
+    ///
+    /// Failing to import single files doesn't abort baking, they are listed in the report.
+    pub fn bake<I, O>(&self, input: I, output: O) -> Result<BakeReport, Error>
+    where
+        I: AsRef<Path>,
+        O: AsRef<Path>,
+    {
+        #[cfg(feature = "profiler")]
+        profile_scope!("bake_assets");
+
+        let input = input.as_ref();
+        let output = output.as_ref();
+        let source: Arc<dyn Source> = Arc::new(Directory::new(input));
+        let old = BakeManifest::load(output)?;
+        let mut manifest = BakeManifest::default();
+        let mut report = BakeReport::default();
+
+        let mut files = Vec::new();
+        collect_files(input, "", &mut files)?;
+        files.sort();
+
+        for path in files {
+            let importer = match self.importers.iter().find(|i| i.accepts(&path)) {
+                Some(importer) => importer,
+                None => continue,
+            };
+
+            let raw = source.load(&path)?;
+            let entry = BakedEntry {
+                hash: content_hash(&raw),
+                importer: importer.name().to_owned(),
+                version: importer.version(),
+                output: format!("{}.bin", path),
+            };
+
+            if old.get(&path) == Some(&entry) && output.join(&entry.output).exists() {
+                debug!("Baked asset {:?} is up to date", path);
+                manifest.entries.insert(path.clone(), entry);
+                report.cached.push(path);
+                continue;
+            }
+
+            debug!("Baking asset {:?} with importer {:?}", path, entry.importer);
+            match importer.bake(&path, source.clone()) {
+                Ok(bytes) => {
+                    let out = output.join(&entry.output);
+                    if let Some(parent) = out.parent() {
+                        fs::create_dir_all(parent)
+                            .with_context(|_| format_err!("Failed to create {:?}", parent))?;
+                    }
+                    fs::write(&out, bytes)
+                        .with_context(|_| format_err!("Failed to write {:?}", out))?;
+                    manifest.entries.insert(path.clone(), entry);
+                    report.baked.push(path);
+                }
+                Err(e) => report.failed.push((path, e)),
+            }
+        }
+
+        fs::create_dir_all(output)
+            .with_context(|_| format_err!("Failed to create {:?}", output))?;
+        manifest.save(output)?;
+
+        Ok(report)
+    }
+}
+
+impl std::fmt::Debug for AssetBaker {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AssetBaker")
+            .field(
+                "importers",
+                &self.importers.iter().map(|i| i.name()).collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
+type DecodeFn<D> = fn(Vec<u8>) -> Result<D, Error>;
+
+struct Decoder {
+    version: u32,
+    decode: Box<dyn Any + Send + Sync>,
+}
+
+/// Output of an `AssetBaker`, used by the `Loader` in place of importing raw files.
+///
+/// Baked data is only used for formats registered with `with_format`, and only if the raw file
+/// still has the hash it was baked from and the format version matches.
+/// Otherwise the raw file is imported as usual.
+pub struct BakedAssets {
+    decoders: FnvHashMap<(&'static str, TypeId), Decoder>,
+    manifest: BakeManifest,
+    source: Arc<dyn Source>,
+}
+
+impl BakedAssets {
+    /// Opens the output directory of an `AssetBaker`.
+    pub fn open<P: AsRef<Path>>(dir: P) -> Result<Self, Error> {
+        Ok(BakedAssets {
+            decoders: Default::default(),
+            manifest: BakeManifest::load(dir.as_ref())?,
+            source: Arc::new(Directory::new(dir.as_ref())),
+        })
+    }
+
+    /// Uses baked data for assets loaded with `format`, if it was baked with the given
+    /// importer version.
+    pub fn with_format<D, F>(mut self, format: &F, version: u32) -> Self
+    where
+        D: DeserializeOwned + Send + Sync + 'static,
+        F: Format<D>,
+    {
+        let decode: DecodeFn<D> = |bytes| BincodeFormat.import_simple(bytes);
+        self.decoders.insert(
+            (format.name(), TypeId::of::<D>()),
+            Decoder {
+                version,
+                decode: Box::new(decode),
+            },
+        );
+        self
+    }
+
+    /// Returns the manifest of the baked assets.
+    pub fn manifest(&self) -> &BakeManifest {
+        &self.manifest
+    }
+
+    /// Loads the baked data for `name`, returning `None` if there is no usable baked data.
+    pub(crate) fn import<D, F>(
+        &self,
+        format: &F,
+        name: &str,
+        source: &Arc<dyn Source>,
+        create_reload: Option<Box<dyn Format<D>>>,
+    ) -> Option<FormatValue<D>>
+    where
+        D: 'static,
+        F: Format<D>,
+    {
+        #[cfg(feature = "profiler")]
+        profile_scope!("import_baked_asset");
+
+        let entry = self.manifest.get(name)?;
+        let decoder = self.decoders.get(&(format.name(), TypeId::of::<D>()))?;
+        if entry.importer != format.name() || entry.version != decoder.version {
+            return None;
+        }
+        let decode = decoder.decode.downcast_ref::<DecodeFn<D>>()?;
+
+        // Errors reading the raw file are reported by the regular import.
+        let (raw, modified) = source.load_with_metadata(name).ok()?;
+        if content_hash(&raw) != entry.hash {
+            debug!("Baked asset {:?} is outdated, importing it again", name);
+            return None;
+        }
+
+        let data = match self.source.load(&entry.output).and_then(decode) {
+            Ok(data) => data,
+            Err(e) => {
+                warn!("Failed to load baked asset {:?}: {}", name, e);
+                return None;
+            }
+        };
+        let reload = create_reload.map(|format| {
+            Box::new(SingleFile::new(
+                format,
+                modified,
+                name.to_owned(),
+                source.clone(),
+            )) as Box<dyn Reload<D>>
+        });
+
+        Some(FormatValue { data, reload })
+    }
+}
+
+impl std::fmt::Debug for BakedAssets {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BakedAssets")
+            .field("manifest", &self.manifest)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs,
+        path::PathBuf,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+    };
+
+    use amethyst_core::ecs::VecStorage;
+    use amethyst_error::Error;
+    use rayon::ThreadPoolBuilder;
+
+    use crate::{
+        Asset, AssetBaker, AssetStorage, BakedAssets, Format, FormatImporter, Handle, Loader,
+        ProcessingState, ProgressCounter,
+    };
+
+    /// Upper cases text files, counting how often it was run.
+    #[derive(Clone, Debug, Default)]
+    struct Upper(Arc<AtomicUsize>);
+
+    impl Format<String> for Upper {
+        fn name(&self) -> &'static str {
+            "Upper"
+        }
+
+        fn import_simple(&self, bytes: Vec<u8>) -> Result<String, Error> {
+            self.0.fetch_add(1, Ordering::Relaxed);
+            Ok(String::from_utf8(bytes)?.to_uppercase())
+        }
+    }
+
+    struct Text(String);
+
+    impl Asset for Text {
+        const NAME: &'static str = "Text";
+        type Data = String;
+        type HandleStorage = VecStorage<Handle<Self>>;
+    }
+
+    fn dirs(test: &str) -> (PathBuf, PathBuf) {
+        let root =
+            std::env::temp_dir().join(format!("amethyst_bake_{}_{}", test, std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("assets/sub")).unwrap();
+        fs::write(root.join("assets/a.txt"), "a").unwrap();
+        fs::write(root.join("assets/sub/b.txt"), "b").unwrap();
+        fs::write(root.join("assets/ignored.dat"), "c").unwrap();
+
+        (root.join("assets"), root.join("baked"))
+    }
+
+    #[test]
+    fn bakes_only_changed_files() {
+        let (assets, baked) = dirs("changed");
+        let format = Upper::default();
+        let baker =
+            AssetBaker::new().with_importer(FormatImporter::new(format.clone(), 1, &["txt"]));
+
+        let report = baker.bake(&assets, &baked).unwrap();
+        assert_eq!(vec!["a.txt", "sub/b.txt"], report.baked);
+        assert!(baked.join("sub/b.txt.bin").exists());
+
+        fs::write(assets.join("a.txt"), "changed").unwrap();
+        let report = baker.bake(&assets, &baked).unwrap();
+        assert_eq!(vec!["a.txt"], report.baked);
+        assert_eq!(vec!["sub/b.txt"], report.cached);
+        assert_eq!(3, format.0.load(Ordering::Relaxed));
+
+        // A new importer version bakes everything again.
+        let baker = AssetBaker::new().with_importer(FormatImporter::new(format, 2, &["txt"]));
+        assert_eq!(2, baker.bake(&assets, &baked).unwrap().baked.len());
+
+        fs::remove_dir_all(assets.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn loader_prefers_up_to_date_baked_assets() {
+        let (assets, baked) = dirs("loader");
+        AssetBaker::new()
+            .with_importer(FormatImporter::new(Upper::default(), 1, &["txt"]))
+            .bake(&assets, &baked)
+            .unwrap();
+        fs::write(assets.join("sub/b.txt"), "outdated").unwrap();
+
+        let pool = Arc::new(ThreadPoolBuilder::new().num_threads(1).build().unwrap());
+        let mut loader = Loader::new(&assets, pool.clone());
+        let format = Upper::default();
+        loader.set_baked(
+            BakedAssets::open(&baked)
+                .unwrap()
+                .with_format::<String, _>(&format, 1),
+        );
+
+        let mut storage = AssetStorage::<Text>::new();
+        let mut progress = ProgressCounter::new();
+        let a = loader.load("a.txt", format.clone(), &mut progress, &storage);
+        let b = loader.load("sub/b.txt", format.clone(), &mut progress, &storage);
+        for _ in 0..500 {
+            storage.process(|s| Ok(ProcessingState::Loaded(Text(s))), 0, &pool, None);
+            if progress.is_complete() {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(2));
+        }
+
+        assert_eq!("A", storage.get(&a).unwrap().0);
+        assert_eq!("OUTDATED", storage.get(&b).unwrap().0);
+        // Only the outdated file was imported again.
+        assert_eq!(1, format.0.load(Ordering::Relaxed));
+
+        fs::remove_dir_all(assets.parent().unwrap()).unwrap();
+    }
+}
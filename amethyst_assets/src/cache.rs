@@ -2,15 +2,19 @@ use std::{borrow::Borrow, hash::Hash};
 
 use derivative::Derivative;
 use fnv::FnvHashMap;
+use uuid::Uuid;
 
 use crate::{Handle, WeakHandle};
 
 /// A simple cache for asset handles of type `A`.
 /// This stores `WeakHandle`, so it doesn't keep the assets alive.
+///
+/// Handles can be cached by an arbitrary key or by the stable id of the asset.
 #[derive(Derivative)]
 #[derivative(Default(bound = ""))]
 pub struct Cache<A> {
     map: FnvHashMap<String, WeakHandle<A>>,
+    uuids: FnvHashMap<Uuid, WeakHandle<A>>,
 }
 
 impl<A> Cache<A>
@@ -36,13 +40,25 @@ where
         self.map.get(key).and_then(WeakHandle::upgrade)
     }
 
+    /// Inserts an asset with a given id and returns the old value (if any).
+    pub fn insert_uuid(&mut self, uuid: Uuid, asset: &Handle<A>) -> Option<WeakHandle<A>> {
+        self.uuids.insert(uuid, asset.downgrade())
+    }
+
+    /// Retrieves an asset handle using a given id.
+    pub fn get_uuid(&self, uuid: &Uuid) -> Option<Handle<A>> {
+        self.uuids.get(uuid).and_then(WeakHandle::upgrade)
+    }
+
     /// Deletes all cached handles which are invalid.
     pub fn clear_dead<F>(&mut self) {
         self.map.retain(|_, h| !h.is_dead());
+        self.uuids.retain(|_, h| !h.is_dead());
     }
 
     /// Clears all values.
     pub fn clear_all(&mut self) {
         self.map.clear();
+        self.uuids.clear();
     }
 }
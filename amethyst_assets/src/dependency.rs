@@ -0,0 +1,256 @@
+//! Tracks which assets depend on which other assets.
+
+use std::any::TypeId;
+
+use fnv::{FnvHashMap, FnvHashSet};
+use parking_lot::RwLock;
+
+use crate::{Asset, Handle};
+
+/// Type erased identifier of a loaded asset, consisting of the asset type and the handle id.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct AssetId {
+    type_id: TypeId,
+    type_name: &'static str,
+    id: u32,
+}
+
+impl AssetId {
+    /// Creates the id of the asset of type `A` with the given handle id.
+    pub fn new<A: Asset>(id: u32) -> Self {
+        AssetId {
+            type_id: TypeId::of::<A>(),
+            type_name: A::NAME,
+            id,
+        }
+    }
+
+    /// Returns the handle id of the asset.
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    /// Returns the `Asset::NAME` of the asset type.
+    pub fn type_name(&self) -> &'static str {
+        self.type_name
+    }
+
+    /// Returns `true` if the asset is of type `A`.
+    pub fn is<A: Asset>(&self) -> bool {
+        self.type_id == TypeId::of::<A>()
+    }
+}
+
+impl<A: Asset> From<&Handle<A>> for AssetId {
+    fn from(handle: &Handle<A>) -> Self {
+        AssetId::new::<A>(handle.id())
+    }
+}
+
+/// Graph of dependencies between assets.
+///
+/// An edge from a dependent to a dependency is recorded by the `Loader` when
+///
+/// * a `Format` reports a dependency through `Format::dependencies`, e.g. the texture of a
+///   sprite sheet.
+/// * an asset is loaded with a `ProgressCounter` created with `ProgressCounter::with_dependent`,
+///   which is what prefabs do when loading their sub assets.
+///
+/// Whenever an asset is hot reloaded, all assets which (transitively) depend on it are reloaded
+/// as well. Edges are removed once an asset is unloaded.
+///
+/// The graph can be queried through `Loader::dependencies`.
+#[derive(Debug, Default)]
+pub struct DependencyGraph {
+    inner: RwLock<Inner>,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    dependencies: FnvHashMap<AssetId, FnvHashSet<AssetId>>,
+    dependents: FnvHashMap<AssetId, FnvHashSet<AssetId>>,
+    stale: FnvHashSet<AssetId>,
+}
+
+impl DependencyGraph {
+    /// Creates a new, empty dependency graph.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Records that `dependent` depends on `dependency`.
+    pub fn add_dependency(&self, dependent: AssetId, dependency: AssetId) {
+        let mut inner = self.inner.write();
+        inner
+            .dependencies
+            .entry(dependent)
+            .or_default()
+            .insert(dependency);
+        inner
+            .dependents
+            .entry(dependency)
+            .or_default()
+            .insert(dependent);
+    }
+
+    /// Returns the assets the given asset directly depends on.
+    pub fn dependencies(&self, asset: AssetId) -> Vec<AssetId> {
+        self.inner
+            .read()
+            .dependencies
+            .get(&asset)
+            .map(|set| set.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Returns the assets which directly depend on the given asset.
+    pub fn dependents(&self, asset: AssetId) -> Vec<AssetId> {
+        self.inner
+            .read()
+            .dependents
+            .get(&asset)
+            .map(|set| set.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Returns all assets which directly or indirectly depend on the given asset.
+    pub fn transitive_dependents(&self, asset: AssetId) -> Vec<AssetId> {
+        let inner = self.inner.read();
+        let mut found = FnvHashSet::default();
+        let mut pending = vec![asset];
+        while let Some(current) = pending.pop() {
+            if let Some(dependents) = inner.dependents.get(&current) {
+                for &dependent in dependents {
+                    if dependent != asset && found.insert(dependent) {
+                        pending.push(dependent);
+                    }
+                }
+            }
+        }
+
+        found.into_iter().collect()
+    }
+
+    /// Removes the given asset and all of its edges from the graph.
+    pub fn remove_asset(&self, asset: AssetId) {
+        let mut inner = self.inner.write();
+        if let Some(dependencies) = inner.dependencies.remove(&asset) {
+            for dependency in dependencies {
+                if let Some(dependents) = inner.dependents.get_mut(&dependency) {
+                    dependents.remove(&asset);
+                }
+            }
+        }
+        if let Some(dependents) = inner.dependents.remove(&asset) {
+            for dependent in dependents {
+                if let Some(dependencies) = inner.dependencies.get_mut(&dependent) {
+                    dependencies.remove(&asset);
+                }
+            }
+        }
+        inner.stale.remove(&asset);
+    }
+
+    /// Marks all dependents of a reloaded asset as needing a reload.
+    pub(crate) fn mark_reloaded(&self, asset: AssetId) {
+        let dependents = self.transitive_dependents(asset);
+        self.inner.write().stale.extend(dependents);
+    }
+
+    /// Returns and forgets the handle ids of all assets of type `A` needing a reload
+    /// because one of their dependencies was reloaded.
+    pub(crate) fn take_stale<A: Asset>(&self) -> Vec<u32> {
+        if self.inner.read().stale.iter().all(|asset| !asset.is::<A>()) {
+            return Vec::new();
+        }
+
+        let mut inner = self.inner.write();
+        let stale = inner
+            .stale
+            .iter()
+            .filter(|asset| asset.is::<A>())
+            .cloned()
+            .collect::<Vec<_>>();
+        for asset in &stale {
+            inner.stale.remove(asset);
+        }
+
+        stale.into_iter().map(|asset| asset.id()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use amethyst_core::ecs::VecStorage;
+
+    use crate::{Asset, Handle};
+
+    use super::{AssetId, DependencyGraph};
+
+    struct Texture;
+    struct Material;
+    struct Prefab;
+
+    macro_rules! test_asset {
+        ($($ty:ident),*) => {
+            $(
+                impl Asset for $ty {
+                    const NAME: &'static str = stringify!($ty);
+                    type Data = ();
+                    type HandleStorage = VecStorage<Handle<Self>>;
+                }
+            )*
+        };
+    }
+
+    test_asset!(Texture, Material, Prefab);
+
+    fn graph() -> DependencyGraph {
+        let graph = DependencyGraph::new();
+        graph.add_dependency(AssetId::new::<Material>(0), AssetId::new::<Texture>(0));
+        graph.add_dependency(AssetId::new::<Prefab>(0), AssetId::new::<Material>(0));
+        graph.add_dependency(AssetId::new::<Prefab>(1), AssetId::new::<Texture>(0));
+        graph
+    }
+
+    #[test]
+    fn queries_direct_and_transitive_dependents() {
+        let graph = graph();
+        let texture = AssetId::new::<Texture>(0);
+
+        assert_eq!(
+            vec![texture],
+            graph.dependencies(AssetId::new::<Material>(0))
+        );
+        assert_eq!(2, graph.dependents(texture).len());
+
+        let transitive = graph.transitive_dependents(texture);
+        assert_eq!(3, transitive.len());
+        assert!(transitive.contains(&AssetId::new::<Prefab>(0)));
+    }
+
+    #[test]
+    fn removing_asset_removes_edges() {
+        let graph = graph();
+        graph.remove_asset(AssetId::new::<Material>(0));
+
+        assert_eq!(
+            vec![AssetId::new::<Prefab>(1)],
+            graph.dependents(AssetId::new::<Texture>(0))
+        );
+        assert!(graph.dependencies(AssetId::new::<Prefab>(0)).is_empty());
+    }
+
+    #[test]
+    fn reload_marks_dependents_stale_per_type() {
+        let graph = graph();
+        graph.mark_reloaded(AssetId::new::<Texture>(0));
+
+        assert_eq!(vec![0], graph.take_stale::<Material>());
+        assert!(graph.take_stale::<Material>().is_empty());
+        let mut prefabs = graph.take_stale::<Prefab>();
+        prefabs.sort();
+        assert_eq!(vec![0, 1], prefabs);
+        assert!(graph.take_stale::<Texture>().is_empty());
+    }
+}
@@ -280,7 +280,7 @@ mod tests {
 
     use super::*;
     use crate as amethyst_assets;
-    use crate::Format;
+    use crate::{BincodeFormat, Format};
     use amethyst_error::Error;
 
     #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -295,6 +295,7 @@ mod tests {
     #[derive(Debug, Clone, serde::Serialize, serde::Deserialize, PartialEq)]
     struct TestFormat(String);
     register_format!("FOO", TestFormat as TestData);
+    register_format!("Bincode", BincodeFormat as TestData);
 
     impl Format<TestData> for TestFormat {
         fn name(&self) -> &'static str {
@@ -321,4 +322,34 @@ mod tests {
             deserialized_prefab.test.import_simple(Vec::new()).unwrap()
         );
     }
+
+    #[test]
+    fn test_format_serialize_binary() {
+        let prefabs = vec![
+            TestPrefab {
+                test: Box::new(TestFormat("test string".to_owned())),
+            },
+            TestPrefab {
+                test: Box::new(BincodeFormat),
+            },
+        ];
+
+        // Tagged formats also survive a round trip through a non self-describing format.
+        let serialized = BincodeFormat::serialize(&prefabs).unwrap();
+        let deserialized: Vec<TestPrefab> = BincodeFormat.import_simple(serialized).unwrap();
+        assert_eq!(
+            TestData("test string".to_owned()),
+            deserialized[0].test.import_simple(Vec::new()).unwrap()
+        );
+
+        let data = TestData("binary".to_owned());
+        assert_eq!("Bincode", deserialized[1].test.name());
+        assert_eq!(
+            data,
+            deserialized[1]
+                .test
+                .import_simple(BincodeFormat::serialize(&data).unwrap())
+                .unwrap()
+        );
+    }
 }
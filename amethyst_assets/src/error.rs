@@ -10,6 +10,8 @@ pub enum Error {
     Format(&'static str),
     #[error(display = "Asset was loaded but no handle to it was saved.")]
     UnusedHandle,
+    #[error(display = "No asset with id {} in the asset manifest", _0)]
+    UnknownUuid(uuid::Uuid),
     #[error(display = "Some error has occurred")]
     #[doc(hidden)]
     __Nonexhaustive,
@@ -1,6 +1,6 @@
 use crate::Format;
 use amethyst_error::{format_err, Error, ResultExt};
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
 /// Format for loading from RON files. Mostly useful for prefabs.
 /// This type cannot be used for tagged deserialization.
@@ -32,6 +32,65 @@ where
     }
 }
 
+/// Format for loading from compact binary files, encoded with `bincode`.
+///
+/// Binary files load a lot faster than text files, which makes this format a good fit for big
+/// prefabs and level data. They are usually produced from RON files at build time:
+///
+/// ```rust,ignore
+/// let ron = std::fs::read("assets/level.ron")?;
+/// let binary = BincodeFormat::convert_ron::<Prefab<MyPrefabData>>(&ron)?;
+/// std::fs::write("assets/level.bin", binary)?;
+///
+/// // Later on, the prefab loads just like the RON one.
+/// loader.load("level.bin", BincodeFormat, ());
+/// ```
+///
+/// Like other non self-describing formats, bincode can't encode types using
+/// `#[serde(untagged)]` or other features which rely on `deserialize_any`.
+///
+/// To use this format inside of prefabs, register it for the asset data type:
+/// ```rust,ignore
+/// amethyst_assets::register_format!("Bincode", BincodeFormat as MyData);
+/// ```
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct BincodeFormat;
+
+impl BincodeFormat {
+    /// Encodes the given data into the representation read by this format.
+    pub fn serialize<D>(data: &D) -> Result<Vec<u8>, Error>
+    where
+        D: Serialize,
+    {
+        bincode::serialize(data).with_context(|_| format_err!("Failed serializing Bincode data"))
+    }
+
+    /// Converts the contents of a RON file into the representation read by this format.
+    ///
+    /// The RON file is parsed as `D`, so it has to be the data type the binary file is
+    /// loaded as later on, e.g. `Prefab<MyPrefabData>`.
+    pub fn convert_ron<D>(ron: &[u8]) -> Result<Vec<u8>, Error>
+    where
+        D: Serialize + DeserializeOwned + Send + Sync + 'static,
+    {
+        let data: D = RonFormat.import_simple(ron.to_vec())?;
+        Self::serialize(&data)
+    }
+}
+
+impl<D> Format<D> for BincodeFormat
+where
+    D: for<'a> Deserialize<'a> + Send + Sync + 'static,
+{
+    fn name(&self) -> &'static str {
+        "Bincode"
+    }
+
+    fn import_simple(&self, bytes: Vec<u8>) -> Result<D, Error> {
+        bincode::deserialize(&bytes).with_context(|_| format_err!("Failed parsing Bincode file"))
+    }
+}
+
 /// Format for loading from JSON files. Mostly useful for prefabs.
 /// This type can only be used as manually specified to the loader.
 #[derive(Clone, Copy, Debug, Serialize, Deserialize)]
@@ -57,3 +116,47 @@ where
         Ok(val)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use serde::{Deserialize, Serialize};
+
+    use crate::{Format, Prefab};
+
+    use super::{BincodeFormat, RonFormat};
+
+    #[derive(Debug, Default, Deserialize, PartialEq, Serialize)]
+    struct Data {
+        name: String,
+        position: Option<[f32; 3]>,
+    }
+
+    #[test]
+    fn binary_prefab_loads_like_ron() {
+        let ron = br#"
+            Prefab(
+                entities: [
+                    PrefabEntity(data: Some(Data(name: "root", position: Some((1.0, 2.0, 3.0))))),
+                    PrefabEntity(parent: Some(0), data: Some(Data(name: "child", position: None))),
+                    PrefabEntity(parent: Some(0)),
+                ],
+            )
+        "#;
+        let binary = BincodeFormat::convert_ron::<Prefab<Data>>(ron).unwrap();
+        assert!(binary.len() < ron.len());
+
+        let from_ron: Prefab<Data> = RonFormat.import_simple(ron.to_vec()).unwrap();
+        let from_binary: Prefab<Data> = BincodeFormat.import_simple(binary).unwrap();
+        assert_eq!(from_ron.len(), from_binary.len());
+        for (ron, binary) in from_ron.entities().zip(from_binary.entities()) {
+            assert_eq!(ron.parent(), binary.parent());
+            assert_eq!(ron.data(), binary.data());
+        }
+    }
+
+    #[test]
+    fn invalid_binary_fails() {
+        let result: Result<Data, _> = BincodeFormat.import_simple(vec![255; 3]);
+        assert!(result.is_err());
+    }
+}
@@ -13,32 +13,48 @@
 pub use crate::formats::JsonFormat;
 pub use crate::{
     asset::{Asset, Format, FormatValue, ProcessableAsset, SerializableFormat},
+    bake::{
+        content_hash, AssetBaker, BakeManifest, BakeReport, BakedAssets, BakedEntry,
+        FormatImporter, Importer, BAKE_MANIFEST,
+    },
     cache::Cache,
+    dependency::{AssetId, DependencyGraph},
     dyn_format::FormatRegisteredData,
-    formats::RonFormat,
+    formats::{BincodeFormat, RonFormat},
     helper::AssetLoaderSystemData,
     loader::Loader,
+    manifest::{AssetManifest, AssetMeta, META_EXTENSION},
     prefab::{
-        AssetPrefab, Prefab, PrefabData, PrefabLoader, PrefabLoaderSystem, PrefabLoaderSystemDesc,
+        AssetPrefab, ExportPrefabData, Prefab, PrefabData, PrefabExporter, PrefabLoader,
+        PrefabLoaderSystem, PrefabLoaderSystemDesc,
+    },
+    progress::{
+        AssetErrorMeta, AssetProgress, Completion, LoadState, Progress, ProgressCounter,
+        TrackedAsset, Tracker,
     },
-    progress::{Completion, Progress, ProgressCounter, Tracker},
     reload::{HotReloadBundle, HotReloadStrategy, HotReloadSystem, Reload, SingleFile},
-    source::{Directory, Source},
+    retention::{CachePolicy, CacheStats},
+    source::{Directory, LayeredSource, PackBuilder, PackSource, Source},
     storage::{AssetStorage, Handle, ProcessingState, Processor, WeakHandle},
 };
 
 pub use rayon::ThreadPool;
+pub use uuid::Uuid;
 
 mod asset;
+mod bake;
 mod cache;
+mod dependency;
 mod dyn_format;
 mod error;
 mod formats;
 mod helper;
 mod loader;
+mod manifest;
 mod prefab;
 mod progress;
 mod reload;
+mod retention;
 mod source;
 mod storage;
 
@@ -1,8 +1,20 @@
-use std::{borrow::Borrow, hash::Hash, path::PathBuf, sync::Arc};
+use std::{
+    borrow::Borrow,
+    cmp::Ordering,
+    collections::BinaryHeap,
+    hash::Hash,
+    path::PathBuf,
+    sync::{
+        atomic::{self, AtomicU64},
+        Arc,
+    },
+};
 
 use fnv::FnvHashMap;
 use log::debug;
+use parking_lot::Mutex;
 use rayon::ThreadPool;
+use uuid::Uuid;
 
 use amethyst_error::ResultExt;
 #[cfg(feature = "profiler")]
@@ -10,17 +22,58 @@ use thread_profiler::profile_scope;
 
 use crate::{
     error::Error,
+    progress::{LoadState, TrackedAsset, Tracker},
     storage::{AssetStorage, Handle, Processed},
-    Asset, Directory, Format, FormatValue, Progress, Source,
+    Asset, AssetId, AssetManifest, BakedAssets, DependencyGraph, Directory, Format, FormatValue,
+    Progress, Source,
 };
 
 /// The asset loader, holding the sources and a reference to the `ThreadPool`.
+///
+/// Load requests are queued by priority; whenever a worker thread of the pool becomes
+/// available, it imports the queued asset with the highest priority. Requests whose handles
+/// have all been dropped before a worker picks them up are cancelled.
 pub struct Loader {
+    baked: Option<Arc<BakedAssets>>,
+    dependencies: Arc<DependencyGraph>,
     hot_reload: bool,
+    jobs: Arc<Mutex<BinaryHeap<Job>>>,
+    manifest: AssetManifest,
+    next_job: AtomicU64,
     pool: Arc<ThreadPool>,
     sources: FnvHashMap<String, Arc<dyn Source>>,
 }
 
+/// A queued load request.
+struct Job {
+    priority: i32,
+    sequence: u64,
+    run: Box<dyn FnOnce() + Send>,
+}
+
+impl Ord for Job {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Higher priority first, requests of equal priority in FIFO order.
+        self.priority
+            .cmp(&other.priority)
+            .then_with(|| other.sequence.cmp(&self.sequence))
+    }
+}
+
+impl PartialOrd for Job {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Job {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Job {}
+
 impl Loader {
     /// Creates a new asset loader, initializing the directory store with the
     /// given path.
@@ -37,7 +90,12 @@ impl Loader {
         S: Source,
     {
         let mut loader = Loader {
+            baked: None,
+            dependencies: Default::default(),
             hot_reload: true,
+            jobs: Default::default(),
+            manifest: Default::default(),
+            next_job: AtomicU64::new(0),
             pool,
             sources: Default::default(),
         };
@@ -73,6 +131,27 @@ impl Loader {
         self.hot_reload = value;
     }
 
+    /// Makes the loader use the output of an `AssetBaker` for assets loaded from the default
+    /// source, instead of importing raw files which didn't change since they were baked.
+    pub fn set_baked(&mut self, baked: BakedAssets) {
+        self.baked = Some(Arc::new(baked));
+    }
+
+    /// Sets the manifest used to look up the paths of assets loaded by id.
+    pub fn set_manifest(&mut self, manifest: AssetManifest) {
+        self.manifest = manifest;
+    }
+
+    /// Returns the manifest used to look up the paths of assets loaded by id.
+    pub fn manifest(&self) -> &AssetManifest {
+        &self.manifest
+    }
+
+    /// Returns the graph of dependencies between the assets loaded by this `Loader`.
+    pub fn dependencies(&self) -> &DependencyGraph {
+        &self.dependencies
+    }
+
     /// Loads an asset with a given format from the default (directory) source.
     /// If you want to load from a custom source instead, use `load_from`.
     ///
@@ -95,6 +174,66 @@ impl Loader {
         self.load_from::<A, F, _, _, _>(name, format, "", progress, storage)
     }
 
+    /// Loads the asset with the given id from the default (directory) source, looking up its
+    /// path in the `AssetManifest`.
+    ///
+    /// If the manifest doesn't know the id, loading fails and the failure is reported to
+    /// `progress`.
+    pub fn load_uuid<A, F, P>(
+        &self,
+        uuid: Uuid,
+        format: F,
+        mut progress: P,
+        storage: &AssetStorage<A>,
+    ) -> Handle<A>
+    where
+        A: Asset,
+        F: Format<A::Data>,
+        P: Progress,
+    {
+        match self.manifest.path(uuid) {
+            Some(path) => self.load(path, format, progress, storage),
+            None => {
+                let handle = storage.allocate();
+                progress.add_assets(1);
+                let tracker = progress.create_asset_tracker(TrackedAsset {
+                    handle_id: handle.id(),
+                    asset_type_name: A::NAME,
+                    name: uuid.to_string(),
+                    format_name: Some(format.name()),
+                    source: Some(String::new()),
+                });
+                Box::new(tracker).fail(
+                    handle.id(),
+                    A::NAME,
+                    uuid.to_string(),
+                    Error::UnknownUuid(uuid).into(),
+                );
+                handle
+            }
+        }
+    }
+
+    /// Loads an asset with a given format and priority from the default (directory) source.
+    ///
+    /// See `load_from_prioritized` for more information.
+    pub fn load_prioritized<A, F, N, P>(
+        &self,
+        name: N,
+        format: F,
+        priority: i32,
+        progress: P,
+        storage: &AssetStorage<A>,
+    ) -> Handle<A>
+    where
+        A: Asset,
+        F: Format<A::Data>,
+        N: Into<String>,
+        P: Progress,
+    {
+        self.load_from_prioritized::<A, F, _, _, _>(name, format, "", priority, progress, storage)
+    }
+
     /// Loads an asset with a given id and format from a custom source.
     /// The actual work is done in a worker thread, thus this method immediately returns a handle.
     ///
@@ -113,6 +252,36 @@ impl Loader {
         name: N,
         format: F,
         source: &S,
+        progress: P,
+        storage: &AssetStorage<A>,
+    ) -> Handle<A>
+    where
+        A: Asset,
+        F: Format<A::Data>,
+        N: Into<String>,
+        P: Progress,
+        S: AsRef<str> + Eq + Hash + ?Sized,
+        String: Borrow<S>,
+    {
+        self.load_from_prioritized(name, format, source, 0, progress, storage)
+    }
+
+    /// Loads an asset with a given id, format and priority from a custom source.
+    ///
+    /// Queued requests with a higher priority are imported first, `load` and `load_from` use
+    /// a priority of `0`. For streaming, the priority could e.g. be derived from the distance
+    /// to the camera.
+    ///
+    /// If all handles to the asset are dropped before the import started, the request is
+    /// cancelled and the tracker is notified with `Tracker::cancelled`.
+    ///
+    /// See `load_from` for the other parameters.
+    pub fn load_from_prioritized<A, F, N, P, S>(
+        &self,
+        name: N,
+        format: F,
+        source: &S,
+        priority: i32,
         mut progress: P,
         storage: &AssetStorage<A>,
     ) -> Handle<A>
@@ -126,8 +295,6 @@ impl Loader {
     {
         #[cfg(feature = "profiler")]
         profile_scope!("load_asset_from");
-        use crate::progress::Tracker;
-
         let name = name.into();
         let source = source.as_ref();
 
@@ -137,7 +304,33 @@ impl Loader {
             other => other,
         };
 
+        let key = (source.to_owned(), format_name, name.clone());
+        if let Some(handle) = storage.revive(&key) {
+            debug!(
+                "{:?}: Reusing retained asset {:?} with format {:?} from source {:?} (handle id: {:?})",
+                A::NAME,
+                name,
+                format_name,
+                source_name,
+                handle,
+            );
+
+            self.record_dependent(&handle, &progress, storage);
+            progress.add_assets(1);
+            let tracker = progress.create_asset_tracker(TrackedAsset {
+                handle_id: handle.id(),
+                asset_type_name: A::NAME,
+                name,
+                format_name: Some(format_name),
+                source: Some(source.to_owned()),
+            });
+            Box::new(tracker).success();
+
+            return handle;
+        }
+
         let handle = storage.allocate();
+        storage.register_key(&handle, key);
 
         debug!(
             "{:?}: Loading asset {:?} with format {:?} from source {:?} (handle id: {:?})",
@@ -148,9 +341,22 @@ impl Loader {
             handle,
         );
 
+        self.record_dependent(&handle, &progress, storage);
+        for dependency in format.dependencies() {
+            self.dependencies
+                .add_dependency(AssetId::from(&handle), dependency);
+        }
+
         progress.add_assets(1);
-        let tracker = progress.create_tracker();
+        let tracker = progress.create_asset_tracker(TrackedAsset {
+            handle_id: handle.id(),
+            asset_type_name: A::NAME,
+            name: name.clone(),
+            format_name: Some(format_name),
+            source: Some(source.to_owned()),
+        });
 
+        let baked = self.baked.clone().filter(|_| source.is_empty());
         let source = self.source(source);
         let handle_clone = handle.clone();
         let processed = storage.processed.clone();
@@ -161,14 +367,29 @@ impl Loader {
             None
         };
 
-        let cl = move || {
-            #[cfg(feature = "profiler")]
-            profile_scope!("load_asset_from_worker");
-            let data = format
-                .import(name.clone(), source, hot_reload)
-                .with_context(|_| Error::Format(format_name));
-            let tracker = Box::new(tracker) as Box<dyn Tracker>;
+        let load = move |handle: Handle<A>, name: String, tracker: Box<dyn Tracker>| {
+            tracker.state_changed(LoadState::Reading);
+            let tracker = Arc::new(Mutex::new(Some(tracker)));
+            let source: Arc<dyn Source> = Arc::new(TrackedSource {
+                inner: source,
+                tracker: tracker.clone(),
+            });
+
+            let baked = baked
+                .as_ref()
+                .and_then(|baked| baked.import(&format, &name, &source, hot_reload.clone()));
+            let data = match baked {
+                Some(value) => Ok(value),
+                None => format
+                    .import(name.clone(), source, hot_reload)
+                    .with_context(|_| Error::Format(format_name)),
+            };
 
+            // The source may be kept around for hot reloading, which isn't tracked.
+            let tracker = tracker.lock().take().unwrap();
+            if data.is_ok() {
+                tracker.state_changed(LoadState::Processing);
+            }
             processed.push(Processed::NewAsset {
                 data,
                 handle,
@@ -176,7 +397,26 @@ impl Loader {
                 tracker,
             });
         };
-        self.pool.spawn(cl);
+
+        let cancelled = storage.processed.clone();
+        let cl = move || {
+            #[cfg(feature = "profiler")]
+            profile_scope!("load_asset_from_worker");
+            let tracker = Box::new(tracker) as Box<dyn Tracker>;
+            if handle.is_unique() {
+                // A `WeakHandle` may still be upgraded, so the storage makes the final decision
+                // and resumes the load if the handle was revived.
+                cancelled.push(Processed::Cancelled {
+                    handle,
+                    name,
+                    tracker,
+                    resume: Box::new(load),
+                });
+                return;
+            }
+            load(handle, name, tracker);
+        };
+        self.spawn(priority, cl);
 
         handle_clone
     }
@@ -192,10 +432,12 @@ impl Loader {
         A: Asset,
         P: Progress,
     {
+        let handle = storage.allocate();
+        self.record_dependent(&handle, &progress, storage);
         progress.add_assets(1);
-        let tracker = progress.create_tracker();
+        let tracker = progress.create_asset_tracker(TrackedAsset::data::<A>(&handle));
+        tracker.state_changed(LoadState::Processing);
         let tracker = Box::new(tracker);
-        let handle = storage.allocate();
         storage.processed.push(Processed::NewAsset {
             data: Ok(FormatValue::data(data)),
             handle: handle.clone(),
@@ -218,17 +460,20 @@ impl Loader {
         P: Progress,
         F: FnOnce() -> A::Data + Send + Sync + 'static,
     {
+        let handle = storage.allocate();
+        self.record_dependent(&handle, &progress, storage);
         progress.add_assets(1);
-        let tracker = progress.create_tracker();
+        let tracker = progress.create_asset_tracker(TrackedAsset::data::<A>(&handle));
         let tracker = Box::new(tracker);
-        let handle = storage.allocate();
         let processed = storage.processed.clone();
 
-        self.pool.spawn({
+        self.spawn(0, {
             let handle = handle.clone();
             move || {
+                let data = data();
+                tracker.state_changed(LoadState::Processing);
                 processed.push(Processed::NewAsset {
-                    data: Ok(FormatValue::data(data())),
+                    data: Ok(FormatValue::data(data)),
                     handle: handle.clone(),
                     name: "<Data>".into(),
                     tracker,
@@ -239,6 +484,48 @@ impl Loader {
         handle
     }
 
+    /// Collects the paths reported as changed by all sources which support change notifications.
+    pub(crate) fn poll_changes(&self) -> Vec<String> {
+        self.sources
+            .values()
+            .filter_map(|source| source.poll_changes())
+            .flatten()
+            .collect()
+    }
+
+    fn record_dependent<A, P>(&self, handle: &Handle<A>, progress: &P, storage: &AssetStorage<A>)
+    where
+        A: Asset,
+        P: Progress,
+    {
+        storage.set_dependency_graph(&self.dependencies);
+        if let Some(dependent) = progress.dependent() {
+            self.dependencies
+                .add_dependency(dependent, AssetId::from(handle));
+        }
+    }
+
+    fn spawn<F>(&self, priority: i32, job: F)
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        let sequence = self.next_job.fetch_add(1, atomic::Ordering::Relaxed);
+        self.jobs.lock().push(Job {
+            priority,
+            sequence,
+            run: Box::new(job),
+        });
+
+        // Every spawned task runs exactly one job, but not necessarily the one queued above.
+        let jobs = self.jobs.clone();
+        self.pool.spawn(move || {
+            let job = jobs.lock().pop();
+            if let Some(job) = job {
+                (job.run)();
+            }
+        });
+    }
+
     fn source(&self, source: &str) -> Arc<dyn Source> {
         self.sources
             .get(source)
@@ -246,3 +533,288 @@ impl Loader {
             .clone()
     }
 }
+
+/// Source of an asset being imported, reporting reads to the asset's tracker.
+struct TrackedSource {
+    inner: Arc<dyn Source>,
+    tracker: Arc<Mutex<Option<Box<dyn Tracker>>>>,
+}
+
+impl TrackedSource {
+    fn report(&self, bytes: usize) {
+        if let Some(ref tracker) = *self.tracker.lock() {
+            tracker.bytes_read(bytes);
+            tracker.state_changed(LoadState::Importing);
+        }
+    }
+}
+
+impl Source for TrackedSource {
+    fn modified(&self, path: &str) -> Result<u64, amethyst_error::Error> {
+        self.inner.modified(path)
+    }
+
+    fn load(&self, path: &str) -> Result<Vec<u8>, amethyst_error::Error> {
+        let bytes = self.inner.load(path)?;
+        self.report(bytes.len());
+        Ok(bytes)
+    }
+
+    fn load_with_metadata(&self, path: &str) -> Result<(Vec<u8>, u64), amethyst_error::Error> {
+        let (bytes, modified) = self.inner.load_with_metadata(path)?;
+        self.report(bytes.len());
+        Ok((bytes, modified))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{mpsc::channel, Arc},
+        thread,
+        time::Duration,
+    };
+
+    use parking_lot::Mutex;
+    use rayon::{ThreadPool, ThreadPoolBuilder};
+
+    use amethyst_core::ecs::VecStorage;
+    use amethyst_error::Error;
+
+    use crate::{
+        Asset, AssetStorage, CachePolicy, Format, Handle, LoadState, Loader, ProcessableAsset,
+        ProgressCounter, Source, Uuid,
+    };
+
+    #[derive(Clone, Debug)]
+    struct Text(String);
+
+    impl Asset for Text {
+        const NAME: &'static str = "Text";
+        type Data = Self;
+        type HandleStorage = VecStorage<Handle<Self>>;
+
+        fn size_hint(&self) -> usize {
+            self.0.len()
+        }
+    }
+
+    /// Records the order assets are imported in.
+    #[derive(Clone, Debug, Default)]
+    struct RecordingFormat(Arc<Mutex<Vec<String>>>);
+
+    impl Format<Text> for RecordingFormat {
+        fn name(&self) -> &'static str {
+            "Recording"
+        }
+
+        fn import_simple(&self, bytes: Vec<u8>) -> Result<Text, Error> {
+            let text = String::from_utf8(bytes)?;
+            self.0.lock().push(text.clone());
+            Ok(Text(text))
+        }
+    }
+
+    struct Names;
+
+    impl Source for Names {
+        fn modified(&self, _: &str) -> Result<u64, Error> {
+            Ok(0)
+        }
+
+        fn load(&self, path: &str) -> Result<Vec<u8>, Error> {
+            Ok(path.as_bytes().to_vec())
+        }
+    }
+
+    /// Returns a loader with a single worker thread, which is blocked until the returned
+    /// closure is called.
+    fn blocked_loader() -> (Loader, Arc<ThreadPool>, impl FnOnce()) {
+        let pool = Arc::new(ThreadPoolBuilder::new().num_threads(1).build().unwrap());
+        let (tx, rx) = channel::<()>();
+        pool.spawn(move || {
+            rx.recv().unwrap();
+        });
+        let loader = Loader::with_default_source(Names, pool.clone());
+
+        (loader, pool, move || tx.send(()).unwrap())
+    }
+
+    fn process_until(storage: &mut AssetStorage<Text>, pool: &ThreadPool, done: impl Fn() -> bool) {
+        for _ in 0..500 {
+            storage.process(Text::process, 0, pool, None);
+            if done() {
+                return;
+            }
+            thread::sleep(Duration::from_millis(2));
+        }
+        panic!("Assets did not finish loading");
+    }
+
+    #[test]
+    fn loads_higher_priorities_first() {
+        let (loader, pool, release) = blocked_loader();
+        let mut storage = AssetStorage::<Text>::new();
+        let format = RecordingFormat::default();
+        let mut progress = ProgressCounter::new();
+
+        let _handles = [
+            loader.load_prioritized("low", format.clone(), -1, &mut progress, &storage),
+            loader.load("default", format.clone(), &mut progress, &storage),
+            loader.load_prioritized("high", format.clone(), 10, &mut progress, &storage),
+            loader.load("default2", format.clone(), &mut progress, &storage),
+        ];
+        release();
+        process_until(&mut storage, &pool, || progress.num_loading() == 0);
+
+        assert_eq!(vec!["high", "default", "default2", "low"], *format.0.lock());
+        assert!(progress.is_complete());
+    }
+
+    #[test]
+    fn cancels_loads_without_handles() {
+        let (loader, pool, release) = blocked_loader();
+        let mut storage = AssetStorage::<Text>::new();
+        let format = RecordingFormat::default();
+        let mut progress = ProgressCounter::new();
+
+        let kept = loader.load("kept", format.clone(), &mut progress, &storage);
+        drop(loader.load("dropped", format.clone(), &mut progress, &storage));
+        release();
+        process_until(&mut storage, &pool, || progress.num_loading() == 0);
+
+        assert_eq!(vec!["kept"], *format.0.lock());
+        assert_eq!(1, progress.num_cancelled());
+        assert_eq!(0, progress.num_failed());
+        assert!(progress.is_complete());
+        assert_eq!(LoadState::Cancelled, progress.assets()[1].state());
+        assert_eq!("kept", storage.get(&kept).unwrap().0);
+    }
+
+    #[test]
+    fn resumes_cancelled_loads_of_revived_handles() {
+        let (loader, pool, release) = blocked_loader();
+        let mut storage = AssetStorage::<Text>::new();
+        let format = RecordingFormat::default();
+        let mut progress = ProgressCounter::new();
+
+        let weak = loader
+            .load("revived", format.clone(), &mut progress, &storage)
+            .downgrade();
+        release();
+        while storage.processed.is_empty() {
+            thread::sleep(Duration::from_millis(2));
+        }
+        // Revive the handle after the worker decided to cancel, like a `Cache` could.
+        let handle = weak.upgrade().unwrap();
+        process_until(&mut storage, &pool, || progress.num_loading() == 0);
+
+        assert_eq!(0, progress.num_cancelled());
+        assert!(progress.is_complete());
+        assert_eq!("revived", storage.get(&handle).unwrap().0);
+    }
+
+    #[test]
+    fn process_budget_limits_finalized_assets() {
+        let pool = Arc::new(ThreadPoolBuilder::new().num_threads(1).build().unwrap());
+        let loader = Loader::with_default_source(Names, pool.clone());
+        let mut storage = AssetStorage::<Text>::new();
+        storage.set_process_budget(Some(2));
+
+        let handles = (0..3)
+            .map(|i| loader.load_from_data(Text(i.to_string()), (), &storage))
+            .collect::<Vec<_>>();
+
+        storage.process(Text::process, 0, &pool, None);
+        assert_eq!(2, handles.iter().filter(|h| storage.contains(h)).count());
+        storage.process(Text::process, 1, &pool, None);
+        assert_eq!(3, handles.iter().filter(|h| storage.contains(h)).count());
+    }
+
+    #[test]
+    fn retains_dropped_assets_within_budget() {
+        let pool = Arc::new(ThreadPoolBuilder::new().num_threads(1).build().unwrap());
+        let loader = Loader::with_default_source(Names, pool.clone());
+        let mut storage = AssetStorage::<Text>::new();
+        storage.set_cache_policy(Some(CachePolicy::lru(8)));
+        let format = RecordingFormat::default();
+        let mut progress = ProgressCounter::new();
+
+        let a = loader.load("aaaa", format.clone(), &mut progress, &storage);
+        let b = loader.load("bbbb", format.clone(), &mut progress, &storage);
+        process_until(&mut storage, &pool, || progress.num_loading() == 0);
+        assert_eq!(8, storage.cache_stats().bytes);
+
+        let a_id = a.id();
+        drop(a);
+        drop(b);
+        storage.process(Text::process, 0, &pool, None);
+        let stats = storage.cache_stats();
+        assert_eq!(
+            (2, 8, 8),
+            (stats.retained, stats.retained_bytes, stats.bytes)
+        );
+
+        // Retained assets are returned right away without loading them again.
+        let a = loader.load(
+            "aaaa",
+            format.clone(),
+            &mut ProgressCounter::new(),
+            &storage,
+        );
+        assert_eq!(a_id, a.id());
+        assert_eq!("aaaa", storage.get(&a).unwrap().0);
+        assert_eq!(1, storage.cache_stats().hits);
+
+        // Going over budget evicts the least recently dropped asset, which is "bbbb".
+        drop(a);
+        let c = loader.load("cc", format.clone(), &mut progress, &storage);
+        process_until(&mut storage, &pool, || progress.num_loading() == 0);
+        drop(c);
+        storage.process(Text::process, 0, &pool, None);
+        let stats = storage.cache_stats();
+        assert_eq!(
+            (2, 6, 1),
+            (stats.retained, stats.retained_bytes, stats.evictions)
+        );
+        assert_eq!(6, stats.bytes);
+
+        let _b = loader.load("bbbb", format.clone(), &mut progress, &storage);
+        process_until(&mut storage, &pool, || progress.num_loading() == 0);
+        assert_eq!(vec!["aaaa", "bbbb", "cc", "bbbb"], *format.0.lock());
+        assert_eq!(4, storage.cache_stats().misses);
+    }
+
+    #[test]
+    fn progress_reports_states_bytes_and_errors() {
+        let pool = Arc::new(ThreadPoolBuilder::new().num_threads(1).build().unwrap());
+        let loader = Loader::with_default_source(Names, pool.clone());
+        let mut storage = AssetStorage::<Text>::new();
+        let format = RecordingFormat::default();
+        let mut progress = ProgressCounter::new();
+
+        let _loaded = loader.load("abc", format.clone(), &mut progress, &storage);
+        let _failed = loader.load_uuid(Uuid::new_v4(), format, &mut progress, &storage);
+        process_until(&mut storage, &pool, || progress.num_loading() == 0);
+
+        assert_eq!(3, progress.bytes_read());
+        let assets = progress.assets();
+        assert_eq!(LoadState::Loaded, assets[0].state());
+        assert_eq!(3, assets[0].bytes_read());
+        for &state in &[
+            LoadState::Queued,
+            LoadState::Reading,
+            LoadState::Importing,
+            LoadState::Processing,
+        ] {
+            assert!(assets[0].time_in(state).is_some());
+        }
+        assert_eq!(LoadState::Failed, assets[1].state());
+        assert_eq!(1, progress.num_in_state(LoadState::Failed));
+
+        let errors = progress.errors();
+        assert_eq!(Some("Recording"), errors[0].format_name);
+        assert_eq!(Some(""), errors[0].source.as_deref());
+        assert!(errors[0].chain()[0].starts_with("No asset with id"));
+    }
+}
@@ -0,0 +1,247 @@
+//! Stable asset ids, which keep working when asset files are moved or renamed.
+
+use std::{fs, path::Path};
+
+use fnv::FnvHashMap;
+use log::warn;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use amethyst_error::{format_err, Error, ResultExt};
+
+use crate::{source::collect_files, Format, RonFormat};
+
+/// Extension of the sidecar files storing the `AssetMeta` of an asset.
+///
+/// The meta file of `textures/player.png` is `textures/player.png.meta`.
+pub const META_EXTENSION: &str = "meta";
+
+/// Contents of the `.meta` sidecar file of an asset.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct AssetMeta {
+    /// Stable id of the asset.
+    pub uuid: Uuid,
+}
+
+impl AssetMeta {
+    /// Creates the meta data of a new asset, with a random id.
+    pub fn new() -> Self {
+        AssetMeta {
+            uuid: Uuid::new_v4(),
+        }
+    }
+
+    /// Reads the meta file of the asset at the given path.
+    pub fn read<P: AsRef<Path>>(asset: P) -> Result<Self, Error> {
+        let path = meta_path(asset.as_ref());
+        let bytes = fs::read(&path).with_context(|_| format_err!("Failed to read {:?}", path))?;
+        RonFormat
+            .import_simple(bytes)
+            .with_context(|_| format_err!("Failed to parse {:?}", path))
+    }
+
+    /// Writes the meta file of the asset at the given path.
+    pub fn write<P: AsRef<Path>>(&self, asset: P) -> Result<(), Error> {
+        let path = meta_path(asset.as_ref());
+        let ron = ron::ser::to_string_pretty(self, Default::default())
+            .with_context(|_| format_err!("Failed to serialize asset meta"))?;
+        fs::write(&path, ron).with_context(|_| format_err!("Failed to write {:?}", path))
+    }
+}
+
+impl Default for AssetMeta {
+    fn default() -> Self {
+        AssetMeta::new()
+    }
+}
+
+fn meta_path(asset: &Path) -> std::path::PathBuf {
+    let mut path = asset.as_os_str().to_owned();
+    path.push(".");
+    path.push(META_EXTENSION);
+    path.into()
+}
+
+fn is_meta(path: &str) -> bool {
+    Path::new(path).extension() == Some(META_EXTENSION.as_ref())
+}
+
+/// Maps the stable ids of assets to their paths and back.
+///
+/// The manifest is usually built from the `.meta` sidecar files of an assets directory with
+/// `from_directory` and handed to the `Loader` with `Loader::set_manifest`. Assets can then be
+/// loaded with `Loader::load_uuid`, from prefabs using `AssetPrefab::Uuid` and cached with
+/// `Cache::insert_uuid`, so moving a file together with its meta file doesn't break any
+/// references to it.
+#[derive(Debug, Default)]
+pub struct AssetManifest {
+    inner: RwLock<Inner>,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    paths: FnvHashMap<Uuid, String>,
+    uuids: FnvHashMap<String, Uuid>,
+}
+
+impl AssetManifest {
+    /// Creates a new, empty manifest.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Builds the manifest from all meta files in the given assets directory.
+    ///
+    /// Assets without a meta file are not part of the manifest.
+    pub fn from_directory<P: AsRef<Path>>(dir: P) -> Result<Self, Error> {
+        let dir = dir.as_ref();
+        let manifest = AssetManifest::new();
+        for path in asset_files(dir)? {
+            if !meta_path(&dir.join(&path)).exists() {
+                continue;
+            }
+
+            let meta = AssetMeta::read(dir.join(&path))?;
+            if let Some(other) = manifest.path(meta.uuid) {
+                warn!(
+                    "Assets {:?} and {:?} share the id {}, ignoring the former",
+                    path, other, meta.uuid,
+                );
+                continue;
+            }
+            manifest.insert(meta.uuid, path);
+        }
+
+        Ok(manifest)
+    }
+
+    /// Writes a meta file with a new id for every asset in the given directory which doesn't
+    /// have one yet. Returns the paths of those assets.
+    ///
+    /// This is meant to be run by tools and editors, the meta files should be committed
+    /// together with the assets.
+    pub fn generate_meta_files<P: AsRef<Path>>(dir: P) -> Result<Vec<String>, Error> {
+        let dir = dir.as_ref();
+        let mut generated = Vec::new();
+        for path in asset_files(dir)? {
+            let asset = dir.join(&path);
+            if !meta_path(&asset).exists() {
+                AssetMeta::new().write(&asset)?;
+                generated.push(path);
+            }
+        }
+
+        Ok(generated)
+    }
+
+    /// Maps the id to the given path, replacing any previous mapping of either.
+    pub fn insert<P: Into<String>>(&self, uuid: Uuid, path: P) {
+        let path = path.into();
+        let mut inner = self.inner.write();
+        if let Some(old) = inner.paths.insert(uuid, path.clone()) {
+            inner.uuids.remove(&old);
+        }
+        if let Some(old) = inner.uuids.insert(path, uuid) {
+            if old != uuid {
+                inner.paths.remove(&old);
+            }
+        }
+    }
+
+    /// Removes the id and its path from the manifest.
+    pub fn remove(&self, uuid: Uuid) -> Option<String> {
+        let mut inner = self.inner.write();
+        let path = inner.paths.remove(&uuid)?;
+        inner.uuids.remove(&path);
+        Some(path)
+    }
+
+    /// Returns the path of the asset with the given id.
+    pub fn path(&self, uuid: Uuid) -> Option<String> {
+        self.inner.read().paths.get(&uuid).cloned()
+    }
+
+    /// Returns the id of the asset at the given path.
+    pub fn uuid(&self, path: &str) -> Option<Uuid> {
+        self.inner.read().uuids.get(path).cloned()
+    }
+
+    /// Returns the number of assets in the manifest.
+    pub fn len(&self) -> usize {
+        self.inner.read().paths.len()
+    }
+
+    /// Returns `true` if the manifest contains no assets.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+fn asset_files(dir: &Path) -> Result<Vec<String>, Error> {
+    let mut files = Vec::new();
+    collect_files(dir, "", &mut files)?;
+    files.retain(|path| !is_meta(path));
+    files.sort();
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use uuid::Uuid;
+
+    use super::{AssetManifest, AssetMeta};
+
+    #[test]
+    fn ids_survive_moving_assets() {
+        let dir = std::env::temp_dir().join(format!("amethyst_manifest_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("textures")).unwrap();
+        fs::write(dir.join("textures/player.png"), b"png").unwrap();
+        fs::write(dir.join("level.ron"), b"()").unwrap();
+
+        let generated = AssetManifest::generate_meta_files(&dir).unwrap();
+        assert_eq!(vec!["level.ron", "textures/player.png"], generated);
+        assert!(AssetManifest::generate_meta_files(&dir).unwrap().is_empty());
+
+        let manifest = AssetManifest::from_directory(&dir).unwrap();
+        assert_eq!(2, manifest.len());
+        let uuid = manifest.uuid("textures/player.png").unwrap();
+        assert_eq!(
+            uuid,
+            AssetMeta::read(dir.join("textures/player.png"))
+                .unwrap()
+                .uuid
+        );
+
+        fs::rename(dir.join("textures/player.png"), dir.join("hero.png")).unwrap();
+        fs::rename(
+            dir.join("textures/player.png.meta"),
+            dir.join("hero.png.meta"),
+        )
+        .unwrap();
+        let manifest = AssetManifest::from_directory(&dir).unwrap();
+        assert_eq!(Some("hero.png".to_owned()), manifest.path(uuid));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn insert_replaces_both_directions() {
+        let manifest = AssetManifest::new();
+        let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+        manifest.insert(a, "a.png");
+        manifest.insert(a, "moved.png");
+        assert_eq!(None, manifest.uuid("a.png"));
+        assert_eq!(Some(a), manifest.uuid("moved.png"));
+
+        manifest.insert(b, "moved.png");
+        assert_eq!(None, manifest.path(a));
+        assert_eq!(1, manifest.len());
+
+        assert_eq!(Some("moved.png".to_owned()), manifest.remove(b));
+        assert!(manifest.is_empty());
+    }
+}
@@ -1,10 +1,10 @@
 use amethyst_core::{
-    ecs::{Entity, WriteStorage},
+    ecs::{Entity, ReadStorage, WriteStorage},
     Named, Transform,
 };
 use amethyst_error::Error;
 
-use crate::{PrefabData, ProgressCounter};
+use crate::{ExportPrefabData, PrefabData, ProgressCounter};
 
 impl<'a, T> PrefabData<'a> for Option<T>
 where
@@ -43,6 +43,33 @@ where
             Ok(false)
         }
     }
+
+    fn remove_from_entity(
+        &self,
+        entity: Entity,
+        system_data: &mut Self::SystemData,
+    ) -> Result<(), Error> {
+        if let Some(ref prefab) = self {
+            prefab.remove_from_entity(entity, system_data)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl<'a, T> ExportPrefabData<'a> for Option<T>
+where
+    T: ExportPrefabData<'a>,
+{
+    type SystemData = <T as ExportPrefabData<'a>>::SystemData;
+
+    fn export_from_entity(
+        entity: Entity,
+        system_data: &Self::SystemData,
+        entities: &[Entity],
+    ) -> Result<Option<Self>, Error> {
+        Ok(Some(T::export_from_entity(entity, system_data, entities)?))
+    }
 }
 
 impl<'a> PrefabData<'a> for Transform {
@@ -59,6 +86,15 @@ impl<'a> PrefabData<'a> for Transform {
         storages.insert(entity, self.clone()).map(|_| ())?;
         Ok(())
     }
+
+    fn remove_from_entity(
+        &self,
+        entity: Entity,
+        storages: &mut Self::SystemData,
+    ) -> Result<(), Error> {
+        storages.remove(entity);
+        Ok(())
+    }
 }
 
 impl<'a> PrefabData<'a> for Named {
@@ -75,6 +111,39 @@ impl<'a> PrefabData<'a> for Named {
         storages.0.insert(entity, self.clone()).map(|_| ())?;
         Ok(())
     }
+
+    fn remove_from_entity(
+        &self,
+        entity: Entity,
+        storages: &mut Self::SystemData,
+    ) -> Result<(), Error> {
+        storages.0.remove(entity);
+        Ok(())
+    }
+}
+
+impl<'a> ExportPrefabData<'a> for Transform {
+    type SystemData = ReadStorage<'a, Transform>;
+
+    fn export_from_entity(
+        entity: Entity,
+        storage: &Self::SystemData,
+        _: &[Entity],
+    ) -> Result<Option<Self>, Error> {
+        Ok(storage.get(entity).cloned())
+    }
+}
+
+impl<'a> ExportPrefabData<'a> for Named {
+    type SystemData = ReadStorage<'a, Named>;
+
+    fn export_from_entity(
+        entity: Entity,
+        storage: &Self::SystemData,
+        _: &[Entity],
+    ) -> Result<Option<Self>, Error> {
+        Ok(storage.get(entity).cloned())
+    }
 }
 
 macro_rules! impl_data {
@@ -115,6 +184,48 @@ macro_rules! impl_data {
                 )*
                 Ok(ret)
             }
+
+            fn remove_from_entity(
+                &self,
+                entity: Entity,
+                system_data: &mut Self::SystemData,
+            ) -> Result<(), Error> {
+                #![allow(unused_variables)]
+                $(
+                    self.$i.remove_from_entity(entity, &mut system_data.$i)?;
+                )*
+                Ok(())
+            }
+        }
+    };
+}
+
+macro_rules! impl_export_data {
+    ( $($ty:ident:$i:tt),* ) => {
+        impl<'a, $($ty),*> ExportPrefabData<'a> for ( $( $ty , )* )
+            where $( $ty : ExportPrefabData<'a> ),*
+        {
+            type SystemData = (
+                $(
+                    $ty::SystemData,
+                )*
+            );
+
+            fn export_from_entity(
+                entity: Entity,
+                system_data: &Self::SystemData,
+                entities: &[Entity],
+            ) -> Result<Option<Self>, Error> {
+                #![allow(unused_variables)]
+                Ok(Some((
+                    $(
+                        match $ty::export_from_entity(entity, &system_data.$i, entities)? {
+                            Some(data) => data,
+                            None => return Ok(None),
+                        },
+                    )*
+                )))
+            }
         }
     };
 }
@@ -141,3 +252,26 @@ impl_data!(A:0, B:1, C:2, D:3, E:4, F:5, G:6, H:7, I:8, J:9, K:10, L:11, M:12, N
 impl_data!(A:0, B:1, C:2, D:3, E:4, F:5, G:6, H:7, I:8, J:9, K:10, L:11, M:12, N:13, O:14, P:15, Q:16, R:17, S:18);
 impl_data!(A:0, B:1, C:2, D:3, E:4, F:5, G:6, H:7, I:8, J:9, K:10, L:11, M:12, N:13, O:14, P:15, Q:16, R:17, S:18, T:19);
 impl_data!(A:0, B:1, C:2, D:3, E:4, F:5, G:6, H:7, I:8, J:9, K:10, L:11, M:12, N:13, O:14, P:15, Q:16, R:17, S:18, T:19, U:20);
+
+impl_export_data!();
+impl_export_data!(A:0);
+impl_export_data!(A:0, B:1);
+impl_export_data!(A:0, B:1, C:2);
+impl_export_data!(A:0, B:1, C:2, D:3);
+impl_export_data!(A:0, B:1, C:2, D:3, E:4);
+impl_export_data!(A:0, B:1, C:2, D:3, E:4, F:5);
+impl_export_data!(A:0, B:1, C:2, D:3, E:4, F:5, G:6);
+impl_export_data!(A:0, B:1, C:2, D:3, E:4, F:5, G:6, H:7);
+impl_export_data!(A:0, B:1, C:2, D:3, E:4, F:5, G:6, H:7, I:8);
+impl_export_data!(A:0, B:1, C:2, D:3, E:4, F:5, G:6, H:7, I:8, J:9);
+impl_export_data!(A:0, B:1, C:2, D:3, E:4, F:5, G:6, H:7, I:8, J:9, K:10);
+impl_export_data!(A:0, B:1, C:2, D:3, E:4, F:5, G:6, H:7, I:8, J:9, K:10, L:11);
+impl_export_data!(A:0, B:1, C:2, D:3, E:4, F:5, G:6, H:7, I:8, J:9, K:10, L:11, M:12);
+impl_export_data!(A:0, B:1, C:2, D:3, E:4, F:5, G:6, H:7, I:8, J:9, K:10, L:11, M:12, N:13);
+impl_export_data!(A:0, B:1, C:2, D:3, E:4, F:5, G:6, H:7, I:8, J:9, K:10, L:11, M:12, N:13, O:14);
+impl_export_data!(A:0, B:1, C:2, D:3, E:4, F:5, G:6, H:7, I:8, J:9, K:10, L:11, M:12, N:13, O:14, P:15);
+impl_export_data!(A:0, B:1, C:2, D:3, E:4, F:5, G:6, H:7, I:8, J:9, K:10, L:11, M:12, N:13, O:14, P:15, Q:16);
+impl_export_data!(A:0, B:1, C:2, D:3, E:4, F:5, G:6, H:7, I:8, J:9, K:10, L:11, M:12, N:13, O:14, P:15, Q:16, R:17);
+impl_export_data!(A:0, B:1, C:2, D:3, E:4, F:5, G:6, H:7, I:8, J:9, K:10, L:11, M:12, N:13, O:14, P:15, Q:16, R:17, S:18);
+impl_export_data!(A:0, B:1, C:2, D:3, E:4, F:5, G:6, H:7, I:8, J:9, K:10, L:11, M:12, N:13, O:14, P:15, Q:16, R:17, S:18, T:19);
+impl_export_data!(A:0, B:1, C:2, D:3, E:4, F:5, G:6, H:7, I:8, J:9, K:10, L:11, M:12, N:13, O:14, P:15, Q:16, R:17, S:18, T:19, U:20);
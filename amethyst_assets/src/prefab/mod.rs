@@ -1,15 +1,22 @@
 use std::marker::PhantomData;
 
 use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 
-use amethyst_core::ecs::prelude::{
-    Component, DenseVecStorage, Entity, FlaggedStorage, Read, ReadExpect, ResourceId, SystemData,
-    World, WriteStorage,
+use fnv::{FnvHashMap, FnvHashSet};
+
+use amethyst_core::{
+    ecs::prelude::{
+        Component, DenseVecStorage, Entities, Entity, FlaggedStorage, Join, Read, ReadExpect,
+        ReadStorage, ResourceId, SystemData, World, WriteStorage,
+    },
+    Parent,
 };
-use amethyst_error::Error;
+use amethyst_error::{format_err, Error};
 
 use crate::{
-    Asset, AssetStorage, Format, Handle, Loader, Progress, ProgressCounter, SerializableFormat,
+    Asset, AssetId, AssetStorage, Format, Handle, Loader, Progress, ProgressCounter,
+    SerializableFormat,
 };
 
 pub use self::system::{PrefabLoaderSystem, PrefabLoaderSystemDesc};
@@ -71,6 +78,54 @@ pub trait PrefabData<'a> {
     ) -> Result<bool, Error> {
         Ok(false)
     }
+
+    /// Remove the data this prefab added from the given `Entity`
+    ///
+    /// Used to strip components which are no longer part of a prefab when re-instantiating it
+    /// after a hot reload, see `PrefabLoaderSystemDesc::with_reinstantiation`. The default
+    /// implementation leaves the entity as it is.
+    ///
+    /// ### Parameters:
+    ///
+    /// - `entity`: `Entity` to remove the components from
+    /// - `system_data`: `SystemData` needed to do the removal
+    fn remove_from_entity(
+        &self,
+        _entity: Entity,
+        _system_data: &mut Self::SystemData,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+/// Trait for reading the prefab data of a single entity back out of the `World`.
+///
+/// This is the counterpart of `PrefabData`, used by `PrefabExporter` to turn entities arranged
+/// at runtime, e.g. in a level editor, into a `Prefab` which can be saved and loaded again.
+/// Can be implemented with `#[derive(PrefabData)]` by adding `#[prefab(Export)]` to the type.
+pub trait ExportPrefabData<'a>: Sized {
+    /// `SystemData` needed to read the entity
+    type SystemData: SystemData<'a>;
+
+    /// Read the data for this prefab from the given `Entity`
+    ///
+    /// ### Parameters:
+    ///
+    /// - `entity`: `Entity` to read components from
+    /// - `system_data`: `SystemData` needed to do the reading
+    /// - `entities`: All entities being exported, in the order of the `Prefab`, so links to
+    ///   other entities can be stored as indices like `PrefabData` expects them.
+    ///
+    /// ### Returns
+    ///
+    /// - `Err(error)` - if the entity can't be represented by this prefab data
+    /// - `Ok(None)` - if the entity doesn't have the components this prefab data is made of
+    /// - `Ok(Some(data))` - otherwise
+    fn export_from_entity(
+        entity: Entity,
+        system_data: &Self::SystemData,
+        entities: &[Entity],
+    ) -> Result<Option<Self>, Error>;
 }
 
 /// Main `Prefab` structure, containing all data loaded in a single prefab.
@@ -82,6 +137,27 @@ pub trait PrefabData<'a> {
 ///
 /// The recommended way of loading resources is to place them on the main `Entity`.
 ///
+/// Entities can also instantiate other prefabs, see `PrefabEntity::set_prefab`. A prefab which
+/// instantiates another prefab on its main entity derives from it, so the following is
+/// `goblin.ron`, but with its main entity overridden by 20 health and the data of its second
+/// entity (index 1 in `goblin.ron`) overridden by a red tint:
+///
+/// ```ron,ignore
+/// #![enable(implicit_some)]
+/// Prefab(
+///     entities: [
+///         (
+///             prefab: "goblin.ron",
+///             data: (health: 20),
+///             overrides: [(entity: 1, data: (tint: (1.0, 0.0, 0.0, 1.0)))],
+///         ),
+///     ],
+/// )
+/// ```
+///
+/// Referenced prefabs are loaded by the `PrefabLoaderSystem` with the format given to
+/// `PrefabLoaderSystemDesc::with_format`.
+///
 /// ### Example:
 ///
 /// If we want to give the existing Baker entity a Knife and a Plate with a
@@ -111,6 +187,8 @@ pub struct Prefab<T> {
     entities: Vec<PrefabEntity<T>>,
     #[serde(skip)]
     counter: Option<ProgressCounter>,
+    #[serde(skip)]
+    nested: Option<ProgressCounter>,
 }
 
 /// Prefab data container for a single entity
@@ -122,7 +200,11 @@ pub struct Prefab<T> {
 #[serde(default)]
 pub struct PrefabEntity<T> {
     parent: Option<usize>,
+    prefab: Option<String>,
+    overrides: Vec<PrefabOverride<T>>,
     data: Option<T>,
+    #[serde(skip)]
+    instance: Option<Handle<Prefab<T>>>,
 }
 
 impl<T> Default for PrefabEntity<T> {
@@ -131,10 +213,29 @@ impl<T> Default for PrefabEntity<T> {
     }
 }
 
+/// Data overriding the data of an entity of a nested prefab.
+///
+/// ### Type parameters:
+///
+/// - `T`: `PrefabData`
+#[derive(Debug, Deserialize, Serialize)]
+pub struct PrefabOverride<T> {
+    /// Index of the overridden entity in the nested prefab
+    pub entity: usize,
+    /// Data added to the entity after the data of the nested prefab
+    pub data: T,
+}
+
 impl<T> PrefabEntity<T> {
     /// New prefab entity
     pub fn new(parent: Option<usize>, data: Option<T>) -> Self {
-        PrefabEntity { parent, data }
+        PrefabEntity {
+            parent,
+            prefab: None,
+            overrides: Vec::new(),
+            data,
+            instance: None,
+        }
     }
 
     /// Set parent index
@@ -142,6 +243,11 @@ impl<T> PrefabEntity<T> {
         self.parent = Some(parent);
     }
 
+    /// Get parent index
+    pub fn parent(&self) -> Option<usize> {
+        self.parent
+    }
+
     /// Set data
     pub fn set_data(&mut self, data: T) {
         self.data = Some(data);
@@ -174,6 +280,31 @@ impl<T> PrefabEntity<T> {
         self.data.get_or_insert_with(func)
     }
 
+    /// Set the name of a prefab to instantiate on this entity
+    ///
+    /// The entities of the nested prefab are created below this entity, with the main entity of
+    /// the nested prefab being this entity. The data of this entity is added after the data of
+    /// the nested prefab, overriding it.
+    pub fn set_prefab<N: Into<String>>(&mut self, name: N) {
+        self.prefab = Some(name.into());
+        self.instance = None;
+    }
+
+    /// Get the name of the prefab instantiated on this entity
+    pub fn prefab(&self) -> Option<&str> {
+        self.prefab.as_deref()
+    }
+
+    /// Override the data of the entity with the given index in the nested prefab
+    pub fn add_override(&mut self, entity: usize, data: T) {
+        self.overrides.push(PrefabOverride { entity, data });
+    }
+
+    /// Get the overrides for the entities of the nested prefab
+    pub fn overrides(&self) -> &[PrefabOverride<T>] {
+        &self.overrides
+    }
+
     /// Trigger sub asset loading for the prefab entity
     pub fn load_sub_assets<'a>(
         &mut self,
@@ -183,11 +314,18 @@ impl<T> PrefabEntity<T> {
     where
         T: PrefabData<'a>,
     {
-        if let Some(ref mut data) = self.data {
-            data.load_sub_assets(progress, system_data)
-        } else {
-            Ok(false)
+        let mut ret = false;
+        for data in self
+            .overrides
+            .iter_mut()
+            .map(|o| &mut o.data)
+            .chain(self.data.as_mut())
+        {
+            if data.load_sub_assets(progress, system_data)? {
+                ret = true;
+            }
         }
+        Ok(ret)
     }
 }
 
@@ -198,6 +336,7 @@ impl<T> Prefab<T> {
             tag: None,
             entities: vec![PrefabEntity::default()],
             counter: None,
+            nested: None,
         }
     }
 
@@ -207,6 +346,7 @@ impl<T> Prefab<T> {
             tag: None,
             entities: vec![PrefabEntity::new(None, Some(data))],
             counter: None,
+            nested: None,
         }
     }
 
@@ -288,16 +428,60 @@ impl<T> Prefab<T> {
             .expect("Sub asset loading has not been triggered")
     }
 
+    /// Get the `ProgressCounter` for loading the prefabs nested in this prefab, if any were
+    /// referenced and their loading has been triggered.
+    pub fn nested_progress(&self) -> Option<&ProgressCounter> {
+        self.nested.as_ref()
+    }
+
+    /// Names of the nested prefabs which still need to be loaded, with the index of the entity
+    /// they are instantiated on.
+    pub(crate) fn unresolved(&self) -> Vec<(usize, String)> {
+        self.entities
+            .iter()
+            .enumerate()
+            .filter(|(_, entity)| entity.instance.is_none())
+            .filter_map(|(index, entity)| entity.prefab.clone().map(|name| (index, name)))
+            .collect()
+    }
+
+    /// Hands the handles of the nested prefabs and the progress of loading them to the prefab.
+    pub(crate) fn resolve(
+        &mut self,
+        instances: Vec<(usize, Handle<Prefab<T>>)>,
+        progress: ProgressCounter,
+    ) {
+        for (index, handle) in instances {
+            self.entities[index].instance = Some(handle);
+        }
+        self.nested = Some(progress);
+    }
+
     /// Trigger sub asset loading for the asset
     pub fn load_sub_assets<'a>(
         &mut self,
         system_data: &mut <T as PrefabData<'a>>::SystemData,
     ) -> Result<bool, Error>
+    where
+        T: PrefabData<'a>,
+    {
+        self.load_sub_assets_for(None, system_data)
+    }
+
+    /// Trigger sub asset loading for the asset, recording the sub assets as dependencies of
+    /// the given prefab asset.
+    pub(crate) fn load_sub_assets_for<'a>(
+        &mut self,
+        dependent: Option<AssetId>,
+        system_data: &mut <T as PrefabData<'a>>::SystemData,
+    ) -> Result<bool, Error>
     where
         T: PrefabData<'a>,
     {
         let mut ret = false;
-        let mut progress = ProgressCounter::default();
+        let mut progress = dependent
+            .map(ProgressCounter::with_dependent)
+            .unwrap_or_default();
         for entity in &mut self.entities {
             if entity.load_sub_assets(&mut progress, system_data)? {
                 ret = true;
@@ -368,6 +552,8 @@ where
     Handle(Handle<A>),
     /// From file, (name, format)
     File(String, F),
+    /// From file with the given id in the `AssetManifest`, (id, format)
+    Uuid(Uuid, F),
     /// Placeholder during loading
     #[serde(skip)]
     Placeholder,
@@ -403,6 +589,15 @@ where
             .map(|_| handle)?)
     }
 
+    fn remove_from_entity(
+        &self,
+        entity: Entity,
+        system_data: &mut Self::SystemData,
+    ) -> Result<(), Error> {
+        system_data.1.remove(entity);
+        Ok(())
+    }
+
     fn load_sub_assets(
         &mut self,
         progress: &mut ProgressCounter,
@@ -413,6 +608,10 @@ where
                 let handle = loader.load(name, format, progress, storage);
                 (true, AssetPrefab::Handle(handle))
             }
+            AssetPrefab::Uuid(uuid, format) => {
+                let handle = loader.load_uuid(uuid, format, progress, storage);
+                (true, AssetPrefab::Handle(handle))
+            }
             slot => (false, slot),
         };
         *self = next;
@@ -463,22 +662,140 @@ where
     }
 }
 
+/// Helper structure for exporting entities as prefabs.
+///
+/// The root `Entity` becomes the main entity of the `Prefab`, all entities below it in the
+/// `Parent` hierarchy are added as children with their parent indices.
+///
+/// ### Example
+///
+/// ```rust,ignore
+/// let prefab = world.exec(|exporter: PrefabExporter<'_, SomePrefab>| {
+///     exporter.export(root)
+/// })?;
+/// let ron = ron::ser::to_string_pretty(&prefab, Default::default())?;
+/// ```
+#[derive(SystemData)]
+pub struct PrefabExporter<'a, T>
+where
+    T: ExportPrefabData<'a>,
+{
+    entities: Entities<'a>,
+    parents: ReadStorage<'a, Parent>,
+    data: <T as ExportPrefabData<'a>>::SystemData,
+}
+
+impl<'a, T> PrefabExporter<'a, T>
+where
+    T: ExportPrefabData<'a>,
+{
+    /// Export the given `Entity` and all its children as a prefab
+    pub fn export(&self, root: Entity) -> Result<Prefab<T>, Error> {
+        if !self.entities.is_alive(root) {
+            return Err(format_err!("Can't export dead entity {:?}", root));
+        }
+
+        let mut children = FnvHashMap::<Entity, Vec<Entity>>::default();
+        for (entity, parent) in (&self.entities, &self.parents).join() {
+            children.entry(parent.entity).or_default().push(entity);
+        }
+
+        // Breadth first, so every parent comes before its children.
+        let mut entities = vec![root];
+        let mut parents = vec![None];
+        let mut visited = FnvHashSet::default();
+        visited.insert(root);
+        let mut index = 0;
+        while index < entities.len() {
+            for &child in children.get(&entities[index]).into_iter().flatten() {
+                if visited.insert(child) {
+                    entities.push(child);
+                    parents.push(Some(index));
+                }
+            }
+            index += 1;
+        }
+
+        let mut prefab = Prefab {
+            tag: None,
+            entities: Vec::with_capacity(entities.len()),
+            counter: None,
+            nested: None,
+        };
+        for (&entity, parent) in entities.iter().zip(parents) {
+            let data = T::export_from_entity(entity, &self.data, &entities)?;
+            prefab.entities.push(PrefabEntity::new(parent, data));
+        }
+        Ok(prefab)
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use std::sync::Arc;
+    use std::{
+        fs,
+        path::Path,
+        sync::Arc,
+        thread,
+        time::{Duration, SystemTime},
+    };
 
     use rayon::ThreadPoolBuilder;
 
     use amethyst_core::{
         ecs::{Builder, RunNow, World, WorldExt},
-        SystemDesc, Time, Transform,
+        Hidden, Named, SystemDesc, Time, Transform,
     };
 
-    use crate::Loader;
+    use crate::{Completion, HotReloadStrategy, HotReloadSystem, Loader, RonFormat};
 
     use super::*;
 
     type MyPrefab = Transform;
+    type NamedPrefab = (Option<Transform>, Option<Named>);
+
+    fn named(name: &'static str) -> NamedPrefab {
+        (None, Some(Named::new(name)))
+    }
+
+    fn write_prefab(dir: &Path, name: &str, prefab: &Prefab<NamedPrefab>) {
+        let ron = ron::ser::to_string(prefab).unwrap();
+        fs::write(dir.join(name), ron).unwrap();
+    }
+
+    /// Loads the prefab with the given name from `dir` and runs the `PrefabLoaderSystem` until
+    /// it is done.
+    fn load_nested(dir: &Path, name: &str) -> (World, ProgressCounter, Entity) {
+        let mut world = World::new();
+        let pool = Arc::new(ThreadPoolBuilder::default().build().unwrap());
+        world.insert(pool.clone());
+        world.insert(Loader::new(dir, pool));
+        world.insert(Time::default());
+        let mut system = PrefabLoaderSystemDesc::<NamedPrefab>::default()
+            .with_format(RonFormat)
+            .build(&mut world);
+        RunNow::setup(&mut system, &mut world);
+
+        let mut progress = ProgressCounter::new();
+        let handle = world.read_resource::<Loader>().load(
+            name,
+            RonFormat,
+            &mut progress,
+            &world.read_resource::<AssetStorage<Prefab<NamedPrefab>>>(),
+        );
+        let root = world.create_entity().with(handle).build();
+        for _ in 0..500 {
+            system.run_now(&world);
+            world.maintain();
+            if progress.num_loading() == 0 {
+                break;
+            }
+            thread::sleep(Duration::from_millis(5));
+        }
+        system.run_now(&world);
+        world.maintain();
+        (world, progress, root)
+    }
 
     #[test]
     fn test_prefab_load() {
@@ -505,4 +822,186 @@ mod tests {
         );
         assert!(world.read_storage::<Transform>().get(root_entity).is_some());
     }
+
+    #[test]
+    fn exported_hierarchy_round_trips_through_ron() {
+        type Data = (Option<Transform>, Option<Named>);
+        let mut world = World::new();
+        world.register::<Transform>();
+        world.register::<Named>();
+        world.register::<Parent>();
+        let mut transform = Transform::default();
+        transform.set_translation_x(1.0);
+        let root = world.create_entity().with(Named::new("root")).build();
+        let child = world
+            .create_entity()
+            .with(Parent::new(root))
+            .with(transform.clone())
+            .build();
+        world
+            .create_entity()
+            .with(Parent::new(child))
+            .with(Named::new("leaf"))
+            .build();
+        world.create_entity().with(Named::new("unrelated")).build();
+
+        let prefab = world
+            .exec(|exporter: PrefabExporter<'_, Data>| exporter.export(root))
+            .unwrap();
+        let ron = ron::ser::to_string(&prefab).unwrap();
+        let prefab: Prefab<Data> = RonFormat.import_simple(ron.into_bytes()).unwrap();
+
+        let parents = prefab
+            .entities()
+            .map(PrefabEntity::parent)
+            .collect::<Vec<_>>();
+        assert_eq!(vec![None, Some(0), Some(1)], parents);
+        let data = prefab
+            .entities()
+            .map(|entity| entity.data().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(None, data[0].0);
+        assert_eq!("root", data[0].1.as_ref().unwrap().name);
+        assert_eq!(Some(transform), data[1].0);
+        assert!(data[1].1.is_none());
+        assert_eq!("leaf", data[2].1.as_ref().unwrap().name);
+    }
+
+    #[test]
+    fn nested_prefabs_with_overrides() {
+        let dir = std::env::temp_dir().join(format!("amethyst_nested_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let mut transform = Transform::default();
+        transform.set_translation_x(1.0);
+
+        let mut base = Prefab::new_main((Some(transform.clone()), Some(Named::new("goblin"))));
+        base.add(Some(0), Some(named("hand")));
+        write_prefab(&dir, "base.ron", &base);
+
+        let mut derived = Prefab::new_main(named("red goblin"));
+        derived.entity(0).unwrap().set_prefab("base.ron");
+        derived
+            .entity(0)
+            .unwrap()
+            .add_override(1, named("red hand"));
+        let sidekick = derived.add(Some(0), None);
+        derived.entity(sidekick).unwrap().set_prefab("base.ron");
+        write_prefab(&dir, "derived.ron", &derived);
+
+        let (world, progress, root) = load_nested(&dir, "derived.ron");
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(progress.is_complete());
+
+        assert_eq!(Some(&transform), world.read_storage().get(root));
+        let names = world.read_storage::<Named>();
+        assert_eq!("red goblin", names.get(root).unwrap().name);
+        let mut all = names
+            .join()
+            .map(|named| named.name.to_string())
+            .collect::<Vec<_>>();
+        all.sort();
+        assert_eq!(vec!["goblin", "hand", "red goblin", "red hand"], all);
+
+        let handle = world
+            .read_storage::<Handle<Prefab<NamedPrefab>>>()
+            .get(root)
+            .cloned()
+            .unwrap();
+        let dependencies = world
+            .read_resource::<Loader>()
+            .dependencies()
+            .dependencies(AssetId::from(&handle));
+        assert_eq!(2, dependencies.len());
+    }
+
+    #[test]
+    fn self_referencing_prefab_fails() {
+        let dir = std::env::temp_dir().join(format!("amethyst_cycle_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let mut prefab = Prefab::new_main(named("loop"));
+        prefab.entity(0).unwrap().set_prefab("loop.ron");
+        write_prefab(&dir, "loop.ron", &prefab);
+
+        let (_, progress, _) = load_nested(&dir, "loop.ron");
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(Completion::Failed, progress.complete());
+    }
+
+    #[test]
+    fn reloaded_prefab_is_reapplied() {
+        let dir = std::env::temp_dir().join(format!("amethyst_reinst_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let mut prefab = Prefab::new_main((Some(Transform::default()), Some(Named::new("goblin"))));
+        prefab.add(Some(0), Some(named("hand")));
+        write_prefab(&dir, "goblin.ron", &prefab);
+
+        let mut world = World::new();
+        let pool = Arc::new(ThreadPoolBuilder::default().build().unwrap());
+        world.insert(pool.clone());
+        let mut loader = Loader::new(&dir, pool);
+        loader.set_hot_reload(true);
+        world.insert(loader);
+        world.insert(Time::default());
+        world.insert(HotReloadStrategy::when_triggered());
+        world.register::<Hidden>();
+        let mut system = PrefabLoaderSystemDesc::<NamedPrefab>::default()
+            .with_reinstantiation()
+            .build(&mut world);
+        RunNow::setup(&mut system, &mut world);
+        let mut run_until = |world: &mut World, done: &dyn Fn(&World) -> bool| {
+            for _ in 0..500 {
+                world.write_resource::<Time>().increment_frame_number();
+                system.run_now(world);
+                world.maintain();
+                if done(world) {
+                    return;
+                }
+                thread::sleep(Duration::from_millis(5));
+            }
+            panic!("Prefab wasn't loaded in time");
+        };
+
+        let handle = world.read_resource::<Loader>().load(
+            "goblin.ron",
+            RonFormat,
+            (),
+            &world.read_resource::<AssetStorage<Prefab<NamedPrefab>>>(),
+        );
+        let root = world.create_entity().with(handle).with(Hidden).build();
+        run_until(&mut world, &|world| {
+            world.read_storage::<Named>().get(root).is_some()
+        });
+        let hand = (&world.entities(), &world.read_storage::<Parent>())
+            .join()
+            .map(|(entity, _)| entity)
+            .next()
+            .unwrap();
+
+        let mut prefab = Prefab::new_main(named("orc"));
+        prefab.add(Some(0), Some(named("claw")));
+        prefab.add(Some(1), Some(named("nail")));
+        write_prefab(&dir, "goblin.ron", &prefab);
+        fs::File::open(dir.join("goblin.ron"))
+            .unwrap()
+            .set_modified(SystemTime::now() + Duration::from_secs(10))
+            .unwrap();
+        world.write_resource::<HotReloadStrategy>().trigger();
+        let mut reload = HotReloadSystem::new();
+        reload.run_now(&world);
+        run_until(&mut world, &|world| {
+            world.read_storage::<Named>().get(root).unwrap().name == "orc"
+        });
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(world.read_storage::<Transform>().get(root).is_none());
+        assert!(world.read_storage::<Hidden>().contains(root));
+        let names = world.read_storage::<Named>();
+        assert_eq!("claw", names.get(hand).unwrap().name);
+        let parents = world.read_storage::<Parent>();
+        let (_, nail) = (&names, &parents)
+            .join()
+            .find(|(named, _)| named.name == "nail")
+            .unwrap();
+        assert_eq!(hand, nail.entity);
+    }
 }
@@ -1,6 +1,7 @@
-use std::{collections::HashMap, marker::PhantomData};
+use std::{marker::PhantomData, sync::Arc};
 
 use derivative::Derivative;
+use fnv::FnvHashMap;
 use log::error;
 
 use amethyst_core::{
@@ -15,17 +16,65 @@ use amethyst_error::{format_err, Error, ResultExt};
 #[cfg(feature = "profiler")]
 use thread_profiler::profile_scope;
 
-use crate::{AssetStorage, Completion, Handle, HotReloadStrategy, ProcessingState};
+use crate::{
+    AssetId, AssetStorage, Completion, Format, Handle, HotReloadStrategy, Loader, ProcessingState,
+    ProgressCounter,
+};
+
+use super::{Prefab, PrefabData, PrefabOverride, PrefabTag};
 
-use super::{Prefab, PrefabData, PrefabTag};
+/// Loads a nested prefab by name.
+type LoadFn<T> = Arc<
+    dyn Fn(&Loader, String, &mut ProgressCounter, &AssetStorage<Prefab<T>>) -> Handle<Prefab<T>>
+        + Send
+        + Sync,
+>;
 
 /// Builds a `PrefabLoaderSystem`.
-#[derive(Derivative, Debug)]
-#[derivative(Default(bound = ""))]
-pub struct PrefabLoaderSystemDesc<T> {
+#[derive(Derivative)]
+#[derivative(Default(bound = ""), Debug(bound = ""))]
+pub struct PrefabLoaderSystemDesc<T>
+where
+    T: Send + Sync + 'static,
+{
+    #[derivative(Debug = "ignore")]
+    load: Option<LoadFn<T>>,
+    reinstantiate: bool,
     marker: PhantomData<T>,
 }
 
+impl<T> PrefabLoaderSystemDesc<T>
+where
+    T: Send + Sync + 'static,
+{
+    /// Sets the format used to load prefabs referenced by other prefabs.
+    ///
+    /// Without a format, loading a prefab which references other prefabs fails.
+    pub fn with_format<F>(mut self, format: F) -> Self
+    where
+        F: Format<Prefab<T>> + Clone,
+    {
+        self.load = Some(Arc::new(move |loader, name, progress, storage| {
+            loader.load(name, format.clone(), progress, storage)
+        }));
+        self
+    }
+
+    /// Re-applies reloaded prefabs to the entities which were already created from them.
+    ///
+    /// When a prefab is hot reloaded, the components its previous version added are removed
+    /// with `PrefabData::remove_from_entity` and the new version is added to the same entities.
+    /// Entities are matched by their index in the prefab, entities no longer in the prefab are
+    /// deleted and new ones are created. Components which weren't added by the prefab are kept.
+    ///
+    /// This is meant for iterating on prefabs during development, as it keeps track of every
+    /// entity created from a prefab.
+    pub fn with_reinstantiation(mut self) -> Self {
+        self.reinstantiate = true;
+        self
+    }
+}
+
 impl<'a, 'b, T> SystemDesc<'a, 'b, PrefabLoaderSystem<T>> for PrefabLoaderSystemDesc<T>
 where
     T: PrefabData<'a> + Send + Sync + 'static,
@@ -35,7 +84,10 @@ where
 
         let insert_reader = WriteStorage::<Handle<Prefab<T>>>::fetch(&world).register_reader();
 
-        PrefabLoaderSystem::new(insert_reader)
+        let mut system = PrefabLoaderSystem::new(insert_reader);
+        system.load = self.load;
+        system.reinstantiate = self.reinstantiate;
+        system
     }
 }
 
@@ -44,13 +96,26 @@ where
 /// ### Type parameters:
 ///
 /// - `T`: `PrefabData`
-pub struct PrefabLoaderSystem<T> {
+pub struct PrefabLoaderSystem<T>
+where
+    T: Send + Sync + 'static,
+{
     _m: PhantomData<T>,
     entities: Vec<Entity>,
     finished: Vec<Entity>,
     to_process: BitSet,
     insert_reader: ReaderId<ComponentEvent>,
     next_tag: u64,
+    load: Option<LoadFn<T>>,
+    /// Nested prefabs to load, by the handle of the referencing prefab.
+    requests: Vec<(Handle<Prefab<T>>, Vec<(usize, String)>)>,
+    /// Loaded nested prefabs, by the handle id of the referencing prefab.
+    resolved: FnvHashMap<u32, (Vec<(usize, Handle<Prefab<T>>)>, ProgressCounter)>,
+    /// Names of the prefabs a nested prefab was loaded through, by its handle id.
+    chains: FnvHashMap<u32, Vec<String>>,
+    reinstantiate: bool,
+    /// Main entities and all created entities of the instances of a prefab, by its handle id.
+    instances: FnvHashMap<u32, Vec<(Entity, Vec<Entity>)>>,
 }
 
 impl<'a, T> PrefabLoaderSystem<T>
@@ -66,8 +131,314 @@ where
             to_process: BitSet::default(),
             insert_reader,
             next_tag: 0,
+            load: None,
+            requests: Vec::new(),
+            resolved: FnvHashMap::default(),
+            chains: FnvHashMap::default(),
+            reinstantiate: false,
+            instances: FnvHashMap::default(),
         }
     }
+
+    fn process(
+        &mut self,
+        handle: &Handle<Prefab<T>>,
+        mut d: Prefab<T>,
+        system_data: &mut T::SystemData,
+    ) -> Result<ProcessingState<Prefab<T>>, Error> {
+        d.tag = Some(self.next_tag);
+        self.next_tag += 1;
+        if !d.loading() {
+            d.load_sub_assets_for(Some(AssetId::from(handle)), system_data)
+                .with_context(|_| format_err!("Failed starting sub asset loading"))?;
+            let unresolved = d.unresolved();
+            if !unresolved.is_empty() {
+                self.request(handle, unresolved)?;
+            }
+        }
+        if let Some((instances, progress)) = self.resolved.remove(&handle.id()) {
+            d.resolve(instances, progress);
+        }
+        if !d.unresolved().is_empty() {
+            return Ok(ProcessingState::Loading(d));
+        }
+
+        let nested = d
+            .nested_progress()
+            .map_or(Completion::Complete, ProgressCounter::complete);
+        let result = match (d.progress().complete(), nested) {
+            (Completion::Failed, _) => {
+                error!("Failed loading sub asset: {:?}", d.progress().errors());
+                Err(Error::from_string("Failed loading sub asset"))
+            }
+            (_, Completion::Failed) => {
+                let errors = d.nested_progress().map(ProgressCounter::errors);
+                error!("Failed loading nested prefab: {:?}", errors);
+                Err(Error::from_string("Failed loading nested prefab"))
+            }
+            (Completion::Complete, Completion::Complete) => Ok(ProcessingState::Loaded(d)),
+            _ => return Ok(ProcessingState::Loading(d)),
+        };
+        self.chains.remove(&handle.id());
+        result
+    }
+
+    fn request(
+        &mut self,
+        handle: &Handle<Prefab<T>>,
+        unresolved: Vec<(usize, String)>,
+    ) -> Result<(), Error> {
+        if self.load.is_none() {
+            return Err(format_err!(
+                "Prefab references the prefabs {:?}, but no format to load them was set with \
+                 `PrefabLoaderSystemDesc::with_format`",
+                unresolved.iter().map(|(_, name)| name).collect::<Vec<_>>(),
+            ));
+        }
+        if let Some(chain) = self.chains.get(&handle.id()) {
+            if let Some((_, name)) = unresolved.iter().find(|(_, name)| chain.contains(name)) {
+                let chain = chain.clone();
+                self.chains.remove(&handle.id());
+                return Err(format_err!(
+                    "Prefab {:?} references itself through {:?}",
+                    name,
+                    chain
+                ));
+            }
+        }
+        self.requests.push((handle.clone(), unresolved));
+        Ok(())
+    }
+
+    /// Applies reloaded prefabs to their instances and forgets the instances of freed prefabs.
+    #[allow(clippy::too_many_arguments)]
+    fn reinstantiate(
+        &mut self,
+        dropped: &[(u32, Prefab<T>)],
+        entities: &Entities<'a>,
+        storage: &AssetStorage<Prefab<T>>,
+        handles: &ReadStorage<'a, Handle<Prefab<T>>>,
+        parents: &mut WriteStorage<'a, Parent>,
+        tags: &mut WriteStorage<'a, PrefabTag<T>>,
+        system_data: &mut T::SystemData,
+    ) {
+        // Nested prefabs reloaded in the same frame are flattened in their previous version.
+        let previous = |handle: &Handle<Prefab<T>>| {
+            dropped
+                .iter()
+                .find(|(id, _)| *id == handle.id())
+                .map(|(_, prefab)| prefab)
+                .or_else(|| storage.get(handle))
+        };
+        for (id, old) in dropped {
+            let prefab = match storage.get_by_id(*id) {
+                Some(prefab) => prefab,
+                None => {
+                    self.instances.remove(id);
+                    continue;
+                }
+            };
+            let instances = match self.instances.get_mut(id) {
+                Some(instances) => instances,
+                None => continue,
+            };
+            instances.retain(|(root, _)| {
+                entities.is_alive(*root) && handles.get(*root).map(Handle::id) == Some(*id)
+            });
+
+            let old = Flattened::new(old, &previous);
+            let new = Flattened::new(prefab, &|h| storage.get(h));
+            let tag = prefab
+                .tag
+                .expect("Unreachable: Every loaded prefab should have a `PrefabTag`");
+            for (_, instance) in instances.iter_mut() {
+                if let Err(e) = old.remove_from_entities(instance, system_data) {
+                    error!("Failed removing reloaded prefab data: {}", e);
+                }
+                // Entities deleted at runtime are created again, so the indices still match.
+                for entity in instance.iter_mut() {
+                    if !entities.is_alive(*entity) {
+                        *entity = entities.create();
+                    }
+                }
+                for entity in instance.drain(new.len().min(instance.len())..) {
+                    if let Err(e) = entities.delete(entity) {
+                        error!("Failed deleting prefab entity: {}", e);
+                    }
+                }
+                let created = new.len() - instance.len();
+                instance.extend((0..created).map(|_| entities.create()));
+                link(&new, instance, parents, tags, tag);
+                if let Err(e) = new.add_to_entities(instance, system_data) {
+                    error!("Failed adding reloaded prefab data: {}", e);
+                }
+            }
+        }
+    }
+
+    /// Starts loading the nested prefabs requested while processing.
+    fn load_requested(&mut self, loader: &Loader, storage: &AssetStorage<Prefab<T>>) {
+        let load = match self.load {
+            Some(ref load) => load,
+            None => return,
+        };
+        for (handle, unresolved) in self.requests.drain(..) {
+            let chain = self.chains.get(&handle.id()).cloned().unwrap_or_default();
+            let mut progress = ProgressCounter::with_dependent(AssetId::from(&handle));
+            let mut instances = Vec::with_capacity(unresolved.len());
+            for (index, name) in unresolved {
+                let nested = load(loader, name.clone(), &mut progress, storage);
+                let mut nested_chain = chain.clone();
+                nested_chain.push(name);
+                self.chains.insert(nested.id(), nested_chain);
+                instances.push((index, nested));
+            }
+            self.resolved.insert(handle.id(), (instances, progress));
+        }
+    }
+}
+
+/// A prefab with all nested prefabs merged into it.
+struct Flattened<'p, T> {
+    /// Parent of every entity, by index.
+    parents: Vec<Option<usize>>,
+    /// Indices of the entities of the prefab and every nested prefab, with the entity the
+    /// prefab is instantiated on first.
+    scopes: Vec<Vec<usize>>,
+    /// Entity index, data and scope index, in the order the data is added.
+    data: Vec<(usize, &'p T, usize)>,
+}
+
+impl<'p, T> Flattened<'p, T> {
+    /// Flattens the prefab, looking up nested prefabs with `get`.
+    ///
+    /// Nested prefabs are added to their entity before the data of the entity, and the
+    /// overrides of their entity are added after it.
+    fn new<G>(prefab: &'p Prefab<T>, get: &G) -> Self
+    where
+        G: Fn(&Handle<Prefab<T>>) -> Option<&'p Prefab<T>>,
+    {
+        let mut flattened = Flattened {
+            parents: vec![None],
+            scopes: Vec::new(),
+            data: Vec::new(),
+        };
+        flattened.add(prefab, &[], 0, get);
+        flattened
+    }
+
+    fn len(&self) -> usize {
+        self.parents.len()
+    }
+
+    fn add<G>(
+        &mut self,
+        prefab: &'p Prefab<T>,
+        overrides: &'p [PrefabOverride<T>],
+        main: usize,
+        get: &G,
+    ) where
+        G: Fn(&Handle<Prefab<T>>) -> Option<&'p Prefab<T>>,
+    {
+        let offset = self.parents.len();
+        let mut scope = vec![main];
+        scope.extend((1..prefab.entities.len()).map(|i| offset + i - 1));
+        for entity_data in prefab.entities.iter().skip(1) {
+            self.parents
+                .push(entity_data.parent.map(|parent| scope[parent]));
+        }
+        let scope_index = self.scopes.len();
+        self.scopes.push(scope.clone());
+
+        for (index, entity_data) in prefab.entities.iter().enumerate() {
+            if let Some(nested) = entity_data.instance.as_ref().and_then(get) {
+                self.add(nested, &entity_data.overrides, scope[index], get);
+            }
+            let data = entity_data.data.iter().chain(
+                overrides
+                    .iter()
+                    .filter(|o| o.entity == index)
+                    .map(|o| &o.data),
+            );
+            for prefab_data in data {
+                self.data.push((scope[index], prefab_data, scope_index));
+            }
+        }
+    }
+
+    /// Adds the prefab data to the given entities, which are ordered by their index.
+    fn add_to_entities<'a>(
+        &self,
+        entities: &[Entity],
+        system_data: &mut T::SystemData,
+    ) -> Result<(), Error>
+    where
+        T: PrefabData<'a>,
+    {
+        let scopes = self
+            .scopes
+            .iter()
+            .map(|scope| scope.iter().map(|&i| entities[i]).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        for &(index, prefab_data, scope) in &self.data {
+            let children = self.scopes[scope]
+                .iter()
+                .skip(1)
+                .filter(|&&child| self.parents[child] == Some(index))
+                .map(|&child| entities[child])
+                .collect::<Vec<_>>();
+            prefab_data.add_to_entity(entities[index], system_data, &scopes[scope], &children)?;
+        }
+        Ok(())
+    }
+
+    /// Removes the prefab data from the given entities, which are ordered by their index.
+    fn remove_from_entities<'a>(
+        &self,
+        entities: &[Entity],
+        system_data: &mut T::SystemData,
+    ) -> Result<(), Error>
+    where
+        T: PrefabData<'a>,
+    {
+        for &(index, prefab_data, _) in &self.data {
+            if let Some(&entity) = entities.get(index) {
+                prefab_data.remove_from_entity(entity, system_data)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Sets `Parent` and `PrefabTag` of all entities but the main entity.
+fn link<'a, T>(
+    flattened: &Flattened<'_, T>,
+    entities: &[Entity],
+    parents: &mut WriteStorage<'a, Parent>,
+    tags: &mut WriteStorage<'a, PrefabTag<T>>,
+    tag: u64,
+) where
+    T: Send + Sync + 'static,
+{
+    for (index, &entity) in entities.iter().enumerate().skip(1) {
+        match flattened.parents[index] {
+            Some(parent) => {
+                parents
+                    .insert(
+                        entity,
+                        Parent {
+                            entity: entities[parent],
+                        },
+                    )
+                    .expect("Unable to insert `Parent` for prefab");
+            }
+            None => {
+                parents.remove(entity);
+            }
+        }
+        tags.insert(entity, PrefabTag::new(tag))
+            .expect("Unable to insert `PrefabTag` for prefab entity");
+    }
 }
 
 impl<'a, T> System<'a> for PrefabLoaderSystem<T>
@@ -84,6 +455,7 @@ where
         Option<Read<'a, HotReloadStrategy>>,
         WriteStorage<'a, Parent>,
         WriteStorage<'a, PrefabTag<T>>,
+        ReadExpect<'a, Loader>,
         T::SystemData,
     );
 
@@ -100,33 +472,35 @@ where
             strategy,
             mut parents,
             mut tags,
+            loader,
             mut prefab_system_data,
         ) = data;
         let strategy = strategy.as_deref();
-        prefab_storage.process(
-            |mut d| {
-                d.tag = Some(self.next_tag);
-                self.next_tag += 1;
-                if !d.loading()
-                    && !d
-                        .load_sub_assets(&mut prefab_system_data)
-                        .with_context(|_| format_err!("Failed starting sub asset loading"))?
-                {
-                    return Ok(ProcessingState::Loaded(d));
-                }
-                match d.progress().complete() {
-                    Completion::Complete => Ok(ProcessingState::Loaded(d)),
-                    Completion::Failed => {
-                        error!("Failed loading sub asset: {:?}", d.progress().errors());
-                        Err(Error::from_string("Failed loading sub asset"))
-                    }
-                    Completion::Loading => Ok(ProcessingState::Loading(d)),
+        let reinstantiate = self.reinstantiate;
+        let mut dropped = Vec::new();
+        prefab_storage.process_with_handle(
+            |handle, d| self.process(handle, d, &mut prefab_system_data),
+            |handle, prefab| {
+                if reinstantiate {
+                    dropped.push((handle.id(), prefab));
                 }
             },
             time.frame_number(),
             &**pool,
             strategy,
         );
+        self.load_requested(&loader, &prefab_storage);
+        if !dropped.is_empty() {
+            self.reinstantiate(
+                &dropped,
+                &entities,
+                &prefab_storage,
+                &prefab_handles,
+                &mut parents,
+                &mut tags,
+                &mut prefab_system_data,
+            );
+        }
         prefab_handles
             .channel()
             .read(&mut self.insert_reader)
@@ -139,54 +513,28 @@ where
         for (root_entity, handle, _) in (&*entities, &prefab_handles, &self.to_process).join() {
             if let Some(prefab) = prefab_storage.get(handle) {
                 self.finished.push(root_entity);
-                // create entities
+                let flattened = Flattened::new(prefab, &|h| prefab_storage.get(h));
                 self.entities.clear();
                 self.entities.push(root_entity);
-
-                let mut children = HashMap::new();
-                for entity_data in prefab.entities.iter().skip(1) {
-                    let new_entity = entities.create();
-                    self.entities.push(new_entity);
-                    if let Some(parent) = entity_data.parent {
-                        parents
-                            .insert(
-                                new_entity,
-                                Parent {
-                                    entity: self.entities[parent],
-                                },
-                            )
-                            .expect("Unable to insert `Parent` for prefab");
-
-                        children
-                            .entry(parent)
-                            .or_insert_with(Vec::new)
-                            .push(new_entity);
-                    }
-                    tags.insert(
-                        new_entity,
-                        PrefabTag::new(
-                            prefab.tag.expect(
-                                "Unreachable: Every loaded prefab should have a `PrefabTag`",
-                            ),
-                        ),
-                    )
-                    .expect("Unable to insert `PrefabTag` for prefab entity");
-                }
-                // create components
-                for (index, entity_data) in prefab.entities.iter().enumerate() {
-                    if let Some(ref prefab_data) = &entity_data.data {
-                        prefab_data
-                            .add_to_entity(
-                                self.entities[index],
-                                &mut prefab_system_data,
-                                &self.entities,
-                                children
-                                    .get(&index)
-                                    .map(|children| &children[..])
-                                    .unwrap_or(&[]),
-                            )
-                            .expect("Unable to add prefab system data to entity");
-                    }
+                self.entities
+                    .extend((1..flattened.len()).map(|_| entities.create()));
+                link(
+                    &flattened,
+                    &self.entities,
+                    &mut parents,
+                    &mut tags,
+                    prefab
+                        .tag
+                        .expect("Unreachable: Every loaded prefab should have a `PrefabTag`"),
+                );
+                flattened
+                    .add_to_entities(&self.entities, &mut prefab_system_data)
+                    .expect("Unable to add prefab system data to entity");
+                if self.reinstantiate {
+                    self.instances
+                        .entry(handle.id())
+                        .or_default()
+                        .push((root_entity, self.entities.clone()));
                 }
             }
         }
@@ -1,12 +1,17 @@
-use std::sync::{
-    atomic::{AtomicUsize, Ordering},
-    Arc,
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
 };
 
 use amethyst_error::Error;
 use log::error;
 use parking_lot::Mutex;
 
+use crate::{Asset, AssetId, Handle};
+
 /// Completion status, returned by `ProgressCounter::complete`.
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum Completion {
@@ -18,6 +23,53 @@ pub enum Completion {
     Loading,
 }
 
+/// Loading state of a single asset.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum LoadState {
+    /// Waiting for a worker thread to pick up the request.
+    Queued,
+    /// Waiting for the first bytes of the asset to be read from its source.
+    Reading,
+    /// Converting the bytes into asset data using the asset's format.
+    Importing,
+    /// Waiting for the asset storage to turn the data into the asset.
+    Processing,
+    /// Loaded successfully.
+    Loaded,
+    /// Failed to load.
+    Failed,
+    /// Cancelled because all handles to the asset were dropped before it was imported.
+    Cancelled,
+}
+
+/// Describes the asset a `Tracker` is created for.
+#[derive(Clone, Debug)]
+pub struct TrackedAsset {
+    /// Id of the asset's handle.
+    pub handle_id: u32,
+    /// `Asset::NAME` of the asset type.
+    pub asset_type_name: &'static str,
+    /// Name of the asset, usually its path.
+    pub name: String,
+    /// Name of the format the asset is loaded with, if it is loaded from a source.
+    pub format_name: Option<&'static str>,
+    /// Id of the source the asset is loaded from, `""` being the default source.
+    pub source: Option<String>,
+}
+
+impl TrackedAsset {
+    /// Describes an asset loaded from data instead of a source.
+    pub(crate) fn data<A: Asset>(handle: &Handle<A>) -> Self {
+        TrackedAsset {
+            handle_id: handle.id(),
+            asset_type_name: A::NAME,
+            name: "<Data>".into(),
+            format_name: None,
+            source: None,
+        }
+    }
+}
+
 /// The `Progress` trait, allowing to track which assets are
 /// imported already.
 pub trait Progress {
@@ -31,6 +83,23 @@ pub trait Progress {
 
     /// Creates a `Tracker`.
     fn create_tracker(self) -> Self::Tracker;
+
+    /// Creates a `Tracker` for the given asset, which is what the `Loader` uses.
+    ///
+    /// Implement this to keep track of single assets, it defaults to `create_tracker`.
+    fn create_asset_tracker(self, _asset: TrackedAsset) -> Self::Tracker
+    where
+        Self: Sized,
+    {
+        self.create_tracker()
+    }
+
+    /// The asset waiting for the tracked assets to load, if any.
+    ///
+    /// The `Loader` records the tracked assets as dependencies of this asset.
+    fn dependent(&self) -> Option<AssetId> {
+        None
+    }
 }
 
 impl Progress for () {
@@ -41,12 +110,83 @@ impl Progress for () {
     fn create_tracker(self) {}
 }
 
+/// Loading progress of a single asset tracked by a `ProgressCounter`.
+#[derive(Clone, Debug)]
+pub struct AssetProgress {
+    asset: TrackedAsset,
+    bytes_read: usize,
+    transitions: Vec<(LoadState, Instant)>,
+}
+
+impl AssetProgress {
+    fn new(asset: TrackedAsset) -> Self {
+        AssetProgress {
+            asset,
+            bytes_read: 0,
+            transitions: vec![(LoadState::Queued, Instant::now())],
+        }
+    }
+
+    fn enter(&mut self, state: LoadState) {
+        if self.state() != state {
+            self.transitions.push((state, Instant::now()));
+        }
+    }
+
+    /// Returns the asset.
+    pub fn asset(&self) -> &TrackedAsset {
+        &self.asset
+    }
+
+    /// Returns the current state of the asset.
+    pub fn state(&self) -> LoadState {
+        self.transitions.last().map(|&(state, _)| state).unwrap()
+    }
+
+    /// Returns the number of bytes read from the asset's source so far.
+    pub fn bytes_read(&self) -> usize {
+        self.bytes_read
+    }
+
+    /// Returns the time the asset spent in the given state, `None` if it never was in it.
+    ///
+    /// For the current state, this is the time since entering it.
+    pub fn time_in(&self, state: LoadState) -> Option<Duration> {
+        let i = self.transitions.iter().position(|&(s, _)| s == state)?;
+        let end = self
+            .transitions
+            .get(i + 1)
+            .map(|&(_, time)| time)
+            .unwrap_or_else(Instant::now);
+        Some(end - self.transitions[i].1)
+    }
+
+    /// Returns the time from queueing the asset until it loaded, failed or was cancelled, or
+    /// until now if it is still loading.
+    pub fn total_time(&self) -> Duration {
+        let end = match self.state() {
+            LoadState::Loaded | LoadState::Failed | LoadState::Cancelled => {
+                self.transitions.last().unwrap().1
+            }
+            _ => Instant::now(),
+        };
+        end - self.transitions[0].1
+    }
+}
+
 /// A progress tracker which is passed to the `Loader`
 /// in order to check how many assets are loaded.
+///
+/// Besides counting the assets, it keeps an `AssetProgress` with the state, the number of
+/// bytes read and stage timings for every asset, which is useful for loading screens.
 #[derive(Default, Debug)]
 pub struct ProgressCounter {
+    assets: Arc<Mutex<Vec<AssetProgress>>>,
+    bytes_read: Arc<AtomicUsize>,
+    dependent: Option<AssetId>,
     errors: Arc<Mutex<Vec<AssetErrorMeta>>>,
     num_assets: usize,
+    num_cancelled: Arc<AtomicUsize>,
     num_failed: Arc<AtomicUsize>,
     num_loading: Arc<AtomicUsize>,
 }
@@ -57,12 +197,44 @@ impl ProgressCounter {
         Default::default()
     }
 
+    /// Creates a new `Progress` struct for loading the dependencies of the given asset.
+    pub fn with_dependent(dependent: AssetId) -> Self {
+        ProgressCounter {
+            dependent: Some(dependent),
+            ..Default::default()
+        }
+    }
+
+    /// Returns the asset waiting for the tracked assets, if any.
+    pub fn dependent(&self) -> Option<AssetId> {
+        self.dependent
+    }
+
     /// Removes all errors and returns them.
     pub fn errors(&self) -> Vec<AssetErrorMeta> {
         let mut lock = self.errors.lock();
         lock.drain(..).collect()
     }
 
+    /// Returns the progress of every asset loaded by the `Loader` with this counter.
+    pub fn assets(&self) -> Vec<AssetProgress> {
+        self.assets.lock().clone()
+    }
+
+    /// Returns the number of tracked assets in the given state.
+    pub fn num_in_state(&self, state: LoadState) -> usize {
+        self.assets
+            .lock()
+            .iter()
+            .filter(|asset| asset.state() == state)
+            .count()
+    }
+
+    /// Returns the number of bytes read from sources for the tracked assets so far.
+    pub fn bytes_read(&self) -> usize {
+        self.bytes_read.load(Ordering::Relaxed)
+    }
+
     /// Returns the number of assets this struct is tracking.
     pub fn num_assets(&self) -> usize {
         self.num_assets
@@ -73,6 +245,12 @@ impl ProgressCounter {
         self.num_failed.load(Ordering::Relaxed)
     }
 
+    /// Returns the number of assets whose loading was cancelled, because all handles to them
+    /// were dropped. Cancelled assets don't count as failed.
+    pub fn num_cancelled(&self) -> usize {
+        self.num_cancelled.load(Ordering::Relaxed)
+    }
+
     /// Returns the number of assets that are still loading.
     pub fn num_loading(&self) -> usize {
         self.num_loading.load(Ordering::Relaxed)
@@ -80,7 +258,7 @@ impl ProgressCounter {
 
     /// Returns the number of assets that have successfully loaded.
     pub fn num_finished(&self) -> usize {
-        self.num_assets - self.num_loading() - self.num_failed()
+        self.num_assets - self.num_loading() - self.num_failed() - self.num_cancelled()
     }
 
     /// Returns `Completion::Complete` if all tracked assets are finished.
@@ -99,6 +277,20 @@ impl ProgressCounter {
     pub fn is_complete(&self) -> bool {
         self.complete() == Completion::Complete
     }
+
+    fn tracker(&self, asset: Option<usize>) -> ProgressCounterTracker {
+        self.num_loading.fetch_add(1, Ordering::Relaxed);
+
+        ProgressCounterTracker {
+            asset,
+            assets: self.assets.clone(),
+            bytes_read: self.bytes_read.clone(),
+            errors: self.errors.clone(),
+            num_cancelled: self.num_cancelled.clone(),
+            num_failed: self.num_failed.clone(),
+            num_loading: self.num_loading.clone(),
+        }
+    }
 }
 
 impl<'a> Progress for &'a mut ProgressCounter {
@@ -109,29 +301,45 @@ impl<'a> Progress for &'a mut ProgressCounter {
     }
 
     fn create_tracker(self) -> Self::Tracker {
-        let errors = self.errors.clone();
-        let num_failed = self.num_failed.clone();
-        let num_loading = self.num_loading.clone();
-        num_loading.fetch_add(1, Ordering::Relaxed);
+        self.tracker(None)
+    }
 
-        ProgressCounterTracker {
-            errors,
-            num_failed,
-            num_loading,
-        }
+    fn create_asset_tracker(self, asset: TrackedAsset) -> Self::Tracker {
+        let index = {
+            let mut assets = self.assets.lock();
+            assets.push(AssetProgress::new(asset));
+            assets.len() - 1
+        };
+        self.tracker(Some(index))
+    }
+
+    fn dependent(&self) -> Option<AssetId> {
+        self.dependent
     }
 }
 
 /// Progress tracker for `ProgressCounter`.
 #[derive(Default, Debug)]
 pub struct ProgressCounterTracker {
+    asset: Option<usize>,
+    assets: Arc<Mutex<Vec<AssetProgress>>>,
+    bytes_read: Arc<AtomicUsize>,
     errors: Arc<Mutex<Vec<AssetErrorMeta>>>,
+    num_cancelled: Arc<AtomicUsize>,
     num_failed: Arc<AtomicUsize>,
     num_loading: Arc<AtomicUsize>,
 }
 
+impl ProgressCounterTracker {
+    fn with_asset<R>(&self, f: impl FnOnce(&mut AssetProgress) -> R) -> Option<R> {
+        let index = self.asset?;
+        self.assets.lock().get_mut(index).map(f)
+    }
+}
+
 impl Tracker for ProgressCounterTracker {
     fn success(self: Box<Self>) {
+        self.with_asset(|asset| asset.enter(LoadState::Loaded));
         self.num_loading.fetch_sub(1, Ordering::Relaxed);
     }
 
@@ -143,11 +351,19 @@ impl Tracker for ProgressCounterTracker {
         error: Error,
     ) {
         show_error(handle_id, asset_type_name, &asset_name, &error);
+        let (format_name, source) = self
+            .with_asset(|asset| {
+                asset.enter(LoadState::Failed);
+                (asset.asset.format_name, asset.asset.source.clone())
+            })
+            .unwrap_or((None, None));
         self.errors.lock().push(AssetErrorMeta {
             error,
             handle_id,
             asset_type_name,
             asset_name,
+            format_name,
+            source,
         });
         self.num_failed.fetch_add(1, Ordering::Relaxed);
 
@@ -155,14 +371,45 @@ impl Tracker for ProgressCounterTracker {
         // the assets that are still loading.
         self.num_loading.fetch_sub(1, Ordering::Relaxed);
     }
+
+    fn cancelled(self: Box<Self>) {
+        self.with_asset(|asset| asset.enter(LoadState::Cancelled));
+        self.num_cancelled.fetch_add(1, Ordering::Relaxed);
+        self.num_loading.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    fn state_changed(&self, state: LoadState) {
+        self.with_asset(|asset| asset.enter(state));
+    }
+
+    fn bytes_read(&self, bytes: usize) {
+        self.bytes_read.fetch_add(bytes, Ordering::Relaxed);
+        self.with_asset(|asset| asset.bytes_read += bytes);
+    }
 }
 
+/// An error which occurred while loading an asset, as returned by `ProgressCounter::errors`.
 #[derive(Debug)]
 pub struct AssetErrorMeta {
+    /// The error.
     pub error: Error,
+    /// Id of the asset's handle.
     pub handle_id: u32,
+    /// `Asset::NAME` of the asset type.
     pub asset_type_name: &'static str,
+    /// Name of the asset, usually its path.
     pub asset_name: String,
+    /// Name of the format the asset was loaded with, if known.
+    pub format_name: Option<&'static str>,
+    /// Id of the source the asset was loaded from, if known.
+    pub source: Option<String>,
+}
+
+impl AssetErrorMeta {
+    /// Returns the messages of the error and all of its causes, outermost first.
+    pub fn chain(&self) -> Vec<String> {
+        self.error.causes().map(|e| e.to_string()).collect()
+    }
 }
 
 /// The `Tracker` trait which will be used by the loader to report
@@ -179,6 +426,21 @@ pub trait Tracker: Send + 'static {
         asset_name: String,
         error: Error,
     );
+
+    /// Called if loading the asset was cancelled, because all handles to it were dropped
+    /// before it was imported.
+    ///
+    /// Defaults to `success`, so trackers which only count finished assets don't wait for it.
+    fn cancelled(self: Box<Self>) {
+        self.success();
+    }
+
+    /// Called when the asset enters a new `LoadState`, before it finally loads, fails or is
+    /// cancelled.
+    fn state_changed(&self, _state: LoadState) {}
+
+    /// Called whenever bytes of the asset were read from its source.
+    fn bytes_read(&self, _bytes: usize) {}
 }
 
 impl Tracker for () {
@@ -210,7 +472,7 @@ fn show_error(handle_id: u32, asset_type_name: &'static str, asset_name: &str, e
 mod tests {
     use amethyst_error::Error;
 
-    use super::{Completion, Progress, ProgressCounter, Tracker};
+    use super::{Completion, LoadState, Progress, ProgressCounter, TrackedAsset, Tracker};
 
     #[test]
     fn progress_counter_complete_returns_correct_completion_status_when_loading_or_complete() {
@@ -288,4 +550,45 @@ mod tests {
         tracker_2.success();
         assert_eq!(2, progress.num_finished());
     }
+
+    #[test]
+    fn progress_counter_tracks_asset_states() {
+        let mut progress_counter = ProgressCounter::new();
+        let mut progress = &mut progress_counter;
+        progress.add_assets(1);
+        let tracker = Box::new(progress.create_asset_tracker(TrackedAsset {
+            handle_id: 0,
+            asset_type_name: "AssetType",
+            name: String::from("test.asset"),
+            format_name: Some("Format"),
+            source: Some(String::new()),
+        }));
+
+        assert_eq!(1, progress.num_in_state(LoadState::Queued));
+        tracker.state_changed(LoadState::Reading);
+        tracker.bytes_read(10);
+        tracker.state_changed(LoadState::Importing);
+        tracker.bytes_read(5);
+        tracker.state_changed(LoadState::Importing);
+
+        let asset = &progress.assets()[0];
+        assert_eq!(LoadState::Importing, asset.state());
+        assert_eq!(15, asset.bytes_read());
+        assert!(asset.time_in(LoadState::Processing).is_none());
+
+        tracker.fail(
+            0,
+            "AssetType",
+            String::from("test.asset"),
+            Error::from_string("outer"),
+        );
+        let asset = &progress.assets()[0];
+        assert_eq!(LoadState::Failed, asset.state());
+        assert!(asset.time_in(LoadState::Reading).unwrap() <= asset.total_time());
+        assert_eq!(15, progress.bytes_read());
+
+        let errors = progress.errors();
+        assert_eq!(Some("Format"), errors[0].format_name);
+        assert_eq!(vec!["outer"], errors[0].chain());
+    }
 }
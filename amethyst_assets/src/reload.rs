@@ -1,11 +1,16 @@
 //! Defines the `Reload` trait.
 
-use std::{sync::Arc, time::Instant};
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use derive_new::new;
+use fnv::{FnvHashMap, FnvHashSet};
 
 use amethyst_core::{
     ecs::prelude::{DispatcherBuilder, Read, System, SystemData, World, Write},
+    ecs::ReadExpect,
     SystemBundle, SystemDesc, Time,
 };
 use amethyst_error::Error;
@@ -87,6 +92,26 @@ impl HotReloadStrategy {
         }
     }
 
+    /// Reloads assets whose files were reported as changed by their `Source`.
+    ///
+    /// Instead of polling every loaded asset, this only reloads assets the sources reported
+    /// changes for, see `Source::poll_changes` and `Directory::watched`. A file is reloaded once
+    /// no further changes were reported for it during `debounce`, so editors writing a file in
+    /// several steps only trigger a single reload.
+    ///
+    /// Assets loaded from sources which don't support change notifications are never reloaded
+    /// with this strategy.
+    pub fn when_changed(debounce: Duration) -> Self {
+        HotReloadStrategy {
+            inner: HotReloadStrategyInner::Changed {
+                debounce,
+                pending: Default::default(),
+                changed: Default::default(),
+                frame_number: 0,
+            },
+        }
+    }
+
     /// Never do any hot-reloading.
     pub fn never() -> Self {
         HotReloadStrategy {
@@ -112,9 +137,19 @@ impl HotReloadStrategy {
         match self.inner {
             HotReloadStrategyInner::Every { frame_number, .. } => frame_number == current_frame,
             HotReloadStrategyInner::Trigger { frame_number, .. } => frame_number == current_frame,
+            HotReloadStrategyInner::Changed { frame_number, .. } => frame_number == current_frame,
             HotReloadStrategyInner::Never => false,
         }
     }
+
+    /// Crate-internal method returning the paths to be reloaded, if the strategy
+    /// is only reloading changed files.
+    pub(crate) fn changed(&self) -> Option<&FnvHashSet<String>> {
+        match self.inner {
+            HotReloadStrategyInner::Changed { ref changed, .. } => Some(changed),
+            _ => None,
+        }
+    }
 }
 
 impl Default for HotReloadStrategy {
@@ -134,6 +169,12 @@ enum HotReloadStrategyInner {
         triggered: bool,
         frame_number: u64,
     },
+    Changed {
+        debounce: Duration,
+        pending: FnvHashMap<String, Instant>,
+        changed: FnvHashSet<String>,
+        frame_number: u64,
+    },
     Never,
 }
 
@@ -160,9 +201,13 @@ impl<'a, 'b> SystemDesc<'a, 'b, HotReloadSystem> for HotReloadSystemDesc {
 pub struct HotReloadSystem;
 
 impl<'a> System<'a> for HotReloadSystem {
-    type SystemData = (Read<'a, Time>, Write<'a, HotReloadStrategy>);
+    type SystemData = (
+        Read<'a, Time>,
+        Write<'a, HotReloadStrategy>,
+        ReadExpect<'a, Loader>,
+    );
 
-    fn run(&mut self, (time, mut strategy): Self::SystemData) {
+    fn run(&mut self, (time, mut strategy, loader): Self::SystemData) {
         #[cfg(feature = "profiler")]
         profile_scope!("hot_reload_system");
 
@@ -186,6 +231,33 @@ impl<'a> System<'a> for HotReloadSystem {
                     *last = Instant::now();
                 }
             }
+            HotReloadStrategyInner::Changed {
+                debounce,
+                ref mut pending,
+                ref mut changed,
+                ref mut frame_number,
+            } => {
+                let now = Instant::now();
+                for path in loader.poll_changes() {
+                    pending.insert(path, now);
+                }
+
+                // Keep the current batch until the storages had a chance to reload it.
+                if changed.is_empty() || time.frame_number() > *frame_number {
+                    changed.clear();
+                    pending.retain(|path, last| {
+                        if now.duration_since(*last) >= debounce {
+                            changed.insert(path.clone());
+                            false
+                        } else {
+                            true
+                        }
+                    });
+                    if !changed.is_empty() {
+                        *frame_number = time.frame_number() + 1;
+                    }
+                }
+            }
             HotReloadStrategyInner::Never => {}
         }
     }
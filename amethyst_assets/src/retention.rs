@@ -0,0 +1,194 @@
+//! Keeps recently dropped assets around so they can be reused without loading them again.
+
+use std::marker::PhantomData;
+
+use derivative::Derivative;
+use fnv::FnvHashMap;
+
+use crate::{Asset, Handle};
+
+/// Policy deciding which assets an `AssetStorage` keeps after all handles to them were dropped.
+///
+/// Without a policy, assets are freed as soon as the last `Handle` is dropped. With a policy,
+/// assets loaded through the `Loader` are retained until their combined size, as reported by
+/// `Asset::size_hint`, exceeds the budget. The least recently dropped assets are evicted first.
+///
+/// Loading a retained asset again with the same name, format and source returns the retained
+/// asset immediately instead of loading it from its source.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct CachePolicy {
+    max_retained_bytes: usize,
+}
+
+impl CachePolicy {
+    /// Creates a policy retaining dropped assets of up to `max_bytes` combined size, evicting
+    /// the least recently used assets first.
+    pub fn lru(max_bytes: usize) -> Self {
+        CachePolicy {
+            max_retained_bytes: max_bytes,
+        }
+    }
+
+    /// Returns the maximum combined size of retained assets.
+    pub fn max_retained_bytes(&self) -> usize {
+        self.max_retained_bytes
+    }
+}
+
+/// Memory statistics of the `AssetStorage<A>`.
+///
+/// The default `Processor` keeps this resource up to date after every frame.
+#[derive(Derivative)]
+#[derivative(
+    Clone(bound = ""),
+    Copy(bound = ""),
+    Debug(bound = ""),
+    Default(bound = "")
+)]
+pub struct CacheStats<A> {
+    /// Combined size of all assets in the storage, including retained ones.
+    pub bytes: usize,
+    /// Number of assets which are only kept alive by the cache policy.
+    pub retained: usize,
+    /// Combined size of all retained assets.
+    pub retained_bytes: usize,
+    /// Number of loads which were served by a retained asset.
+    pub hits: u64,
+    /// Number of loads for which no retained asset was available.
+    pub misses: u64,
+    /// Number of retained assets which were evicted to stay within the budget.
+    pub evictions: u64,
+    #[derivative(Debug = "ignore")]
+    marker: PhantomData<A>,
+}
+
+/// Identifies a loaded asset by source, format name and asset name.
+pub(crate) type CacheKey = (String, &'static str, String);
+
+#[derive(Derivative)]
+#[derivative(Default(bound = ""))]
+pub(crate) struct Retention<A> {
+    policy: Option<CachePolicy>,
+    keys: FnvHashMap<u32, CacheKey>,
+    retained: FnvHashMap<CacheKey, Retained<A>>,
+    retained_bytes: usize,
+    tick: u64,
+    hits: u64,
+    misses: u64,
+    evictions: u64,
+}
+
+struct Retained<A> {
+    handle: Handle<A>,
+    size: usize,
+    tick: u64,
+}
+
+impl<A: Asset> Retention<A> {
+    pub(crate) fn policy(&self) -> Option<CachePolicy> {
+        self.policy
+    }
+
+    /// Sets the policy, evicting retained assets which no longer fit.
+    pub(crate) fn set_policy(&mut self, policy: Option<CachePolicy>) {
+        self.policy = policy;
+        if policy.is_some() {
+            self.evict();
+        } else {
+            self.clear();
+        }
+    }
+
+    /// Drops all retained assets and keys, e.g. because the storage was cleared.
+    pub(crate) fn clear(&mut self) {
+        self.keys.clear();
+        self.retained.clear();
+        self.retained_bytes = 0;
+    }
+
+    /// Remembers under which key the asset with the given id is loaded.
+    pub(crate) fn register(&mut self, id: u32, key: CacheKey) {
+        if self.policy.is_some() {
+            self.keys.insert(id, key);
+        }
+    }
+
+    /// Forgets the key of an asset which was freed or failed to load.
+    pub(crate) fn forget(&mut self, id: u32) {
+        self.keys.remove(&id);
+    }
+
+    /// Takes a retained asset out of the cache, if there is one for the given key.
+    pub(crate) fn revive(&mut self, key: &CacheKey) -> Option<Handle<A>> {
+        self.policy?;
+        match self.retained.remove(key) {
+            Some(retained) => {
+                self.hits += 1;
+                self.retained_bytes -= retained.size;
+                Some(retained.handle)
+            }
+            None => {
+                self.misses += 1;
+                None
+            }
+        }
+    }
+
+    /// Retains an asset all user handles of which were dropped.
+    ///
+    /// Assets which weren't loaded through the `Loader` or which are bigger than the whole
+    /// budget are not retained.
+    pub(crate) fn retain(&mut self, handle: &Handle<A>, size: usize) {
+        let max = match self.policy {
+            Some(policy) => policy.max_retained_bytes,
+            None => return,
+        };
+        let key = match self.keys.get(&handle.id()) {
+            Some(key) if size <= max => key.clone(),
+            _ => return,
+        };
+
+        self.tick += 1;
+        self.retained_bytes += size;
+        let replaced = self.retained.insert(
+            key,
+            Retained {
+                handle: handle.clone(),
+                size,
+                tick: self.tick,
+            },
+        );
+        // The same asset was loaded twice, keep the more recently dropped one.
+        if let Some(replaced) = replaced {
+            self.retained_bytes -= replaced.size;
+        }
+    }
+
+    /// Evicts the least recently dropped assets until the retained ones fit into the budget.
+    pub(crate) fn evict(&mut self) {
+        let max = self.policy.map_or(0, |policy| policy.max_retained_bytes());
+        while self.retained_bytes > max {
+            let key = self
+                .retained
+                .iter()
+                .min_by_key(|(_, retained)| retained.tick)
+                .map(|(key, _)| key.clone())
+                .expect("Retained bytes without retained assets");
+            let retained = self.retained.remove(&key).unwrap();
+            self.retained_bytes -= retained.size;
+            self.evictions += 1;
+        }
+    }
+
+    pub(crate) fn stats(&self, bytes: usize) -> CacheStats<A> {
+        CacheStats {
+            bytes,
+            retained: self.retained.len(),
+            retained_bytes: self.retained_bytes,
+            hits: self.hits,
+            misses: self.misses,
+            evictions: self.evictions,
+            marker: PhantomData,
+        }
+    }
+}
@@ -1,9 +1,14 @@
 use std::{
-    fs::File,
+    fs::{self, File},
     path::{Path, PathBuf},
+    sync::mpsc::{channel, Receiver},
     time::UNIX_EPOCH,
 };
 
+use derivative::Derivative;
+use notify::{RawEvent, RecommendedWatcher, RecursiveMode, Watcher};
+use parking_lot::Mutex;
+
 #[cfg(feature = "profiler")]
 use thread_profiler::profile_scope;
 
@@ -17,9 +22,24 @@ use crate::{error, source::Source};
 /// inside the `Loader`, which is automatically used when you call
 /// `load`. In case you want another, second, directory for assets,
 /// you can instantiate one yourself, too. Please use `Loader::load_from` then.
+///
+/// A directory created with `watched` uses file system notifications (e.g. inotify on Linux)
+/// to report changed files, which allows `HotReloadStrategy::when_changed` to reload only
+/// the assets that actually changed.
 #[derive(Debug)]
 pub struct Directory {
     loc: PathBuf,
+    watcher: Option<DirectoryWatcher>,
+}
+
+#[derive(Derivative)]
+#[derivative(Debug)]
+struct DirectoryWatcher {
+    root: PathBuf,
+    events: Mutex<Receiver<RawEvent>>,
+    // Never read, but has to be kept alive for events to be sent.
+    #[derivative(Debug = "ignore")]
+    _watcher: Mutex<RecommendedWatcher>,
 }
 
 impl Directory {
@@ -28,7 +48,37 @@ impl Directory {
     where
         P: Into<PathBuf>,
     {
-        Directory { loc: loc.into() }
+        Directory {
+            loc: loc.into(),
+            watcher: None,
+        }
+    }
+
+    /// Creates a new directory storage which watches the directory for changes.
+    pub fn watched<P>(loc: P) -> Result<Self, Error>
+    where
+        P: Into<PathBuf>,
+    {
+        let loc = loc.into();
+        let root = loc
+            .canonicalize()
+            .with_context(|_| format_err!("Failed to canonicalize {:?}", loc))?;
+
+        let (tx, rx) = channel();
+        let mut watcher = notify::raw_watcher(tx)
+            .with_context(|_| format_err!("Failed to create file system watcher"))?;
+        watcher
+            .watch(&root, RecursiveMode::Recursive)
+            .with_context(|_| format_err!("Failed to watch directory {:?}", root))?;
+
+        Ok(Directory {
+            loc,
+            watcher: Some(DirectoryWatcher {
+                root,
+                events: Mutex::new(rx),
+                _watcher: Mutex::new(watcher),
+            }),
+        })
     }
 
     fn path(&self, s_path: &str) -> PathBuf {
@@ -75,6 +125,54 @@ impl Source for Directory {
 
         Ok(v)
     }
+
+    fn poll_changes(&self) -> Option<Vec<String>> {
+        let watcher = self.watcher.as_ref()?;
+
+        let events = watcher.events.lock();
+        let changes = events
+            .try_iter()
+            .filter_map(|event| event.path)
+            .filter_map(|path| {
+                let relative = path.strip_prefix(&watcher.root).ok()?;
+                let components = relative
+                    .iter()
+                    .map(|c| c.to_str())
+                    .collect::<Option<Vec<_>>>()?;
+                Some(components.join("/"))
+            })
+            .collect();
+
+        Some(changes)
+    }
+}
+
+/// Collects the paths of all files below `root.join(prefix)`, relative to `root` and joined
+/// with `/`.
+pub(crate) fn collect_files(
+    root: &Path,
+    prefix: &str,
+    files: &mut Vec<String>,
+) -> Result<(), Error> {
+    let dir = root.join(prefix);
+    let entries = fs::read_dir(&dir).with_context(|_| format_err!("Failed to read {:?}", dir))?;
+    for entry in entries {
+        let entry = entry.with_context(|_| format_err!("Failed to read {:?}", dir))?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let path = if prefix.is_empty() {
+            name
+        } else {
+            format!("{}/{}", prefix, name)
+        };
+
+        if entry.path().is_dir() {
+            collect_files(root, &path, files)?;
+        } else {
+            files.push(path);
+        }
+    }
+
+    Ok(())
 }
 
 #[cfg(test)]
@@ -98,6 +196,31 @@ mod test {
         );
     }
 
+    #[test]
+    fn watched_directory_reports_changed_files() {
+        use std::{fs, thread, time::Duration};
+
+        let dir =
+            std::env::temp_dir().join(format!("amethyst_assets_watch_{}", std::process::id()));
+        fs::create_dir_all(dir.join("subdir")).unwrap();
+        let directory = Directory::watched(&dir).expect("Failed to watch directory");
+        assert_eq!(Some(Vec::new()), directory.poll_changes());
+
+        fs::write(dir.join("subdir/asset"), b"changed").unwrap();
+        let mut changes = Vec::new();
+        for _ in 0..50 {
+            changes.extend(directory.poll_changes().unwrap());
+            if !changes.is_empty() {
+                break;
+            }
+            thread::sleep(Duration::from_millis(20));
+        }
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(changes.iter().any(|path| path == "subdir/asset"));
+        assert_eq!(None, Directory::new(".").poll_changes());
+    }
+
     #[cfg(windows)]
     #[test]
     fn tolerates_backslashed_location_with_forward_slashed_asset_paths() {
@@ -0,0 +1,198 @@
+use fnv::FnvHashMap;
+use parking_lot::Mutex;
+
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+use amethyst_error::{format_err, Error, ResultExt};
+
+use crate::{error, source::Source};
+
+/// Layered source, stacking several sources on top of each other.
+///
+/// Every path is resolved from the highest-priority layer which has it, so a mod directory
+/// layered on top of the base game's pack can override single files:
+///
+/// ```rust,ignore
+/// let source = LayeredSource::new()
+///     .with_layer(PackSource::open("base.pack")?)
+///     .with_layer(PackSource::open("dlc.pack")?)
+///     .with_layer(Directory::new("mods"));
+/// loader.set_default_source(source);
+/// ```
+///
+/// `modified` reports the modification time of the winning layer. When a different layer
+/// starts (or stops) shadowing a file which has been loaded before, `modified` reports a time
+/// newer than the previously loaded one, so hot reloading picks up the change even if the new
+/// file is older.
+#[derive(Default)]
+pub struct LayeredSource {
+    layers: Vec<Box<dyn Source>>,
+    loaded: Mutex<FnvHashMap<String, (usize, u64)>>,
+}
+
+impl LayeredSource {
+    /// Creates a new layered source without any layers.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Adds a layer on top of all existing layers, giving it the highest priority.
+    pub fn with_layer<S>(mut self, source: S) -> Self
+    where
+        S: Source,
+    {
+        self.push_layer(source);
+        self
+    }
+
+    /// Adds a layer on top of all existing layers, giving it the highest priority.
+    pub fn push_layer<S>(&mut self, source: S)
+    where
+        S: Source,
+    {
+        self.layers.push(Box::new(source));
+    }
+
+    /// Returns the number of layers.
+    pub fn num_layers(&self) -> usize {
+        self.layers.len()
+    }
+
+    /// Returns the index of the layer the given path is resolved from, if any.
+    ///
+    /// Layers are indexed in the order they were added, the first one having index `0`.
+    pub fn resolve(&self, path: &str) -> Option<usize> {
+        self.winner(path).map(|(layer, _)| layer)
+    }
+
+    fn winner(&self, path: &str) -> Option<(usize, u64)> {
+        self.layers
+            .iter()
+            .enumerate()
+            .rev()
+            .find_map(|(i, layer)| layer.modified(path).ok().map(|m| (i, m)))
+    }
+}
+
+impl std::fmt::Debug for LayeredSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LayeredSource")
+            .field("layers", &self.layers.len())
+            .finish()
+    }
+}
+
+impl Source for LayeredSource {
+    fn modified(&self, path: &str) -> Result<u64, Error> {
+        #[cfg(feature = "profiler")]
+        profile_scope!("layered_modified_asset");
+
+        let winner = self.winner(path);
+        let loaded = self.loaded.lock().get(path).cloned();
+
+        match (winner, loaded) {
+            (Some((layer, modified)), Some((loaded_layer, loaded_modified)))
+                if layer != loaded_layer =>
+            {
+                Ok(modified.max(loaded_modified + 1))
+            }
+            (Some((_, modified)), _) => Ok(modified),
+            (None, _) => Err(format_err!("No layer contains {:?}", path)),
+        }
+    }
+
+    fn load(&self, path: &str) -> Result<Vec<u8>, Error> {
+        #[cfg(feature = "profiler")]
+        profile_scope!("layered_load_asset");
+
+        self.load_with_metadata(path).map(|(b, _)| b)
+    }
+
+    fn load_with_metadata(&self, path: &str) -> Result<(Vec<u8>, u64), Error> {
+        #[cfg(feature = "profiler")]
+        profile_scope!("layered_load_asset_with_metadata");
+
+        let (layer, _) = self
+            .winner(path)
+            .ok_or_else(|| format_err!("No layer contains {:?}", path))
+            .with_context(|_| error::Error::Source)?;
+        let (b, m) = self.layers[layer].load_with_metadata(path)?;
+        self.loaded.lock().insert(path.to_owned(), (layer, m));
+
+        Ok((b, m))
+    }
+
+    fn poll_changes(&self) -> Option<Vec<String>> {
+        self.layers
+            .iter()
+            .filter_map(|layer| layer.poll_changes())
+            .fold(None, |changes: Option<Vec<String>>, layer_changes| {
+                let mut changes = changes.unwrap_or_default();
+                changes.extend(layer_changes);
+                Some(changes)
+            })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use crate::source::{Directory, PackBuilder, PackSource, Source};
+
+    use super::LayeredSource;
+
+    fn pack(files: &[(&str, &[u8], u64)]) -> PackSource {
+        let mut builder = PackBuilder::new();
+        for &(name, bytes, modified) in files {
+            builder = builder.add_file(name, bytes.to_vec(), modified);
+        }
+        let mut bytes = Vec::new();
+        builder.write(&mut bytes).unwrap();
+        PackSource::from_bytes(bytes).unwrap()
+    }
+
+    #[test]
+    fn resolves_from_highest_layer() {
+        let test_assets_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/assets");
+        let source = LayeredSource::new()
+            .with_layer(Directory::new(test_assets_dir))
+            .with_layer(pack(&[("subdir/asset", b"mod", 1), ("other", b"other", 1)]));
+
+        assert_eq!(b"mod".to_vec(), source.load("subdir/asset").unwrap());
+        assert_eq!(b"other".to_vec(), source.load("other").unwrap());
+        assert_eq!(Some(1), source.resolve("subdir/asset"));
+        assert!(source.load("missing").is_err());
+    }
+
+    #[test]
+    fn falls_through_to_lower_layer() {
+        let source = LayeredSource::new()
+            .with_layer(pack(&[("a", b"base", 5)]))
+            .with_layer(pack(&[("b", b"mod", 1)]));
+
+        assert_eq!(
+            (b"base".to_vec(), 5),
+            source.load_with_metadata("a").unwrap()
+        );
+        assert_eq!(Some(0), source.resolve("a"));
+        assert_eq!(5, source.modified("a").unwrap());
+    }
+
+    #[test]
+    fn reports_change_when_shadowing_changes() {
+        let mut source = LayeredSource::new().with_layer(pack(&[("a", b"base", 10)]));
+        let (_, modified) = source.load_with_metadata("a").unwrap();
+        assert_eq!(10, modified);
+        assert_eq!(10, source.modified("a").unwrap());
+
+        // An older file in a higher layer still has to trigger a reload.
+        source.push_layer(pack(&[("a", b"mod", 3)]));
+        assert!(source.modified("a").unwrap() > modified);
+
+        let (bytes, modified) = source.load_with_metadata("a").unwrap();
+        assert_eq!(b"mod".to_vec(), bytes);
+        assert_eq!(modified, source.modified("a").unwrap());
+    }
+}
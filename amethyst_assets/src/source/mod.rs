@@ -1,11 +1,19 @@
 use amethyst_error::Error;
 
-pub use self::dir::Directory;
+pub use self::{
+    dir::Directory,
+    layered::LayeredSource,
+    pack::{PackBuilder, PackSource},
+};
+
+pub(crate) use self::dir::collect_files;
 
 #[cfg(feature = "profiler")]
 use thread_profiler::profile_scope;
 
 mod dir;
+mod layered;
+mod pack;
 
 /// A trait for asset sources, which provides
 /// methods for loading bytes.
@@ -32,4 +40,14 @@ pub trait Source: Send + Sync + 'static {
 
         Ok((b, m))
     }
+
+    /// Returns the paths which changed since the last call.
+    ///
+    /// This is used by `HotReloadStrategy::when_changed` to only reload assets whose files
+    /// actually changed, instead of polling `modified` for every loaded asset. The default
+    /// implementation returns `None`, which means the source doesn't support change
+    /// notifications.
+    fn poll_changes(&self) -> Option<Vec<String>> {
+        None
+    }
 }
@@ -0,0 +1,437 @@
+use std::{
+    fs::{self, File},
+    io::{Cursor, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+use flate2::{read::DeflateDecoder, write::DeflateEncoder, Compression};
+use fnv::FnvHashMap;
+use parking_lot::Mutex;
+
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+use amethyst_error::{format_err, Error, ResultExt};
+
+use crate::{error, source::Source};
+
+const MAGIC: &[u8; 4] = b"AMPK";
+const VERSION: u32 = 1;
+
+trait PackReader: Read + Seek + Send + 'static {}
+
+impl<T: Read + Seek + Send + 'static> PackReader for T {}
+
+/// A single entry in the index of a pack.
+#[derive(Clone, Debug)]
+struct PackEntry {
+    modified: u64,
+    offset: u64,
+    stored_len: u64,
+    len: u64,
+    compressed: bool,
+}
+
+/// Pack file source.
+///
+/// Reads assets out of a single archive produced by `PackBuilder`. The index of the archive is
+/// read once when the pack is opened, after which `load` only has to seek to the (possibly
+/// compressed) entry and read it.
+///
+/// Packs are mounted like any other source:
+///
+/// ```rust,ignore
+/// loader.add_source("base", PackSource::open("base.pack")?);
+/// let handle = loader.load_from("textures/logo.png", ImageFormat::default(), "base", (), &storage);
+/// ```
+pub struct PackSource {
+    index: FnvHashMap<String, PackEntry>,
+    reader: Mutex<Box<dyn PackReader>>,
+}
+
+impl PackSource {
+    /// Opens the pack file at the given location and reads its index.
+    pub fn open<P>(path: P) -> Result<Self, Error>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        let file = File::open(path)
+            .with_context(|_| format_err!("Failed to open pack file {:?}", path))
+            .with_context(|_| error::Error::Source)?;
+
+        Self::from_reader(file)
+    }
+
+    /// Creates a pack source from an in-memory pack, e.g. one embedded with `include_bytes!`.
+    pub fn from_bytes(bytes: Vec<u8>) -> Result<Self, Error> {
+        Self::from_reader(Cursor::new(bytes))
+    }
+
+    fn from_reader<R>(mut reader: R) -> Result<Self, Error>
+    where
+        R: Read + Seek + Send + 'static,
+    {
+        let index = read_index(&mut reader)
+            .with_context(|_| format_err!("Failed to read pack index"))
+            .with_context(|_| error::Error::Source)?;
+
+        Ok(PackSource {
+            index,
+            reader: Mutex::new(Box::new(reader)),
+        })
+    }
+
+    /// Returns `true` if the pack contains an entry for the given path.
+    pub fn contains(&self, path: &str) -> bool {
+        self.index.contains_key(path)
+    }
+
+    /// Returns an iterator over the paths of all entries in this pack.
+    pub fn paths(&self) -> impl Iterator<Item = &str> {
+        self.index.keys().map(String::as_str)
+    }
+
+    fn entry(&self, path: &str) -> Result<&PackEntry, Error> {
+        self.index
+            .get(path)
+            .ok_or_else(|| format_err!("No entry {:?} in pack", path))
+    }
+}
+
+impl std::fmt::Debug for PackSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PackSource")
+            .field("entries", &self.index.len())
+            .finish()
+    }
+}
+
+impl Source for PackSource {
+    fn modified(&self, path: &str) -> Result<u64, Error> {
+        #[cfg(feature = "profiler")]
+        profile_scope!("pack_modified_asset");
+
+        Ok(self.entry(path)?.modified)
+    }
+
+    fn load(&self, path: &str) -> Result<Vec<u8>, Error> {
+        #[cfg(feature = "profiler")]
+        profile_scope!("pack_load_asset");
+
+        let entry = self.entry(path).with_context(|_| error::Error::Source)?;
+
+        let mut stored = vec![0; entry.stored_len as usize];
+        {
+            let mut reader = self.reader.lock();
+            reader
+                .seek(SeekFrom::Start(entry.offset))
+                .and_then(|_| reader.read_exact(&mut stored))
+                .with_context(|_| format_err!("Failed to read pack entry {:?}", path))
+                .with_context(|_| error::Error::Source)?;
+        }
+
+        if !entry.compressed {
+            return Ok(stored);
+        }
+
+        let mut v = Vec::with_capacity(entry.len as usize);
+        DeflateDecoder::new(&stored[..])
+            .read_to_end(&mut v)
+            .with_context(|_| format_err!("Failed to decompress pack entry {:?}", path))
+            .with_context(|_| error::Error::Source)?;
+
+        Ok(v)
+    }
+
+    fn load_with_metadata(&self, path: &str) -> Result<(Vec<u8>, u64), Error> {
+        #[cfg(feature = "profiler")]
+        profile_scope!("pack_load_asset_with_metadata");
+
+        let b = self.load(path)?;
+        let m = self.entry(path)?.modified;
+
+        Ok((b, m))
+    }
+}
+
+/// Builder for pack files which can be read by `PackSource`.
+///
+/// ```rust,ignore
+/// PackBuilder::new()
+///     .with_compression(true)
+///     .add_directory("assets")?
+///     .write_file("assets.pack")?;
+/// ```
+#[derive(Debug)]
+pub struct PackBuilder {
+    compression: bool,
+    files: Vec<(String, Vec<u8>, u64)>,
+}
+
+impl Default for PackBuilder {
+    fn default() -> Self {
+        PackBuilder {
+            compression: true,
+            files: Vec::new(),
+        }
+    }
+}
+
+impl PackBuilder {
+    /// Creates a new, empty pack builder. Compression is enabled by default.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Enables or disables compression of the entries added to the pack.
+    ///
+    /// Entries which don't get smaller when compressed are always stored as-is.
+    pub fn with_compression(mut self, compression: bool) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Adds a single entry to the pack.
+    ///
+    /// The path should always use `/` as separator. `modified` is reported by
+    /// `PackSource::modified` for this entry.
+    pub fn add_file<N>(mut self, path: N, bytes: Vec<u8>, modified: u64) -> Self
+    where
+        N: Into<String>,
+    {
+        self.files.push((path.into(), bytes, modified));
+        self
+    }
+
+    /// Adds all files found recursively in the given directory.
+    ///
+    /// Entries are named by their path relative to `dir`, so a `Directory` source and a
+    /// `PackSource` built from the same directory resolve the same paths.
+    pub fn add_directory<P>(mut self, dir: P) -> Result<Self, Error>
+    where
+        P: AsRef<Path>,
+    {
+        let dir = dir.as_ref();
+        let mut pending = vec![dir.to_path_buf()];
+        while let Some(current) = pending.pop() {
+            let read_dir = fs::read_dir(&current)
+                .with_context(|_| format_err!("Failed to read directory {:?}", current))?;
+            for entry in read_dir {
+                let path = entry
+                    .with_context(|_| format_err!("Failed to read directory {:?}", current))?
+                    .path();
+                if path.is_dir() {
+                    pending.push(path);
+                    continue;
+                }
+
+                let name = entry_name(dir, &path)?;
+                let modified = fs::metadata(&path)
+                    .and_then(|m| m.modified())
+                    .with_context(|_| format_err!("Failed to fetch metadata for {:?}", path))?
+                    .duration_since(UNIX_EPOCH)
+                    .map(|d| d.as_secs())
+                    .unwrap_or(0);
+                let bytes =
+                    fs::read(&path).with_context(|_| format_err!("Failed to read {:?}", path))?;
+                self.files.push((name, bytes, modified));
+            }
+        }
+
+        Ok(self)
+    }
+
+    /// Writes the pack to the given writer.
+    pub fn write<W>(self, mut writer: W) -> Result<(), Error>
+    where
+        W: Write,
+    {
+        let mut entries = Vec::with_capacity(self.files.len());
+        let mut blobs = Vec::with_capacity(self.files.len());
+        for (name, bytes, modified) in self.files {
+            let len = bytes.len() as u64;
+            let (stored, compressed) = if self.compression {
+                let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+                encoder
+                    .write_all(&bytes)
+                    .with_context(|_| format_err!("Failed to compress {:?}", name))?;
+                let stored = encoder
+                    .finish()
+                    .with_context(|_| format_err!("Failed to compress {:?}", name))?;
+                if stored.len() < bytes.len() {
+                    (stored, true)
+                } else {
+                    (bytes, false)
+                }
+            } else {
+                (bytes, false)
+            };
+            entries.push((name, modified, stored.len() as u64, len, compressed));
+            blobs.push(stored);
+        }
+
+        let header_len = 4
+            + 4
+            + 4
+            + entries
+                .iter()
+                .map(|(name, ..)| 4 + name.len() as u64 + 8 * 4 + 1)
+                .sum::<u64>();
+
+        let mut header = Vec::with_capacity(header_len as usize);
+        header.extend_from_slice(MAGIC);
+        header.extend_from_slice(&VERSION.to_le_bytes());
+        header.extend_from_slice(&(entries.len() as u32).to_le_bytes());
+        let mut offset = header_len;
+        for (name, modified, stored_len, len, compressed) in entries {
+            header.extend_from_slice(&(name.len() as u32).to_le_bytes());
+            header.extend_from_slice(name.as_bytes());
+            header.extend_from_slice(&modified.to_le_bytes());
+            header.extend_from_slice(&offset.to_le_bytes());
+            header.extend_from_slice(&stored_len.to_le_bytes());
+            header.extend_from_slice(&len.to_le_bytes());
+            header.push(compressed as u8);
+            offset += stored_len;
+        }
+
+        writer
+            .write_all(&header)
+            .with_context(|_| format_err!("Failed to write pack index"))?;
+        for blob in blobs {
+            writer
+                .write_all(&blob)
+                .with_context(|_| format_err!("Failed to write pack entry"))?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes the pack to a file at the given location.
+    pub fn write_file<P>(self, path: P) -> Result<(), Error>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        let file = File::create(path)
+            .with_context(|_| format_err!("Failed to create pack file {:?}", path))?;
+        self.write(std::io::BufWriter::new(file))
+    }
+}
+
+fn entry_name(root: &Path, path: &Path) -> Result<String, Error> {
+    let relative: PathBuf = path
+        .strip_prefix(root)
+        .with_context(|_| format_err!("{:?} is not inside {:?}", path, root))?
+        .into();
+    let components = relative
+        .iter()
+        .map(|c| {
+            c.to_str()
+                .ok_or_else(|| format_err!("Path {:?} is not valid UTF-8", path))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(components.join("/"))
+}
+
+fn read_index<R: Read>(reader: &mut R) -> Result<FnvHashMap<String, PackEntry>, Error> {
+    let mut magic = [0; 4];
+    reader.read_exact(&mut magic)?;
+    if &magic != MAGIC {
+        return Err(format_err!("Not a pack file"));
+    }
+    let version = read_u32(reader)?;
+    if version != VERSION {
+        return Err(format_err!("Unsupported pack version {}", version));
+    }
+
+    let count = read_u32(reader)?;
+    let mut index = FnvHashMap::default();
+    for _ in 0..count {
+        let name_len = read_u32(reader)?;
+        let mut name = vec![0; name_len as usize];
+        reader.read_exact(&mut name)?;
+        let name =
+            String::from_utf8(name).with_context(|_| format_err!("Invalid entry name in pack"))?;
+        let entry = PackEntry {
+            modified: read_u64(reader)?,
+            offset: read_u64(reader)?,
+            stored_len: read_u64(reader)?,
+            len: read_u64(reader)?,
+            compressed: {
+                let mut flag = [0; 1];
+                reader.read_exact(&mut flag)?;
+                flag[0] != 0
+            },
+        };
+        index.insert(name, entry);
+    }
+
+    Ok(index)
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> Result<u32, Error> {
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_u64<R: Read>(reader: &mut R) -> Result<u64, Error> {
+    let mut bytes = [0; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use crate::source::Source;
+
+    use super::{PackBuilder, PackSource};
+
+    fn build(compression: bool) -> PackSource {
+        let test_assets_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/assets");
+        let mut bytes = Vec::new();
+        PackBuilder::new()
+            .with_compression(compression)
+            .add_directory(test_assets_dir)
+            .expect("Failed to add tests/assets to pack")
+            .add_file("big", vec![7; 4096], 42)
+            .write(&mut bytes)
+            .expect("Failed to write pack");
+
+        PackSource::from_bytes(bytes).expect("Failed to read pack")
+    }
+
+    #[test]
+    fn loads_asset_from_pack() {
+        for &compression in &[false, true] {
+            let pack = build(compression);
+
+            assert_eq!(
+                b"data".to_vec(),
+                pack.load("subdir/asset")
+                    .expect("Failed to load subdir/asset from pack")
+            );
+            assert_eq!(vec![7; 4096], pack.load("big").unwrap());
+            assert_eq!(42, pack.modified("big").unwrap());
+        }
+    }
+
+    #[test]
+    fn missing_entry_is_an_error() {
+        let pack = build(true);
+
+        assert!(!pack.contains("subdir/missing"));
+        assert!(pack.load("subdir/missing").is_err());
+        assert!(pack.modified("subdir/missing").is_err());
+    }
+
+    #[test]
+    fn rejects_invalid_pack() {
+        assert!(PackSource::from_bytes(b"not a pack".to_vec()).is_err());
+    }
+}
@@ -8,7 +8,9 @@ use std::{
 
 use crossbeam_queue::SegQueue;
 use derivative::Derivative;
+use fnv::FnvHashSet;
 use log::{debug, error, trace, warn};
+use parking_lot::Mutex;
 use rayon::ThreadPool;
 
 use amethyst_core::{
@@ -26,9 +28,11 @@ use thread_profiler::profile_scope;
 
 use crate::{
     asset::{Asset, FormatValue, ProcessableAsset},
+    dependency::{AssetId, DependencyGraph},
     error,
     progress::Tracker,
     reload::{HotReloadStrategy, Reload},
+    retention::{CacheKey, CachePolicy, CacheStats, Retention},
 };
 
 /// An `Allocator`, holding a counter for producing unique IDs.
@@ -49,10 +53,14 @@ impl Allocator {
 pub struct AssetStorage<A: Asset> {
     assets: VecStorage<(A, u32)>,
     bitset: BitSet,
+    budget: Option<usize>,
+    bytes: usize,
+    dependencies: Mutex<Option<Arc<DependencyGraph>>>,
     handles: Vec<Handle<A>>,
     handle_alloc: Allocator,
     pub(crate) processed: Arc<SegQueue<Processed<A>>>,
     reloads: Vec<(WeakHandle<A>, Box<dyn Reload<A::Data>>)>,
+    retention: Mutex<Retention<A>>,
     unused_handles: SegQueue<Handle<A>>,
 }
 
@@ -80,6 +88,54 @@ impl<A: Asset> AssetStorage<A> {
             .unwrap_or_else(|_| self.allocate_new())
     }
 
+    /// Limits how many loaded or reloaded assets are finalized per call to `process`.
+    ///
+    /// Any further assets stay queued until the next call, which avoids frame spikes when big
+    /// batches of assets finish loading at once. `None`, the default, means no limit.
+    pub fn set_process_budget(&mut self, budget: Option<usize>) {
+        self.budget = budget;
+    }
+
+    /// Returns the limit of assets finalized per call to `process`, if any.
+    pub fn process_budget(&self) -> Option<usize> {
+        self.budget
+    }
+
+    /// Sets the policy for keeping assets around after all handles to them were dropped.
+    ///
+    /// `None`, the default, frees assets as soon as they are no longer used.
+    pub fn set_cache_policy(&mut self, policy: Option<CachePolicy>) {
+        self.retention.get_mut().set_policy(policy);
+    }
+
+    /// Returns the cache policy of this storage, if any.
+    pub fn cache_policy(&self) -> Option<CachePolicy> {
+        self.retention.lock().policy()
+    }
+
+    /// Returns the memory statistics of this storage.
+    pub fn cache_stats(&self) -> CacheStats<A> {
+        self.retention.lock().stats(self.bytes)
+    }
+
+    /// Remembers the key an asset is loaded with, so it can be retained once it is dropped.
+    pub(crate) fn register_key(&self, handle: &Handle<A>, key: CacheKey) {
+        self.retention.lock().register(handle.id(), key);
+    }
+
+    /// Returns a retained asset loaded with the given key, if there is one.
+    pub(crate) fn revive(&self, key: &CacheKey) -> Option<Handle<A>> {
+        self.retention.lock().revive(key)
+    }
+
+    /// Sets the dependency graph this storage reports reloads and unloads to.
+    pub(crate) fn set_dependency_graph(&self, graph: &Arc<DependencyGraph>) {
+        let mut dependencies = self.dependencies.lock();
+        if dependencies.is_none() {
+            *dependencies = Some(graph.clone());
+        }
+    }
+
     fn allocate_new(&self) -> Handle<A> {
         let id = self.handle_alloc.next_id() as u32;
         Handle {
@@ -93,6 +149,8 @@ impl<A: Asset> AssetStorage<A> {
     pub fn unload_all(&mut self) {
         unsafe { self.assets.clean(&self.bitset) }
         self.bitset.clear();
+        self.bytes = 0;
+        self.retention.get_mut().clear();
     }
 
     /// When cloning an asset handle, you'll get another handle,
@@ -111,6 +169,7 @@ impl<A: Asset> AssetStorage<A> {
             self.bitset.add(id);
             self.handles.push(h.clone());
 
+            self.bytes += asset.size_hint();
             unsafe {
                 self.assets.insert(id, (asset, 0));
             }
@@ -163,6 +222,7 @@ impl<A: Asset> AssetStorage<A> {
         if self.bitset.contains(handle.id()) {
             let data = unsafe { self.assets.get_mut(handle.id()) };
             data.1 += 1;
+            self.bytes = self.bytes - data.0.size_hint() + asset.size_hint();
             std::mem::replace(&mut data.0, asset)
         } else {
             panic!("Trying to replace not loaded asset");
@@ -180,6 +240,7 @@ impl<A: Asset> AssetStorage<A> {
         let id = handle.id();
         self.bitset.add(id);
         self.handles.push(handle.clone());
+        self.bytes += asset.size_hint();
         unsafe {
             self.assets.insert(id, (asset, 0));
         }
@@ -242,13 +303,37 @@ impl<A: Asset> AssetStorage<A> {
         D: FnMut(A),
         F: FnMut(A::Data) -> Result<ProcessingState<A>, Error>,
     {
+        self.process_with_handle(|_, d| f(d), |_, a| drop_fn(a), frame_number, pool, strategy);
+    }
+
+    /// Like `process_custom_drop`, but also passes the handle of the processed or dropped asset
+    /// to `f` and `drop_fn`.
+    pub(crate) fn process_with_handle<F, D>(
+        &mut self,
+        mut f: F,
+        mut drop_fn: D,
+        frame_number: u64,
+        pool: &ThreadPool,
+        strategy: Option<&HotReloadStrategy>,
+    ) where
+        D: FnMut(&Handle<A>, A),
+        F: FnMut(&Handle<A>, A::Data) -> Result<ProcessingState<A>, Error>,
+    {
+        let dependencies = self.dependencies.lock().clone();
         {
             let mut requeue = Vec::new();
-            while let Ok(processed) = self.processed.pop() {
+            let mut finalized = 0;
+            // Stop finalizing assets once the budget is used up.
+            while self.budget.filter(|&budget| finalized >= budget).is_none() {
+                let processed = match self.processed.pop() {
+                    Ok(processed) => processed,
+                    Err(_) => break,
+                };
                 let assets = &mut self.assets;
                 let bitset = &mut self.bitset;
                 let handles = &mut self.handles;
                 let reloads = &mut self.reloads;
+                let bytes = &mut self.bytes;
 
                 let f = &mut f;
                 let (reload_obj, handle) = match processed {
@@ -260,7 +345,7 @@ impl<A: Asset> AssetStorage<A> {
                     } => {
                         let (asset, reload_obj) = match data
                             .map(|FormatValue { data, reload }| (data, reload))
-                            .and_then(|(d, rel)| f(d).map(|a| (a, rel)))
+                            .and_then(|(d, rel)| f(&handle, d).map(|a| (a, rel)))
                             .with_context(|_| error::Error::Asset(name.clone()))
                         {
                             Ok((ProcessingState::Loaded(x), r)) => {
@@ -313,6 +398,7 @@ impl<A: Asset> AssetStorage<A> {
                                     handle,
                                     e,
                                 );
+                                self.retention.get_mut().forget(handle.id());
                                 tracker.fail(handle.id(), A::NAME, name, e);
 
                                 continue;
@@ -322,6 +408,8 @@ impl<A: Asset> AssetStorage<A> {
                         let id = handle.id();
                         bitset.add(id);
                         handles.push(handle.clone());
+                        *bytes += asset.size_hint();
+                        finalized += 1;
 
                         // NOTE: the loader has to ensure that a handle will be used
                         // together with a `Data` only once.
@@ -339,7 +427,7 @@ impl<A: Asset> AssetStorage<A> {
                     } => {
                         let (asset, reload_obj) = match data
                             .map(|FormatValue { data, reload }| (data, reload))
-                            .and_then(|(d, rel)| f(d).map(|a| (a, rel)))
+                            .and_then(|(d, rel)| f(&handle, d).map(|a| (a, rel)))
                             .with_context(|_| error::Error::Asset(name.clone()))
                         {
                             Ok((ProcessingState::Loaded(x), r)) => (x, r),
@@ -382,10 +470,57 @@ impl<A: Asset> AssetStorage<A> {
                         );
                         let data = unsafe { self.assets.get_mut(id) };
                         data.1 += 1;
-                        drop_fn(std::mem::replace(&mut data.0, asset));
+                        *bytes = *bytes - data.0.size_hint() + asset.size_hint();
+                        drop_fn(&handle, std::mem::replace(&mut data.0, asset));
+                        finalized += 1;
+                        if let Some(ref dependencies) = dependencies {
+                            dependencies.mark_reloaded(AssetId::new::<A>(id));
+                        }
 
                         (reload_obj, handle)
                     }
+                    Processed::Cancelled {
+                        handle,
+                        name,
+                        tracker,
+                        resume,
+                    } => {
+                        // Unwrapping the id is atomic with respect to `WeakHandle::upgrade`,
+                        // once it succeeded no handle to the asset can come back.
+                        let id = match Arc::try_unwrap(handle.id) {
+                            Ok(id) => id,
+                            Err(id) => {
+                                let handle = Handle {
+                                    id,
+                                    marker: PhantomData,
+                                };
+                                debug!(
+                                    "{:?}: Resuming cancelled load of asset {:?} (handle id: {:?})",
+                                    A::NAME,
+                                    name,
+                                    handle,
+                                );
+                                pool.spawn(move || resume(handle, name, tracker));
+                                continue;
+                            }
+                        };
+                        debug!(
+                            "{:?}: Loading asset {:?} (handle id: {:?}) was cancelled",
+                            A::NAME,
+                            name,
+                            id,
+                        );
+                        self.retention.get_mut().forget(id);
+                        tracker.cancelled();
+
+                        // Nobody else can have a handle to this id, so it can be reused.
+                        self.unused_handles.push(Handle {
+                            id: Arc::new(id),
+                            marker: PhantomData,
+                        });
+
+                        continue;
+                    }
                 };
 
                 // Add the reload obj if it is `Some`.
@@ -399,6 +534,18 @@ impl<A: Asset> AssetStorage<A> {
             }
         }
 
+        {
+            let assets = &self.assets;
+            let retention = self.retention.get_mut();
+            if retention.policy().is_some() {
+                for handle in self.handles.iter().filter(|handle| handle.is_unique()) {
+                    let size = unsafe { assets.get(handle.id()).0.size_hint() };
+                    retention.retain(handle, size);
+                }
+                retention.evict();
+            }
+        }
+
         let mut count = 0;
         let mut skip = 0;
         while let Some(i) = self.handles.iter().skip(skip).position(Handle::is_unique) {
@@ -410,9 +557,14 @@ impl<A: Asset> AssetStorage<A> {
             let id = handle.id();
             unsafe {
                 let (asset, _) = self.assets.remove(id);
-                drop_fn(asset);
+                self.bytes -= asset.size_hint();
+                drop_fn(&handle, asset);
             }
             self.bitset.remove(id);
+            self.retention.get_mut().forget(id);
+            if let Some(ref dependencies) = dependencies {
+                dependencies.remove_asset(AssetId::new::<A>(id));
+            }
 
             // Can't reuse old handle here, because otherwise weak handles would still be valid.
             // TODO: maybe just store u32?
@@ -425,51 +577,86 @@ impl<A: Asset> AssetStorage<A> {
             debug!("{:?}: Freed {} handle ids", A::NAME, count,);
         }
 
-        if strategy
-            .map(|s| s.needs_reload(frame_number))
-            .unwrap_or(false)
-        {
+        if let Some(strategy) = strategy.filter(|s| s.needs_reload(frame_number)) {
             trace!("{:?}: Testing for asset reloads..", A::NAME);
-            self.hot_reload(pool);
+            self.hot_reload(pool, strategy.changed());
+        }
+
+        if let Some(ref dependencies) = dependencies {
+            let stale = dependencies.take_stale::<A>();
+            if !stale.is_empty() {
+                debug!(
+                    "{:?}: Reloading {} assets whose dependencies were reloaded",
+                    A::NAME,
+                    stale.len(),
+                );
+                self.reload_ids(pool, &stale);
+            }
         }
     }
 
-    fn hot_reload(&mut self, pool: &ThreadPool) {
+    fn hot_reload(&mut self, pool: &ThreadPool, changed: Option<&FnvHashSet<String>>) {
         self.reloads.retain(|&(ref handle, _)| !handle.is_dead());
+        let needs_reload = |rel: &dyn Reload<A::Data>| match changed {
+            Some(changed) => changed.contains(&rel.name()),
+            None => rel.needs_reload(),
+        };
         while let Some(p) = self
             .reloads
             .iter()
-            .position(|&(_, ref rel)| rel.needs_reload())
+            .position(|&(_, ref rel)| needs_reload(&**rel))
         {
             let (handle, rel): (WeakHandle<_>, Box<dyn Reload<_>>) = self.reloads.swap_remove(p);
+            self.spawn_reload(pool, handle, rel);
+        }
+    }
 
-            let name = rel.name();
-            let format = rel.format();
-            let handle = handle.upgrade();
-
-            debug!(
-                "{:?}: Asset {:?} (handle id: {:?}) needs a reload using format {:?}",
-                A::NAME,
-                name,
-                handle,
-                format,
-            );
-
-            if let Some(handle) = handle {
-                let processed = self.processed.clone();
-                pool.spawn(move || {
-                    let old_reload = rel.clone();
-                    let data = rel.reload().with_context(|_| error::Error::Format(format));
-
-                    let p = Processed::HotReload {
-                        data,
-                        name,
-                        handle,
-                        old_reload,
-                    };
-                    processed.push(p);
-                });
-            }
+    /// Reloads the assets with the given handle ids, regardless of whether they changed.
+    fn reload_ids(&mut self, pool: &ThreadPool, ids: &[u32]) {
+        self.reloads.retain(|&(ref handle, _)| !handle.is_dead());
+        while let Some(p) = self.reloads.iter().position(|&(ref handle, _)| {
+            handle
+                .upgrade()
+                .map(|handle| ids.contains(&handle.id()))
+                .unwrap_or(false)
+        }) {
+            let (handle, rel) = self.reloads.swap_remove(p);
+            self.spawn_reload(pool, handle, rel);
+        }
+    }
+
+    fn spawn_reload(
+        &self,
+        pool: &ThreadPool,
+        handle: WeakHandle<A>,
+        rel: Box<dyn Reload<A::Data>>,
+    ) {
+        let name = rel.name();
+        let format = rel.format();
+        let handle = handle.upgrade();
+
+        debug!(
+            "{:?}: Asset {:?} (handle id: {:?}) needs a reload using format {:?}",
+            A::NAME,
+            name,
+            handle,
+            format,
+        );
+
+        if let Some(handle) = handle {
+            let processed = self.processed.clone();
+            pool.spawn(move || {
+                let old_reload = rel.clone();
+                let data = rel.reload().with_context(|_| error::Error::Format(format));
+
+                let p = Processed::HotReload {
+                    data,
+                    name,
+                    handle,
+                    old_reload,
+                };
+                processed.push(p);
+            });
         }
     }
 }
@@ -479,10 +666,14 @@ impl<A: Asset> Default for AssetStorage<A> {
         AssetStorage {
             assets: Default::default(),
             bitset: Default::default(),
+            budget: None,
+            bytes: 0,
+            dependencies: Default::default(),
             handles: Default::default(),
             handle_alloc: Default::default(),
             processed: Arc::new(SegQueue::new()),
             reloads: Default::default(),
+            retention: Default::default(),
             unused_handles: SegQueue::new(),
         }
     }
@@ -535,9 +726,10 @@ where
         ReadExpect<'a, Arc<ThreadPool>>,
         Read<'a, Time>,
         Option<Read<'a, HotReloadStrategy>>,
+        Write<'a, CacheStats<A>>,
     );
 
-    fn run(&mut self, (mut storage, pool, time, strategy): Self::SystemData) {
+    fn run(&mut self, (mut storage, pool, time, strategy, mut stats): Self::SystemData) {
         #[cfg(feature = "profiler")]
         profile_scope!("processor_system");
 
@@ -547,6 +739,7 @@ where
             &**pool,
             strategy.as_deref(),
         );
+        *stats = storage.cache_stats();
     }
 }
 
@@ -584,7 +777,7 @@ impl<A> Handle<A> {
     }
 
     /// Returns `true` if this is the only handle to the asset its pointing at.
-    fn is_unique(&self) -> bool {
+    pub(crate) fn is_unique(&self) -> bool {
         Arc::strong_count(&self.id) == 1
     }
 }
@@ -609,6 +802,13 @@ pub(crate) enum Processed<A: Asset> {
         name: String,
         old_reload: Box<dyn Reload<A::Data>>,
     },
+    Cancelled {
+        handle: Handle<A>,
+        name: String,
+        tracker: Box<dyn Tracker>,
+        /// Imports the asset after all, if the handle was revived by a `WeakHandle`.
+        resume: Box<dyn FnOnce(Handle<A>, String, Box<dyn Tracker>) + Send>,
+    },
 }
 
 /// A weak handle, which is useful if you don't directly need the asset
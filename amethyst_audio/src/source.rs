@@ -28,6 +28,10 @@ impl Asset for Source {
     const NAME: &'static str = "audio::Source";
     type Data = AudioData;
     type HandleStorage = VecStorage<SourceHandle>;
+
+    fn size_hint(&self) -> usize {
+        self.bytes.len()
+    }
 }
 
 impl ProcessableAsset for Source {
@@ -52,3 +56,59 @@ impl<'a> PrefabData<'a> for AudioData {
             .load_from_data(self.clone(), (), &system_data.1))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, thread, time::Duration};
+
+    use rayon::{ThreadPool, ThreadPoolBuilder};
+
+    use amethyst_assets::{AssetStorage, CachePolicy, Loader, ProcessableAsset, ProgressCounter};
+
+    use super::Source;
+    use crate::{OggFormat, WavFormat};
+
+    fn process_until(
+        storage: &mut AssetStorage<Source>,
+        pool: &ThreadPool,
+        progress: &ProgressCounter,
+    ) {
+        for _ in 0..500 {
+            storage.process(Source::process, 0, pool, None);
+            if progress.num_loading() == 0 {
+                return;
+            }
+            thread::sleep(Duration::from_millis(2));
+        }
+        panic!("Sources did not finish loading");
+    }
+
+    #[test]
+    fn budget_evicts_sources_by_their_length() {
+        let pool = Arc::new(ThreadPoolBuilder::new().num_threads(1).build().unwrap());
+        let loader = Loader::new(concat!(env!("CARGO_MANIFEST_DIR"), "/tests"), pool.clone());
+        let mut storage = AssetStorage::<Source>::new();
+        // Room for the ogg file (59316 bytes) or the wav file (1310 bytes), but not both.
+        storage.set_cache_policy(Some(CachePolicy::lru(60_000)));
+        let mut progress = ProgressCounter::new();
+
+        let ogg = loader.load("sound_test.ogg", OggFormat, &mut progress, &storage);
+        let wav = loader.load("sound_test.wav", WavFormat, &mut progress, &storage);
+        process_until(&mut storage, &pool, &progress);
+        assert_eq!(60_626, storage.cache_stats().bytes);
+
+        drop(ogg);
+        storage.process(Source::process, 0, &pool, None);
+        assert_eq!(1, storage.cache_stats().retained);
+
+        // Retaining the wav file as well goes over budget and evicts the ogg file.
+        drop(wav);
+        storage.process(Source::process, 0, &pool, None);
+        let stats = storage.cache_stats();
+        assert_eq!(
+            (1, 1310, 1),
+            (stats.retained, stats.retained_bytes, stats.evictions)
+        );
+        assert_eq!(1310, stats.bytes);
+    }
+}
@@ -45,6 +45,9 @@ pub mod bundle;
 pub mod deferred_dispatcher_operation;
 pub mod frame_limiter;
 pub mod geometry;
+#[cfg(feature = "saveload")]
+pub mod saveload;
+pub mod snapshot;
 pub mod timing;
 pub mod transform;
 
@@ -0,0 +1,760 @@
+//! Saving and restoring the persistent part of a `World`, e.g. for game saves.
+//!
+//! Entities marked with the `Persistent` component are written to save files together with
+//! the component types and resources registered with the `SaveLoadBundle`. Loading a save file
+//! replaces all persistent entities with the saved ones, keeping the `Parent` links between
+//! them intact.
+//!
+//! ```
+//! use amethyst::core::{
+//!     ecs::prelude::*,
+//!     saveload::{Persistent, SaveLoad, SaveLoadBundle},
+//!     Named, SystemBundle,
+//! };
+//!
+//! let mut world = World::new();
+//! SaveLoadBundle::new(1)
+//!     .with_component::<Named>("name")
+//!     .build(&mut world, &mut DispatcherBuilder::new())
+//!     .unwrap();
+//! world
+//!     .create_entity()
+//!     .with(Named::new("player"))
+//!     .with(Persistent)
+//!     .build();
+//!
+//! let saveload = world.read_resource::<SaveLoad>();
+//! let save = saveload.save(&world).unwrap();
+//! let entities = saveload.load(&world, &save).unwrap();
+//! assert_eq!(1, entities.len());
+//! ```
+
+use std::{fmt, fs, path::Path, sync::Arc};
+
+use fnv::FnvHashMap;
+use log::warn;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use amethyst_error::{format_err, Error, ResultExt};
+
+use crate::{
+    bundle::SystemBundle,
+    ecs::{
+        prelude::{Component, DispatcherBuilder, Entity, Join, World, WorldExt},
+        shred::Resource,
+        storage::NullStorage,
+    },
+    transform::Parent,
+};
+
+/// Marks an entity to be written to save files.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Persistent;
+
+impl Component for Persistent {
+    type Storage = NullStorage<Self>;
+}
+
+/// Encoding of save files.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SaveFormat {
+    /// Human readable RON, useful while developing.
+    Ron,
+    /// Compact binary encoding using `bincode`.
+    Binary,
+}
+
+impl SaveFormat {
+    fn encode<T: Serialize>(self, value: &T) -> Result<Data, Error> {
+        match self {
+            SaveFormat::Ron => ron::ser::to_string(value)
+                .map(Data::Text)
+                .with_context(|_| format_err!("Failed to encode RON")),
+            SaveFormat::Binary => bincode::serialize(value)
+                .map(Data::Binary)
+                .with_context(|_| format_err!("Failed to encode binary data")),
+        }
+    }
+}
+
+/// Encoded data of a component type or resource.
+#[derive(Debug, Deserialize, Serialize)]
+enum Data {
+    Text(String),
+    Binary(Vec<u8>),
+}
+
+impl Data {
+    fn format(&self) -> SaveFormat {
+        match self {
+            Data::Text(_) => SaveFormat::Ron,
+            Data::Binary(_) => SaveFormat::Binary,
+        }
+    }
+
+    fn decode<T: DeserializeOwned>(&self) -> Result<T, Error> {
+        match self {
+            Data::Text(text) => {
+                ron::de::from_str(text).with_context(|_| format_err!("Failed to decode RON"))
+            }
+            Data::Binary(bytes) => bincode::deserialize(bytes)
+                .with_context(|_| format_err!("Failed to decode binary data")),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct SaveFile {
+    version: u32,
+    entities: u32,
+    /// Child and parent, by their index in the save file.
+    parents: Vec<(u32, u32)>,
+    components: Vec<(String, Data)>,
+    resources: Vec<(String, Data)>,
+}
+
+/// Applies decoded data to the `World` and the newly created entities.
+type Apply = Box<dyn FnOnce(&World, &[Entity])>;
+type Migrate = Arc<dyn Fn(&Data) -> Result<Data, Error> + Send + Sync>;
+
+struct Entry {
+    name: String,
+    register: fn(&mut World),
+    save: fn(&World, &FnvHashMap<Entity, u32>, SaveFormat) -> Result<Option<Data>, Error>,
+    decode: fn(&Data, u32) -> Result<Apply, Error>,
+}
+
+struct Migration {
+    name: String,
+    from_version: u32,
+    component: Migrate,
+    resource: Migrate,
+}
+
+struct Registry {
+    version: u32,
+    format: SaveFormat,
+    components: Vec<Entry>,
+    resources: Vec<Entry>,
+    migrations: Vec<Migration>,
+}
+
+impl Registry {
+    fn decode(
+        &self,
+        entries: &[Entry],
+        sections: &[(String, Data)],
+        version: u32,
+        entities: u32,
+        resource: bool,
+    ) -> Result<Vec<Apply>, Error> {
+        let mut applies = Vec::with_capacity(sections.len());
+        for (name, data) in sections {
+            let entry = match entries.iter().find(|entry| entry.name == *name) {
+                Some(entry) => entry,
+                None => {
+                    warn!("Ignoring unknown save data {:?}", name);
+                    continue;
+                }
+            };
+            let mut migrated = None;
+            for migration in self.migrations.iter().filter(|m| m.name == *name) {
+                if version <= migration.from_version {
+                    let migrate = if resource {
+                        &migration.resource
+                    } else {
+                        &migration.component
+                    };
+                    let data = migrate(migrated.as_ref().unwrap_or(data)).with_context(|_| {
+                        format_err!(
+                            "Failed to migrate {:?} from version {}",
+                            name,
+                            migration.from_version
+                        )
+                    })?;
+                    migrated = Some(data);
+                }
+            }
+            let apply = (entry.decode)(migrated.as_ref().unwrap_or(data), entities)
+                .with_context(|_| format_err!("Failed to load {:?}", name))?;
+            applies.push(apply);
+        }
+        Ok(applies)
+    }
+}
+
+fn register_component<C>(world: &mut World)
+where
+    C: Component,
+    C::Storage: Default,
+{
+    world.register::<C>();
+}
+
+fn register_resource<R>(world: &mut World)
+where
+    R: Resource + Default,
+{
+    world.entry::<R>().or_insert_with(R::default);
+}
+
+fn save_component<C>(
+    world: &World,
+    indices: &FnvHashMap<Entity, u32>,
+    format: SaveFormat,
+) -> Result<Option<Data>, Error>
+where
+    C: Component + Serialize,
+{
+    let storage = world.read_storage::<C>();
+    let mut saved = (&world.entities(), &storage)
+        .join()
+        .filter_map(|(entity, component)| indices.get(&entity).map(|&i| (i, component)))
+        .collect::<Vec<_>>();
+    saved.sort_by_key(|(i, _)| *i);
+    format.encode(&saved).map(Some)
+}
+
+fn decode_component<C>(data: &Data, entities: u32) -> Result<Apply, Error>
+where
+    C: Component + DeserializeOwned,
+{
+    let saved = data.decode::<Vec<(u32, C)>>()?;
+    if let Some((i, _)) = saved.iter().find(|(i, _)| *i >= entities) {
+        return Err(format_err!("Invalid entity index {}", i));
+    }
+    Ok(Box::new(move |world, entities| {
+        let mut storage = world.write_storage::<C>();
+        for (i, component) in saved {
+            storage
+                .insert(entities[i as usize], component)
+                .expect("Unreachable: Entities were just created");
+        }
+    }))
+}
+
+fn save_resource<R>(
+    world: &World,
+    _: &FnvHashMap<Entity, u32>,
+    format: SaveFormat,
+) -> Result<Option<Data>, Error>
+where
+    R: Resource + Serialize,
+{
+    match world.try_fetch::<R>() {
+        Some(resource) => format.encode(&*resource).map(Some),
+        None => Ok(None),
+    }
+}
+
+fn decode_resource<R>(data: &Data, _: u32) -> Result<Apply, Error>
+where
+    R: Resource + DeserializeOwned,
+{
+    let resource = data.decode::<R>()?;
+    Ok(Box::new(move |world, _| {
+        *world.write_resource::<R>() = resource
+    }))
+}
+
+/// Saves and restores the persistent entities and the registered resources of a `World`.
+///
+/// This resource is added by the `SaveLoadBundle`.
+pub struct SaveLoad {
+    registry: Arc<Registry>,
+}
+
+impl SaveLoad {
+    /// Returns the version written to new save files.
+    pub fn version(&self) -> u32 {
+        self.registry.version
+    }
+
+    /// Returns the encoding of save files.
+    pub fn format(&self) -> SaveFormat {
+        self.registry.format
+    }
+
+    /// Encodes all persistent entities and the registered resources of the `World`.
+    ///
+    /// Only `Parent` links between persistent entities are saved; persistent entities with
+    /// another parent are saved as root entities.
+    pub fn save(&self, world: &World) -> Result<Vec<u8>, Error> {
+        let entities = world.entities();
+        let persistent = world.read_storage::<Persistent>();
+        let saved = (&entities, &persistent)
+            .join()
+            .map(|(entity, _)| entity)
+            .collect::<Vec<_>>();
+        let indices = saved
+            .iter()
+            .enumerate()
+            .map(|(i, &entity)| (entity, i as u32))
+            .collect::<FnvHashMap<_, _>>();
+        let parents = world.read_storage::<Parent>();
+        let parents = (&entities, &persistent, &parents)
+            .join()
+            .filter_map(|(entity, _, parent)| {
+                indices
+                    .get(&parent.entity)
+                    .map(|&parent| (indices[&entity], parent))
+            })
+            .collect();
+
+        let format = self.registry.format;
+        let mut file = SaveFile {
+            version: self.registry.version,
+            entities: saved.len() as u32,
+            parents,
+            components: Vec::with_capacity(self.registry.components.len()),
+            resources: Vec::with_capacity(self.registry.resources.len()),
+        };
+        for entry in &self.registry.components {
+            if let Some(data) = (entry.save)(world, &indices, format)
+                .with_context(|_| format_err!("Failed to save {:?}", entry.name))?
+            {
+                file.components.push((entry.name.clone(), data));
+            }
+        }
+        for entry in &self.registry.resources {
+            if let Some(data) = (entry.save)(world, &indices, format)
+                .with_context(|_| format_err!("Failed to save {:?}", entry.name))?
+            {
+                file.resources.push((entry.name.clone(), data));
+            }
+        }
+
+        match format {
+            SaveFormat::Ron => ron::ser::to_string_pretty(&file, Default::default())
+                .map(String::into_bytes)
+                .with_context(|_| format_err!("Failed to encode save file")),
+            SaveFormat::Binary => bincode::serialize(&file)
+                .with_context(|_| format_err!("Failed to encode save file")),
+        }
+    }
+
+    /// Saves the `World` to the file at the given path, see `save`.
+    pub fn save_file<P: AsRef<Path>>(&self, world: &World, path: P) -> Result<(), Error> {
+        let path = path.as_ref();
+        let bytes = self.save(world)?;
+        fs::write(path, bytes).with_context(|_| format_err!("Failed to write {:?}", path))
+    }
+
+    /// Replaces all persistent entities of the `World` with the ones of the save file and
+    /// overwrites the saved resources. Returns the created entities, in the order they were
+    /// saved.
+    ///
+    /// Save files written by older versions are migrated with the migrations registered on the
+    /// `SaveLoadBundle`. Data not registered with the bundle is ignored. The `World` is left
+    /// untouched if the save file can't be decoded. The components of the deleted entities are
+    /// removed on the next `World::maintain`.
+    pub fn load(&self, world: &World, bytes: &[u8]) -> Result<Vec<Entity>, Error> {
+        let file: SaveFile = match self.registry.format {
+            SaveFormat::Ron => std::str::from_utf8(bytes)
+                .map_err(Error::new)
+                .and_then(|text| ron::de::from_str(text).map_err(Error::new)),
+            SaveFormat::Binary => bincode::deserialize(bytes).map_err(Error::new),
+        }
+        .with_context(|_| format_err!("Failed to decode save file"))?;
+        if file.version > self.registry.version {
+            return Err(format_err!(
+                "Save file version {} is newer than the supported version {}",
+                file.version,
+                self.registry.version
+            ));
+        }
+        if let Some(&(child, parent)) = file
+            .parents
+            .iter()
+            .find(|&&(child, parent)| child >= file.entities || parent >= file.entities)
+        {
+            return Err(format_err!(
+                "Invalid parent link from {} to {}",
+                child,
+                parent
+            ));
+        }
+
+        let registry = &self.registry;
+        let mut applies = registry.decode(
+            &registry.components,
+            &file.components,
+            file.version,
+            file.entities,
+            false,
+        )?;
+        applies.extend(registry.decode(
+            &registry.resources,
+            &file.resources,
+            file.version,
+            file.entities,
+            true,
+        )?);
+
+        let entities = {
+            let all = world.entities();
+            let mut persistent = world.write_storage::<Persistent>();
+            for (entity, _) in (&all, &persistent).join() {
+                all.delete(entity)
+                    .expect("Unreachable: Joined entities are alive");
+            }
+            persistent.clear();
+            (0..file.entities)
+                .map(|_| {
+                    let entity = all.create();
+                    persistent
+                        .insert(entity, Persistent)
+                        .expect("Unreachable: Entities were just created");
+                    entity
+                })
+                .collect::<Vec<_>>()
+        };
+        {
+            let mut parents = world.write_storage::<Parent>();
+            for (child, parent) in file.parents {
+                parents
+                    .insert(
+                        entities[child as usize],
+                        Parent {
+                            entity: entities[parent as usize],
+                        },
+                    )
+                    .expect("Unreachable: Entities were just created");
+            }
+        }
+        for apply in applies {
+            apply(world, &entities);
+        }
+        Ok(entities)
+    }
+
+    /// Loads the save file at the given path into the `World`, see `load`.
+    pub fn load_file<P: AsRef<Path>>(&self, world: &World, path: P) -> Result<Vec<Entity>, Error> {
+        let path = path.as_ref();
+        let bytes = fs::read(path).with_context(|_| format_err!("Failed to read {:?}", path))?;
+        self.load(world, &bytes)
+    }
+}
+
+impl fmt::Debug for SaveLoad {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.registry.fmt(f)
+    }
+}
+
+impl fmt::Debug for Registry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names = |entries: &[Entry]| {
+            entries
+                .iter()
+                .map(|entry| entry.name.clone())
+                .collect::<Vec<_>>()
+        };
+        f.debug_struct("SaveLoad")
+            .field("version", &self.version)
+            .field("format", &self.format)
+            .field("components", &names(&self.components))
+            .field("resources", &names(&self.resources))
+            .field("migrations", &self.migrations.len())
+            .finish()
+    }
+}
+
+/// Adds the `SaveLoad` resource, used to save and load the persistent part of the `World`.
+///
+/// Component types and resources are written to save files under the given names, which have
+/// to stay the same between versions of a game to be able to load older saves. Every save file
+/// stores the version of the bundle it was written with; when the layout of a component or
+/// resource changes, the version should be increased and a migration registered with
+/// `with_migration`.
+///
+/// ## Errors
+///
+/// No errors will be returned by this bundle.
+pub struct SaveLoadBundle {
+    registry: Registry,
+}
+
+impl SaveLoadBundle {
+    /// Creates a bundle writing save files of the given version, in the RON format.
+    pub fn new(version: u32) -> Self {
+        SaveLoadBundle {
+            registry: Registry {
+                version,
+                format: SaveFormat::Ron,
+                components: Vec::new(),
+                resources: Vec::new(),
+                migrations: Vec::new(),
+            },
+        }
+    }
+
+    /// Sets the encoding of save files.
+    pub fn with_format(mut self, format: SaveFormat) -> Self {
+        self.registry.format = format;
+        self
+    }
+
+    /// Saves the component of type `C` of all persistent entities under the given name.
+    pub fn with_component<C>(mut self, name: &str) -> Self
+    where
+        C: Component + Serialize + DeserializeOwned,
+        C::Storage: Default,
+    {
+        self.registry.components.retain(|entry| entry.name != name);
+        self.registry.components.push(Entry {
+            name: name.to_owned(),
+            register: register_component::<C>,
+            save: save_component::<C>,
+            decode: decode_component::<C>,
+        });
+        self
+    }
+
+    /// Saves the resource of type `R` under the given name.
+    ///
+    /// The resource is inserted with its default value if it isn't in the `World` yet.
+    pub fn with_resource<R>(mut self, name: &str) -> Self
+    where
+        R: Resource + Default + Serialize + DeserializeOwned,
+    {
+        self.registry.resources.retain(|entry| entry.name != name);
+        self.registry.resources.push(Entry {
+            name: name.to_owned(),
+            register: register_resource::<R>,
+            save: save_resource::<R>,
+            decode: decode_resource::<R>,
+        });
+        self
+    }
+
+    /// Converts the component or resource with the given name from the layout `Old` used up to
+    /// version `from_version` to the layout `New` used by the next version.
+    ///
+    /// When loading a save file, all migrations for versions starting at the version of the file
+    /// are applied in the order of their versions, so migrations can be chained.
+    pub fn with_migration<Old, New, F>(mut self, name: &str, from_version: u32, f: F) -> Self
+    where
+        Old: DeserializeOwned,
+        New: Serialize,
+        F: Fn(Old) -> New + Send + Sync + 'static,
+    {
+        let f = Arc::new(f);
+        let component = {
+            let f = f.clone();
+            Arc::new(move |data: &Data| {
+                let migrated = data
+                    .decode::<Vec<(u32, Old)>>()?
+                    .into_iter()
+                    .map(|(i, old)| (i, f(old)))
+                    .collect::<Vec<_>>();
+                data.format().encode(&migrated)
+            })
+        };
+        let resource = Arc::new(move |data: &Data| data.format().encode(&f(data.decode()?)));
+        let index = self
+            .registry
+            .migrations
+            .iter()
+            .position(|migration| migration.from_version > from_version)
+            .unwrap_or(self.registry.migrations.len());
+        self.registry.migrations.insert(
+            index,
+            Migration {
+                name: name.to_owned(),
+                from_version,
+                component,
+                resource,
+            },
+        );
+        self
+    }
+}
+
+impl fmt::Debug for SaveLoadBundle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.registry.fmt(f)
+    }
+}
+
+impl<'a, 'b> SystemBundle<'a, 'b> for SaveLoadBundle {
+    fn build(self, world: &mut World, _: &mut DispatcherBuilder<'a, 'b>) -> Result<(), Error> {
+        world.register::<Persistent>();
+        world.register::<Parent>();
+        for entry in self
+            .registry
+            .components
+            .iter()
+            .chain(&self.registry.resources)
+        {
+            (entry.register)(world);
+        }
+        world.insert(SaveLoad {
+            registry: Arc::new(self.registry),
+        });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::{Deserialize, Serialize};
+
+    use crate::{
+        ecs::prelude::{Builder, Component, DenseVecStorage, DispatcherBuilder, World, WorldExt},
+        Named, SystemBundle,
+    };
+
+    use super::*;
+
+    #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+    struct Health(u32);
+
+    impl Component for Health {
+        type Storage = DenseVecStorage<Self>;
+    }
+
+    #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+    struct Vitals {
+        health: u32,
+        shield: u32,
+    }
+
+    impl Component for Vitals {
+        type Storage = DenseVecStorage<Self>;
+    }
+
+    #[derive(Debug, Default, Deserialize, PartialEq, Serialize)]
+    struct Score(u64);
+
+    fn world(bundle: SaveLoadBundle) -> World {
+        let mut world = World::new();
+        bundle
+            .build(&mut world, &mut DispatcherBuilder::new())
+            .unwrap();
+        world
+    }
+
+    fn round_trip(format: SaveFormat) {
+        let bundle = || {
+            SaveLoadBundle::new(1)
+                .with_format(format)
+                .with_component::<Health>("health")
+                .with_component::<Named>("name")
+                .with_resource::<Score>("score")
+        };
+        let mut world = world(bundle());
+        world.insert(Score(42));
+        let player = world
+            .create_entity()
+            .with(Persistent)
+            .with(Named::new("player"))
+            .with(Health(7))
+            .build();
+        world
+            .create_entity()
+            .with(Persistent)
+            .with(Named::new("sword"))
+            .with(Parent { entity: player })
+            .build();
+        world.create_entity().with(Health(1)).build();
+        let save = world.read_resource::<SaveLoad>().save(&world).unwrap();
+
+        let loaded = self::world(bundle());
+        let entities = loaded
+            .read_resource::<SaveLoad>()
+            .load(&loaded, &save)
+            .unwrap();
+        assert_eq!(2, entities.len());
+        assert_eq!(Score(42), *loaded.read_resource::<Score>());
+        let names = loaded.read_storage::<Named>();
+        let healths = loaded.read_storage::<Health>();
+        let parents = loaded.read_storage::<Parent>();
+        let (player, _) = (&loaded.entities(), &names)
+            .join()
+            .find(|(_, named)| named.name == "player")
+            .unwrap();
+        assert_eq!(Some(&Health(7)), healths.get(player));
+        let (_, _, sword) = (&loaded.entities(), &names, &parents)
+            .join()
+            .find(|(_, named, _)| named.name == "sword")
+            .unwrap();
+        assert_eq!(player, sword.entity);
+        assert_eq!(1, healths.join().count());
+    }
+
+    #[test]
+    fn round_trip_ron() {
+        round_trip(SaveFormat::Ron);
+    }
+
+    #[test]
+    fn round_trip_binary() {
+        round_trip(SaveFormat::Binary);
+    }
+
+    #[test]
+    fn loading_replaces_persistent_entities() {
+        let mut world = world(SaveLoadBundle::new(1).with_component::<Health>("health"));
+        world
+            .create_entity()
+            .with(Persistent)
+            .with(Health(3))
+            .build();
+        let save = world.read_resource::<SaveLoad>().save(&world).unwrap();
+        world
+            .create_entity()
+            .with(Persistent)
+            .with(Health(5))
+            .build();
+        let kept = world.create_entity().with(Health(9)).build();
+
+        world
+            .read_resource::<SaveLoad>()
+            .load(&world, &save)
+            .unwrap();
+        world.maintain();
+        let healths = world.read_storage::<Health>();
+        let mut all = healths.join().cloned().collect::<Vec<_>>();
+        all.sort_by_key(|health| health.0);
+        assert_eq!(vec![Health(3), Health(9)], all);
+        assert_eq!(Some(&Health(9)), healths.get(kept));
+    }
+
+    #[test]
+    fn old_saves_are_migrated() {
+        for format in &[SaveFormat::Ron, SaveFormat::Binary] {
+            let mut old = world(
+                SaveLoadBundle::new(1)
+                    .with_format(*format)
+                    .with_component::<Health>("vitals"),
+            );
+            old.create_entity()
+                .with(Persistent)
+                .with(Health(10))
+                .build();
+            let save = old.read_resource::<SaveLoad>().save(&old).unwrap();
+
+            let world = world(
+                SaveLoadBundle::new(2)
+                    .with_format(*format)
+                    .with_component::<Vitals>("vitals")
+                    .with_migration("vitals", 1, |Health(health)| Vitals { health, shield: 0 }),
+            );
+            let saveload = world.read_resource::<SaveLoad>();
+            saveload.load(&world, &save).unwrap();
+            let vitals = world.read_storage::<Vitals>();
+            assert_eq!(
+                vec![&Vitals {
+                    health: 10,
+                    shield: 0
+                }],
+                vitals.join().collect::<Vec<_>>()
+            );
+
+            let newer = saveload.save(&world).unwrap();
+            let saveload = old.read_resource::<SaveLoad>();
+            assert!(saveload.load(&old, &newer).is_err());
+        }
+    }
+}
@@ -0,0 +1,336 @@
+//! Snapshots of the simulation state of a `World`, for rollback and replays.
+//!
+//! `WorldSnapshots` keeps a copy of the registered component types and resources for the most
+//! recent fixed updates. When it is in the `World`, the `CoreApplication` captures a snapshot
+//! before every fixed update. Restoring a snapshot rewinds the fixed updates with
+//! `Time::rewind_fixed_update`, so the following fixed updates are simulated again from the
+//! restored state.
+//!
+//! ```
+//! use amethyst::core::{ecs::prelude::*, snapshot::WorldSnapshots, Time, Transform};
+//!
+//! let mut world = World::new();
+//! world.register::<Transform>();
+//! world.insert(Time::default());
+//! world.insert(WorldSnapshots::new(60).with_component::<Transform>());
+//! ```
+
+use std::{any::Any, collections::VecDeque, fmt};
+
+use amethyst_error::{format_err, Error};
+
+use crate::{
+    ecs::{
+        prelude::{Component, Entity, Join, World, WorldExt},
+        shred::Resource,
+    },
+    timing::Time,
+};
+
+type Data = Box<dyn Any + Send + Sync>;
+
+struct Entry {
+    name: &'static str,
+    capture: fn(&World) -> Option<Data>,
+    restore: fn(&World, &Data),
+}
+
+fn capture_component<C>(world: &World) -> Option<Data>
+where
+    C: Component + Clone + Send + Sync,
+{
+    let storage = world.read_storage::<C>();
+    let components = (&world.entities(), &storage)
+        .join()
+        .map(|(entity, component)| (entity, component.clone()))
+        .collect::<Vec<_>>();
+    Some(Box::new(components))
+}
+
+fn restore_component<C>(world: &World, data: &Data)
+where
+    C: Component + Clone + Send + Sync,
+{
+    let components = data
+        .downcast_ref::<Vec<(Entity, C)>>()
+        .expect("Unreachable: Snapshot data has the type it was captured with");
+    let mut storage = world.write_storage::<C>();
+    storage.clear();
+    for (entity, component) in components {
+        // Entities deleted since the snapshot was captured can't be restored.
+        let _ = storage.insert(*entity, component.clone());
+    }
+}
+
+fn capture_resource<R>(world: &World) -> Option<Data>
+where
+    R: Resource + Clone,
+{
+    world
+        .try_fetch::<R>()
+        .map(|resource| Box::new(R::clone(&resource)) as Data)
+}
+
+fn restore_resource<R>(world: &World, data: &Data)
+where
+    R: Resource + Clone,
+{
+    let resource = data
+        .downcast_ref::<R>()
+        .expect("Unreachable: Snapshot data has the type it was captured with");
+    if let Some(mut current) = world.try_fetch_mut::<R>() {
+        *current = resource.clone();
+    }
+}
+
+struct Snapshot {
+    fixed_frame_number: u64,
+    components: Vec<Option<Data>>,
+    resources: Vec<Option<Data>>,
+}
+
+/// Ring buffer of snapshots of the registered component types and resources of the `World`.
+///
+/// Snapshots are identified by the fixed frame number, see `Time::fixed_frame_number`, and
+/// contain the state of the `World` before that fixed update was run. Only the given number of
+/// most recent snapshots are kept.
+///
+/// Entities are not part of the snapshots: restoring a snapshot replaces the registered
+/// components of all entities, but entities deleted since the snapshot was captured don't get
+/// their components back, and entities created since keep existing without them.
+pub struct WorldSnapshots {
+    capacity: usize,
+    components: Vec<Entry>,
+    resources: Vec<Entry>,
+    snapshots: VecDeque<Snapshot>,
+}
+
+impl WorldSnapshots {
+    /// Creates a ring buffer keeping snapshots of the last `capacity` fixed updates.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if `capacity` is zero.
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "Snapshot capacity must be greater than zero");
+        WorldSnapshots {
+            capacity,
+            components: Vec::new(),
+            resources: Vec::new(),
+            snapshots: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    /// Adds the component type `C` to the snapshots. The component has to be registered in the
+    /// `World`.
+    pub fn with_component<C>(mut self) -> Self
+    where
+        C: Component + Clone + Send + Sync,
+    {
+        self.components.push(Entry {
+            name: std::any::type_name::<C>(),
+            capture: capture_component::<C>,
+            restore: restore_component::<C>,
+        });
+        self.snapshots.clear();
+        self
+    }
+
+    /// Adds the resource `R` to the snapshots. The resource isn't restored if it was removed
+    /// from the `World` in the meantime.
+    pub fn with_resource<R>(mut self) -> Self
+    where
+        R: Resource + Clone,
+    {
+        self.resources.push(Entry {
+            name: std::any::type_name::<R>(),
+            capture: capture_resource::<R>,
+            restore: restore_resource::<R>,
+        });
+        self.snapshots.clear();
+        self
+    }
+
+    /// Returns the maximum number of snapshots kept.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns the number of snapshots kept.
+    pub fn len(&self) -> usize {
+        self.snapshots.len()
+    }
+
+    /// Returns `true` if no snapshot was captured yet.
+    pub fn is_empty(&self) -> bool {
+        self.snapshots.is_empty()
+    }
+
+    /// Returns the fixed frame numbers of all kept snapshots, from oldest to newest.
+    pub fn fixed_frames(&self) -> impl Iterator<Item = u64> + '_ {
+        self.snapshots
+            .iter()
+            .map(|snapshot| snapshot.fixed_frame_number)
+    }
+
+    /// Returns `true` if there is a snapshot of the given fixed frame.
+    pub fn contains(&self, fixed_frame_number: u64) -> bool {
+        self.index(fixed_frame_number).is_some()
+    }
+
+    /// Drops all snapshots.
+    pub fn clear(&mut self) {
+        self.snapshots.clear();
+    }
+
+    /// Captures a snapshot for the current fixed frame number of the `Time` resource.
+    ///
+    /// Snapshots of this and later fixed frames, which belong to a timeline that was rewound,
+    /// are replaced. If the buffer is full, the oldest snapshot is dropped.
+    pub fn capture(&mut self, world: &World) {
+        let fixed_frame_number = world.read_resource::<Time>().fixed_frame_number();
+        while let Some(last) = self.snapshots.back() {
+            if last.fixed_frame_number < fixed_frame_number {
+                break;
+            }
+            self.snapshots.pop_back();
+        }
+        if self.snapshots.len() == self.capacity {
+            self.snapshots.pop_front();
+        }
+        self.snapshots.push_back(Snapshot {
+            fixed_frame_number,
+            components: self
+                .components
+                .iter()
+                .map(|entry| (entry.capture)(world))
+                .collect(),
+            resources: self
+                .resources
+                .iter()
+                .map(|entry| (entry.capture)(world))
+                .collect(),
+        });
+    }
+
+    /// Restores the snapshot of the given fixed frame and rewinds the `Time` resource to
+    /// before that fixed frame, so the fixed updates since then are run again.
+    ///
+    /// Fails if there is no snapshot of the given fixed frame, e.g. because it is older than
+    /// all kept snapshots.
+    pub fn restore(&self, world: &World, fixed_frame_number: u64) -> Result<(), Error> {
+        let index = self
+            .index(fixed_frame_number)
+            .ok_or_else(|| format_err!("No snapshot of fixed frame {}", fixed_frame_number))?;
+        let snapshot = &self.snapshots[index];
+        for (entry, data) in self.components.iter().zip(&snapshot.components) {
+            if let Some(data) = data {
+                (entry.restore)(world, data);
+            }
+        }
+        for (entry, data) in self.resources.iter().zip(&snapshot.resources) {
+            if let Some(data) = data {
+                (entry.restore)(world, data);
+            }
+        }
+        world
+            .write_resource::<Time>()
+            .rewind_fixed_update(fixed_frame_number);
+        Ok(())
+    }
+
+    fn index(&self, fixed_frame_number: u64) -> Option<usize> {
+        self.snapshots
+            .binary_search_by_key(&fixed_frame_number, |snapshot| snapshot.fixed_frame_number)
+            .ok()
+    }
+}
+
+impl fmt::Debug for WorldSnapshots {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names = |entries: &[Entry]| entries.iter().map(|e| e.name).collect::<Vec<_>>();
+        f.debug_struct("WorldSnapshots")
+            .field("capacity", &self.capacity)
+            .field("components", &names(&self.components))
+            .field("resources", &names(&self.resources))
+            .field("fixed_frames", &self.fixed_frames().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ecs::prelude::{Builder, Component, DenseVecStorage, World, WorldExt};
+
+    use super::*;
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct Position(i32);
+
+    impl Component for Position {
+        type Storage = DenseVecStorage<Self>;
+    }
+
+    #[derive(Clone, Debug, Default, PartialEq)]
+    struct Ticks(u64);
+
+    /// Runs all pending fixed updates, capturing a snapshot before each of them like the
+    /// `CoreApplication` does.
+    fn fixed_updates(world: &World) {
+        while world.write_resource::<Time>().step_fixed_update() {
+            world.write_resource::<WorldSnapshots>().capture(world);
+            for position in (&mut world.write_storage::<Position>()).join() {
+                position.0 += 1;
+            }
+            world.write_resource::<Ticks>().0 += 1;
+        }
+    }
+
+    fn state(world: &World) -> (Vec<Position>, u64) {
+        (
+            world.read_storage::<Position>().join().cloned().collect(),
+            world.read_resource::<Ticks>().0,
+        )
+    }
+
+    #[test]
+    fn restored_snapshot_is_resimulated() {
+        let mut world = World::new();
+        world.register::<Position>();
+        world.insert(Ticks::default());
+        let mut time = Time::default();
+        time.set_fixed_seconds(1.0);
+        time.set_delta_seconds(5.5);
+        time.start_fixed_update();
+        world.insert(time);
+        world.insert(
+            WorldSnapshots::new(3)
+                .with_component::<Position>()
+                .with_resource::<Ticks>(),
+        );
+        world.create_entity().with(Position(0)).build();
+
+        fixed_updates(&world);
+        let end = state(&world);
+        assert_eq!((vec![Position(5)], 5), end);
+        let snapshots = world.read_resource::<WorldSnapshots>();
+        assert_eq!(vec![3, 4, 5], snapshots.fixed_frames().collect::<Vec<_>>());
+        assert!(snapshots.restore(&world, 2).is_err());
+
+        snapshots.restore(&world, 4).unwrap();
+        drop(snapshots);
+        assert_eq!((vec![Position(3)], 3), state(&world));
+        assert_eq!(3, world.read_resource::<Time>().fixed_frame_number());
+
+        fixed_updates(&world);
+        assert_eq!(end, state(&world));
+        assert_eq!(5, world.read_resource::<Time>().fixed_frame_number());
+        assert_eq!(
+            vec![3, 4, 5],
+            world
+                .read_resource::<WorldSnapshots>()
+                .fixed_frames()
+                .collect::<Vec<_>>()
+        );
+    }
+}
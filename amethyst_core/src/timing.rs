@@ -29,6 +29,12 @@ pub struct Time {
     fixed_time_accumulator: f32,
     /// Fixed update interpolation alpha
     interpolation_alpha: f32,
+    /// The total number of fixed updates that have been run in this session.
+    fixed_frame_number: u64,
+    /// Number of fixed updates to run again after rewinding.
+    fixed_rewind: u64,
+    /// Duration of every frame, replacing the measured time.
+    virtual_delta: Option<Duration>,
 }
 
 impl Time {
@@ -73,6 +79,12 @@ impl Time {
         self.frame_number
     }
 
+    /// Gets the number of the current fixed update. This increments by 1 every fixed update,
+    /// and goes back when the fixed updates are rewound. There is no fixed frame 0.
+    pub fn fixed_frame_number(&self) -> u64 {
+        self.fixed_frame_number
+    }
+
     /// Gets the time since the start of the game, taking into account the speed multiplier.
     pub fn absolute_time(&self) -> Duration {
         self.absolute_time
@@ -98,6 +110,11 @@ impl Time {
         self.time_scale
     }
 
+    /// Gets the duration used for every frame instead of the measured time, if any.
+    pub fn virtual_delta(&self) -> Option<Duration> {
+        self.virtual_delta
+    }
+
     /// Gets the current interpolation alpha factor.
     pub fn interpolation_alpha(&self) -> f32 {
         self.interpolation_alpha
@@ -109,6 +126,7 @@ impl Time {
     /// This should only be called by the engine.  Bad things might happen if you call this in
     /// your game.
     pub fn set_delta_seconds(&mut self, secs: f32) {
+        let secs = self.virtual_delta.map_or(secs, duration_to_secs);
         self.delta_seconds = secs * self.time_scale;
         self.delta_time = secs_to_duration(secs * self.time_scale);
         self.delta_real_seconds = secs;
@@ -123,6 +141,7 @@ impl Time {
     /// This should only be called by the engine.  Bad things might happen if you call this in
     /// your game.
     pub fn set_delta_time(&mut self, time: Duration) {
+        let time = self.virtual_delta.unwrap_or(time);
         self.delta_seconds = duration_to_secs(time) * self.time_scale;
         self.delta_time = secs_to_duration(duration_to_secs(time) * self.time_scale);
         self.delta_real_seconds = duration_to_secs(time);
@@ -132,6 +151,20 @@ impl Time {
         self.absolute_real_time += self.delta_real_time;
     }
 
+    /// Makes every frame last exactly the given duration instead of the measured time, starting
+    /// with the current frame. Passing `None` goes back to measuring the time.
+    ///
+    /// This makes frame timing deterministic, e.g. when replaying recorded input or running a
+    /// simulation in tests.
+    pub fn set_virtual_delta(&mut self, delta: Option<Duration>) {
+        self.virtual_delta = delta;
+        if let Some(delta) = delta {
+            self.absolute_time -= self.delta_time;
+            self.absolute_real_time -= self.delta_real_time;
+            self.set_delta_time(delta);
+        }
+    }
+
     /// Sets both `fixed_seconds` and `fixed_time` based on the seconds given.
     pub fn set_fixed_seconds(&mut self, secs: f32) {
         self.fixed_seconds = secs;
@@ -178,14 +211,32 @@ impl Time {
     /// This should only be called by the engine.  Bad things might happen if you call this in
     /// your game.
     pub fn step_fixed_update(&mut self) -> bool {
-        if self.fixed_time_accumulator >= self.fixed_seconds {
+        if self.fixed_rewind > 0 {
+            self.fixed_rewind -= 1;
+            self.fixed_frame_number += 1;
+            true
+        } else if self.fixed_time_accumulator >= self.fixed_seconds {
             self.fixed_time_accumulator -= self.fixed_seconds;
+            self.fixed_frame_number += 1;
             true
         } else {
             false
         }
     }
 
+    /// Goes back to before the given fixed update, so `step_fixed_update` runs all fixed updates
+    /// from `fixed_frame_number` up to the current one again before continuing as usual.
+    ///
+    /// Used to re-simulate fixed updates after restoring a `WorldSnapshots` snapshot. Does
+    /// nothing if the given fixed update hasn't been run yet.
+    pub fn rewind_fixed_update(&mut self, fixed_frame_number: u64) {
+        let target = fixed_frame_number.max(1) - 1;
+        if target < self.fixed_frame_number {
+            self.fixed_rewind += self.fixed_frame_number - target;
+            self.fixed_frame_number = target;
+        }
+    }
+
     /// Updates the interpolation alpha factor given the current fixed update rate and accumulator.
     ///
     /// This should only be called by the engine.  Bad things might happen if you call this in
@@ -207,6 +258,9 @@ impl Default for Time {
             fixed_time_accumulator: 0.0,
             frame_number: 0,
             interpolation_alpha: 0.0,
+            fixed_frame_number: 0,
+            fixed_rewind: 0,
+            virtual_delta: None,
             absolute_real_time: Duration::default(),
             absolute_time: Duration::default(),
             time_scale: 1.0,
@@ -431,6 +485,44 @@ mod tests {
         }
         assert_eq!(fixed_count, 2);
     }
+
+    // Test that a virtual delta replaces the measured frame time, including the current frame
+    #[test]
+    fn virtual_delta() {
+        use super::Time;
+
+        let mut time = Time::default();
+        time.set_delta_seconds(0.5);
+        time.set_virtual_delta(Some(Duration::from_millis(100)));
+        time.set_delta_seconds(0.7);
+        assert_eq!(time.delta_time(), Duration::from_millis(100));
+        assert_eq!(time.absolute_time(), Duration::from_millis(200));
+
+        time.set_virtual_delta(None);
+        time.set_delta_time(Duration::from_millis(300));
+        assert_eq!(time.absolute_time(), Duration::from_millis(500));
+    }
+
+    // Test that rewound fixed updates are run again with the same fixed frame numbers
+    #[test]
+    fn fixed_update_rewind() {
+        use super::Time;
+
+        let mut time = Time::default();
+        time.set_fixed_seconds(1.0);
+        time.set_delta_seconds(3.5);
+        time.start_fixed_update();
+        while time.step_fixed_update() {}
+        assert_eq!(time.fixed_frame_number(), 3);
+
+        time.rewind_fixed_update(2);
+        time.rewind_fixed_update(5);
+        let mut frames = Vec::new();
+        while time.step_fixed_update() {
+            frames.push(time.fixed_frame_number());
+        }
+        assert_eq!(frames, vec![2, 3]);
+    }
 }
 
 /// Converts a Duration to the time in seconds.
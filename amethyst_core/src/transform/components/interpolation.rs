@@ -0,0 +1,102 @@
+use crate::{
+    ecs::{
+        prelude::{Component, DenseVecStorage, Join, ReadStorage, World, WriteStorage},
+        storage::MaskedStorage,
+    },
+    math::Translation3,
+    transform::Transform,
+};
+
+/// Smooths the rendered movement of an entity which is moved in `fixed_update`.
+///
+/// The local `Transform` is captured after every fixed update by
+/// `capture_transform_interpolations`, so the component always holds the transforms of the
+/// last two fixed updates, no matter how many fixed updates ran in a frame. Every frame, the
+/// `TransformInterpolationSystem` writes a transform interpolated between them by
+/// `Time::interpolation_alpha` into the global matrix. The local `Transform` is not changed, so
+/// the simulation is not affected, but everything reading the global matrix, most notably
+/// rendering, sees the entity up to one fixed update behind the simulation.
+///
+/// Use `reset` after teleporting the entity, so it doesn't visibly move to its new position.
+#[derive(Clone, Debug, Default)]
+pub struct TransformInterpolation {
+    pub(crate) previous: Transform,
+    pub(crate) current: Transform,
+    /// Whether a fixed update was captured since the component was created or reset.
+    pub(crate) captured: bool,
+}
+
+impl TransformInterpolation {
+    /// Creates the component, which starts interpolating after the next fixed update.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// The local transform after the second to last fixed update.
+    pub fn previous(&self) -> &Transform {
+        &self.previous
+    }
+
+    /// The local transform after the last fixed update.
+    pub fn current(&self) -> &Transform {
+        &self.current
+    }
+
+    /// Interpolates between the transforms of the last two fixed updates, `alpha` ranges from
+    /// `0.0` for the previous to `1.0` for the current transform.
+    pub fn interpolate(&self, alpha: f32) -> Transform {
+        let translation = self
+            .previous
+            .translation()
+            .lerp(self.current.translation(), alpha);
+        let rotation = self
+            .previous
+            .rotation()
+            .try_slerp(self.current.rotation(), alpha, f32::EPSILON)
+            .unwrap_or_else(|| *self.current.rotation());
+        let scale = self.previous.scale().lerp(self.current.scale(), alpha);
+
+        Transform::new(Translation3::from(translation), rotation, scale)
+    }
+
+    /// Stops interpolating from the previous transform, e.g. after the entity was teleported.
+    ///
+    /// The entity is shown at its current transform until the next two fixed updates were
+    /// captured.
+    pub fn reset(&mut self) {
+        self.captured = false;
+    }
+
+    fn capture(&mut self, local: &Transform) {
+        if self.captured {
+            self.previous = std::mem::replace(&mut self.current, local.clone());
+        } else {
+            self.previous = local.clone();
+            self.current = local.clone();
+            self.captured = true;
+        }
+    }
+}
+
+impl Component for TransformInterpolation {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Captures the local transforms of all entities with a `TransformInterpolation`, the
+/// transform captured before becomes the previous one.
+///
+/// Has to be called after every fixed update, `CoreApplication` does so right after
+/// `State::fixed_update`. Does nothing if `TransformInterpolation` isn't registered.
+pub fn capture_transform_interpolations(world: &World) {
+    if !world.has_value::<MaskedStorage<TransformInterpolation>>() {
+        return;
+    }
+
+    let (locals, mut interpolations) = world.system_data::<(
+        ReadStorage<'_, Transform>,
+        WriteStorage<'_, TransformInterpolation>,
+    )>();
+    for (local, interpolation) in (&locals, &mut interpolations).join() {
+        interpolation.capture(local);
+    }
+}
@@ -1,9 +1,11 @@
 //! Components for the transform processor.
 
 pub use self::{
+    interpolation::{capture_transform_interpolations, TransformInterpolation},
     parent::{HierarchyEvent, Parent, ParentHierarchy},
     transform::Transform,
 };
 
+mod interpolation;
 mod parent;
 mod transform;
@@ -0,0 +1,483 @@
+//! Utilities for working with the entity hierarchy defined by the `Parent` component.
+//!
+//! `DepthFirst` and `BreadthFirst` iterate over the descendants of an entity in a
+//! `ParentHierarchy`, and can be used in systems. The `HierarchyExt` trait adds operations on
+//! whole subtrees to the `World`. These bring the `ParentHierarchy` up to date before using it,
+//! so they see `Parent` components that were changed since the `HierarchySystem` last ran.
+
+use std::collections::VecDeque;
+
+use amethyst_error::{format_err, Error};
+use specs_hierarchy::ParentData;
+
+use crate::{
+    ecs::{
+        prelude::{Component, Entity, ReadStorage, World, WorldExt},
+        shred::Fetch,
+    },
+    math::{Matrix3, Matrix4, Rotation3, Translation3, UnitQuaternion, Vector3, U3},
+    named::Named,
+    transform::{Parent, ParentHierarchy, Transform},
+};
+
+/// Iterates over all descendants of an entity, each entity is followed by its own descendants
+/// before its next sibling.
+///
+/// The entity itself is not included.
+pub struct DepthFirst<'a> {
+    hierarchy: &'a ParentHierarchy,
+    stack: Vec<Entity>,
+}
+
+impl<'a> DepthFirst<'a> {
+    /// Creates an iterator over the descendants of `entity`.
+    pub fn new(hierarchy: &'a ParentHierarchy, entity: Entity) -> Self {
+        DepthFirst {
+            hierarchy,
+            stack: hierarchy.children(entity).iter().rev().cloned().collect(),
+        }
+    }
+}
+
+impl Iterator for DepthFirst<'_> {
+    type Item = Entity;
+
+    fn next(&mut self) -> Option<Entity> {
+        let entity = self.stack.pop()?;
+        self.stack
+            .extend(self.hierarchy.children(entity).iter().rev().cloned());
+        Some(entity)
+    }
+}
+
+impl std::fmt::Debug for DepthFirst<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DepthFirst")
+            .field("stack", &self.stack)
+            .finish()
+    }
+}
+
+/// Iterates over all descendants of an entity, level by level: first the children, then the
+/// grandchildren and so on.
+///
+/// The entity itself is not included.
+pub struct BreadthFirst<'a> {
+    hierarchy: &'a ParentHierarchy,
+    queue: VecDeque<Entity>,
+}
+
+impl<'a> BreadthFirst<'a> {
+    /// Creates an iterator over the descendants of `entity`.
+    pub fn new(hierarchy: &'a ParentHierarchy, entity: Entity) -> Self {
+        BreadthFirst {
+            hierarchy,
+            queue: hierarchy.children(entity).iter().cloned().collect(),
+        }
+    }
+}
+
+impl Iterator for BreadthFirst<'_> {
+    type Item = Entity;
+
+    fn next(&mut self) -> Option<Entity> {
+        let entity = self.queue.pop_front()?;
+        self.queue
+            .extend(self.hierarchy.children(entity).iter().cloned());
+        Some(entity)
+    }
+}
+
+impl std::fmt::Debug for BreadthFirst<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BreadthFirst")
+            .field("queue", &self.queue)
+            .finish()
+    }
+}
+
+/// Resource listing the component types `HierarchyExt::clone_subtree` copies to the clones.
+///
+/// ```
+/// use amethyst::core::{ecs::prelude::*, transform::CloneableComponents, Named, Transform};
+///
+/// let mut world = World::new();
+/// world.insert(
+///     CloneableComponents::new()
+///         .with_component::<Transform>()
+///         .with_component::<Named>(),
+/// );
+/// ```
+#[derive(Default)]
+pub struct CloneableComponents {
+    components: Vec<(&'static str, fn(&World, Entity, Entity))>,
+}
+
+impl CloneableComponents {
+    /// Creates an empty list, only the `Parent` components are set up for the clones.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Adds the component type `C`. The component has to be registered in the `World`.
+    pub fn with_component<C>(mut self) -> Self
+    where
+        C: Component + Clone + Send + Sync,
+    {
+        self.components
+            .push((std::any::type_name::<C>(), clone_component::<C>));
+        self
+    }
+}
+
+impl std::fmt::Debug for CloneableComponents {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list()
+            .entries(self.components.iter().map(|(name, _)| name))
+            .finish()
+    }
+}
+
+fn clone_component<C>(world: &World, from: Entity, to: Entity)
+where
+    C: Component + Clone + Send + Sync,
+{
+    let mut storage = world.write_storage::<C>();
+    if let Some(component) = storage.get(from).cloned() {
+        storage
+            .insert(to, component)
+            .expect("Unreachable: The clone was just created");
+    }
+}
+
+/// Operations on whole subtrees of the entity hierarchy.
+///
+/// All operations need the `ParentHierarchy` resource, which is set up by the
+/// `TransformBundle`.
+pub trait HierarchyExt {
+    /// Moves `entity` below `parent`, or to the top of the hierarchy if `parent` is `None`.
+    ///
+    /// If the entity has a `Transform`, it is changed so that the entity keeps its position,
+    /// rotation and scale in the world. A shear, which can result from a non-uniform scale
+    /// in the old or new parents, is lost.
+    ///
+    /// Fails if one of the entities is dead or `parent` is `entity` or one of its descendants.
+    fn reparent(&mut self, entity: Entity, parent: Option<Entity>) -> Result<(), Error>;
+
+    /// Deletes `entity` and all its descendants.
+    fn despawn_recursive(&mut self, entity: Entity) -> Result<(), Error>;
+
+    /// Finds the descendant of `entity` at the given path of `Named` components, separated by
+    /// `/`, e.g. `"body/arm/hand"` for the entity named "hand", below a child named "arm" of a
+    /// child named "body" of `entity`. If several children have the same name, the first one
+    /// is used.
+    fn find_by_path(&self, entity: Entity, path: &str) -> Option<Entity>;
+
+    /// Creates a copy of `entity` and all its descendants, with the same parent as `entity`,
+    /// and returns the copy of `entity`.
+    ///
+    /// The components listed in the `CloneableComponents` resource are copied, if there is
+    /// none only the hierarchy is copied.
+    fn clone_subtree(&mut self, entity: Entity) -> Result<Entity, Error>;
+}
+
+impl HierarchyExt for World {
+    fn reparent(&mut self, entity: Entity, parent: Option<Entity>) -> Result<(), Error> {
+        check_alive(self, entity)?;
+        if let Some(parent) = parent {
+            check_alive(self, parent)?;
+            let hierarchy = maintained_hierarchy(self)?;
+            if parent == entity || DepthFirst::new(&hierarchy, entity).any(|e| e == parent) {
+                return Err(format_err!(
+                    "Can't move entity {:?} below itself or its descendant {:?}",
+                    entity,
+                    parent
+                ));
+            }
+        }
+
+        let local = {
+            let transforms = self.read_storage::<Transform>();
+            let parents = self.read_storage::<Parent>();
+            match transforms.get(entity) {
+                Some(_) => {
+                    let global = global_matrix(&transforms, &parents, Some(entity));
+                    let parent_global = global_matrix(&transforms, &parents, parent);
+                    let local = parent_global
+                        .try_inverse()
+                        .ok_or_else(|| format_err!("Can't move entity below a zero scale"))?
+                        * global;
+                    Some(decompose(&local))
+                }
+                None => None,
+            }
+        };
+        if let Some(local) = local {
+            *self
+                .write_storage::<Transform>()
+                .get_mut(entity)
+                .expect("Unreachable: The entity has a transform") = local;
+        }
+
+        let mut parents = self.write_storage::<Parent>();
+        match parent {
+            Some(parent) => {
+                parents.insert(entity, Parent::new(parent))?;
+            }
+            None => {
+                parents.remove(entity);
+            }
+        }
+        Ok(())
+    }
+
+    fn despawn_recursive(&mut self, entity: Entity) -> Result<(), Error> {
+        check_alive(self, entity)?;
+        let mut entities =
+            DepthFirst::new(&*maintained_hierarchy(self)?, entity).collect::<Vec<_>>();
+        entities.push(entity);
+        self.delete_entities(&entities)?;
+        Ok(())
+    }
+
+    fn find_by_path(&self, entity: Entity, path: &str) -> Option<Entity> {
+        let hierarchy = maintained_hierarchy(self).ok()?;
+        let names = self.read_storage::<Named>();
+        path.split('/')
+            .filter(|name| !name.is_empty())
+            .try_fold(entity, |entity, name| {
+                hierarchy
+                    .children(entity)
+                    .iter()
+                    .find(|child| match names.get(**child) {
+                        Some(named) => named.name == name,
+                        None => false,
+                    })
+                    .cloned()
+            })
+    }
+
+    fn clone_subtree(&mut self, entity: Entity) -> Result<Entity, Error> {
+        check_alive(self, entity)?;
+        let mut originals = vec![entity];
+        originals.extend(DepthFirst::new(&*maintained_hierarchy(self)?, entity));
+
+        let clones = {
+            let entities = self.entities();
+            originals
+                .iter()
+                .map(|_| entities.create())
+                .collect::<Vec<_>>()
+        };
+
+        if let Some(cloneable) = self.try_fetch::<CloneableComponents>() {
+            for (original, clone) in originals.iter().zip(&clones) {
+                for (_, clone_component) in &cloneable.components {
+                    clone_component(self, *original, *clone);
+                }
+            }
+        }
+
+        let mut parents = self.write_storage::<Parent>();
+        if let Some(parent) = parents.get(entity).cloned() {
+            parents.insert(clones[0], parent)?;
+        }
+        for (original, clone) in originals.iter().zip(&clones).skip(1) {
+            let parent = parents
+                .get(*original)
+                .expect("Unreachable: Descendants have a parent")
+                .entity;
+            let index = originals
+                .iter()
+                .position(|e| *e == parent)
+                .expect("Unreachable: Parents precede their children");
+            parents.insert(*clone, Parent::new(clones[index]))?;
+        }
+        Ok(clones[0])
+    }
+}
+
+fn check_alive(world: &World, entity: Entity) -> Result<(), Error> {
+    if world.is_alive(entity) {
+        Ok(())
+    } else {
+        Err(format_err!("Entity {:?} is not alive", entity))
+    }
+}
+
+/// Brings the `ParentHierarchy` up to date with the `Parent` components, like the
+/// `HierarchySystem` does.
+fn maintained_hierarchy(world: &World) -> Result<Fetch<'_, ParentHierarchy>, Error> {
+    let mut hierarchy = world
+        .try_fetch_mut::<ParentHierarchy>()
+        .ok_or_else(|| format_err!("There is no ParentHierarchy, add the TransformBundle"))?;
+    hierarchy.maintain(world.system_data::<ParentData<'_, Parent>>());
+    drop(hierarchy);
+    Ok(world.fetch::<ParentHierarchy>())
+}
+
+/// Computes the global matrix from the local transforms, since `Transform::global_matrix` is
+/// only updated by the `TransformSystem`.
+fn global_matrix(
+    transforms: &ReadStorage<'_, Transform>,
+    parents: &ReadStorage<'_, Parent>,
+    mut entity: Option<Entity>,
+) -> Matrix4<f32> {
+    let mut matrix = Matrix4::identity();
+    while let Some(current) = entity {
+        if let Some(transform) = transforms.get(current) {
+            matrix = transform.matrix() * matrix;
+        }
+        entity = parents.get(current).map(|parent| parent.entity);
+    }
+    matrix
+}
+
+fn decompose(matrix: &Matrix4<f32>) -> Transform {
+    let translation = Translation3::new(matrix[(0, 3)], matrix[(1, 3)], matrix[(2, 3)]);
+    let mut linear: Matrix3<f32> = matrix.fixed_slice::<U3, U3>(0, 0).into();
+    let mut scale = Vector3::new(
+        linear.column(0).norm(),
+        linear.column(1).norm(),
+        linear.column(2).norm(),
+    );
+    if linear.determinant() < 0.0 {
+        scale.x = -scale.x;
+    }
+    for (i, s) in scale.iter().enumerate() {
+        if s.abs() > f32::EPSILON {
+            linear.column_mut(i).unscale_mut(*s);
+        }
+    }
+    let rotation = UnitQuaternion::from_rotation_matrix(&Rotation3::from_matrix_unchecked(linear));
+    Transform::new(translation, rotation, scale)
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+    use specs_hierarchy::HierarchySystem;
+
+    use super::*;
+    use crate::{
+        ecs::prelude::{Builder, Join, System},
+        named::WithNamed,
+    };
+
+    fn world() -> World {
+        let mut world = World::new();
+        world.register::<Transform>();
+        world.register::<Named>();
+        let mut system = HierarchySystem::<Parent>::new(&mut world);
+        System::setup(&mut system, &mut world);
+        world
+    }
+
+    fn child(world: &mut World, parent: Entity, name: &'static str) -> Entity {
+        world
+            .create_entity()
+            .with(Parent::new(parent))
+            .with(Transform::default())
+            .named(name)
+            .build()
+    }
+
+    #[test]
+    fn traversal_and_paths() {
+        let mut world = world();
+        let root = world.create_entity().named("root").build();
+        let body = child(&mut world, root, "body");
+        let arm = child(&mut world, body, "arm");
+        let hand = child(&mut world, arm, "hand");
+        let head = child(&mut world, root, "head");
+
+        let hierarchy = maintained_hierarchy(&world).unwrap();
+        assert_eq!(
+            vec![body, arm, hand, head],
+            DepthFirst::new(&hierarchy, root).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![body, head, arm, hand],
+            BreadthFirst::new(&hierarchy, root).collect::<Vec<_>>()
+        );
+        drop(hierarchy);
+
+        assert_eq!(Some(hand), world.find_by_path(root, "body/arm/hand"));
+        assert_eq!(Some(head), world.find_by_path(root, "head"));
+        assert_eq!(None, world.find_by_path(root, "body/hand"));
+
+        world.despawn_recursive(body).unwrap();
+        assert!(world.is_alive(root) && world.is_alive(head));
+        assert!(!world.is_alive(body) && !world.is_alive(arm) && !world.is_alive(hand));
+    }
+
+    #[test]
+    fn reparent_keeps_world_transform() {
+        let mut world = world();
+        let mut transform = Transform::default();
+        transform.set_translation_xyz(1.0, 2.0, 3.0);
+        transform.set_rotation_z_axis(1.0);
+        transform.set_scale(Vector3::new(2.0, 2.0, 2.0));
+        let old_parent = world.create_entity().with(transform).build();
+        let mut transform = Transform::default();
+        transform.set_translation_xyz(-4.0, 0.5, 0.0);
+        transform.set_rotation_x_axis(0.5);
+        let new_parent = world.create_entity().with(transform).build();
+        let mut transform = Transform::default();
+        transform.set_translation_xyz(0.0, 1.0, 0.0);
+        transform.set_rotation_y_axis(0.3);
+        let entity = world
+            .create_entity()
+            .with(transform)
+            .with(Parent::new(old_parent))
+            .build();
+
+        let global = |world: &World| {
+            global_matrix(
+                &world.read_storage::<Transform>(),
+                &world.read_storage::<Parent>(),
+                Some(entity),
+            )
+        };
+        let before = global(&world);
+        world.reparent(entity, Some(new_parent)).unwrap();
+        assert_eq!(
+            new_parent,
+            world.read_storage::<Parent>().get(entity).unwrap().entity
+        );
+        assert_relative_eq!(before, global(&world), epsilon = 1e-5);
+        world.reparent(entity, None).unwrap();
+        assert!(world.read_storage::<Parent>().get(entity).is_none());
+        assert_relative_eq!(before, global(&world), epsilon = 1e-5);
+
+        world.reparent(new_parent, Some(entity)).unwrap();
+        assert!(world.reparent(entity, Some(new_parent)).is_err());
+    }
+
+    #[test]
+    fn clone_subtree() {
+        let mut world = world();
+        world.insert(CloneableComponents::new().with_component::<Named>());
+        let root = world.create_entity().named("root").build();
+        let body = child(&mut world, root, "body");
+        let arm = child(&mut world, body, "arm");
+
+        let clone = world.clone_subtree(body).unwrap();
+        assert_ne!(body, clone);
+        assert_eq!(
+            root,
+            world.read_storage::<Parent>().get(clone).unwrap().entity
+        );
+        let cloned_arm = world.find_by_path(clone, "arm").unwrap();
+        assert_ne!(arm, cloned_arm);
+        // Only the registered components are cloned.
+        assert!(world.read_storage::<Transform>().get(cloned_arm).is_none());
+        assert_eq!(
+            2,
+            (&world.read_storage::<Parent>())
+                .join()
+                .filter(|parent| parent.entity == root)
+                .count()
+        );
+    }
+}
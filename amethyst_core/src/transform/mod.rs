@@ -1,7 +1,13 @@
 //! `amethyst` transform ecs module
 
-pub use self::{bundle::TransformBundle, components::*, systems::*};
+pub use self::{
+    bundle::TransformBundle,
+    components::*,
+    hierarchy::{BreadthFirst, CloneableComponents, DepthFirst, HierarchyExt},
+    systems::*,
+};
 
 pub mod bundle;
 pub mod components;
+pub mod hierarchy;
 pub mod systems;
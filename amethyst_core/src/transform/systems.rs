@@ -4,14 +4,18 @@ use crate::{
     ecs::{
         hibitset::BitSet,
         prelude::{
-            ComponentEvent, Entities, Join, ReadExpect, ReadStorage, ReaderId, System, SystemData,
-            World, WriteStorage,
+            ComponentEvent, Entities, Entity, Join, Read, ReadExpect, ReadStorage, ReaderId,
+            System, SystemData, World, WriteStorage,
         },
     },
+    math::Matrix4,
+    timing::Time,
     SystemDesc,
 };
 
-use crate::transform::{HierarchyEvent, Parent, ParentHierarchy, Transform};
+use crate::transform::{
+    HierarchyEvent, Parent, ParentHierarchy, Transform, TransformInterpolation,
+};
 
 #[cfg(feature = "profiler")]
 use thread_profiler::profile_scope;
@@ -140,6 +144,68 @@ impl<'a> System<'a> for TransformSystem {
     }
 }
 
+/// Writes the interpolated transforms of entities with a `TransformInterpolation` component
+/// into their global matrix.
+///
+/// The transforms to interpolate between are captured after every fixed update by
+/// `capture_transform_interpolations`, this system only interpolates between them.
+///
+/// Has to run after the `TransformSystem`, the `TransformBundle` adds it as
+/// "transform_interpolation_system". The global matrix of an entity with a parent is the
+/// global matrix of the parent, as computed by the `TransformSystem`, times the interpolated
+/// local transform. Children of interpolated entities are not interpolated themselves unless
+/// they have the component as well.
+#[derive(Debug, Default)]
+pub struct TransformInterpolationSystem {
+    interpolated: Vec<(Entity, Matrix4<f32>)>,
+}
+
+impl TransformInterpolationSystem {
+    /// Creates a new transform interpolation system.
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+impl<'a> System<'a> for TransformInterpolationSystem {
+    type SystemData = (
+        Entities<'a>,
+        Read<'a, Time>,
+        ReadStorage<'a, Parent>,
+        WriteStorage<'a, Transform>,
+        ReadStorage<'a, TransformInterpolation>,
+    );
+
+    fn run(&mut self, (entities, time, parents, mut locals, interpolations): Self::SystemData) {
+        #[cfg(feature = "profiler")]
+        profile_scope!("transform_interpolation_system");
+
+        let alpha = time.interpolation_alpha();
+        for (entity, interpolation) in (&entities, &interpolations).join() {
+            // Until a fixed update was captured, the local transform is shown as it is.
+            if !interpolation.captured {
+                continue;
+            }
+
+            let matrix = interpolation.interpolate(alpha).matrix();
+            let matrix = match parents.get(entity).and_then(|p| locals.get(p.entity)) {
+                Some(parent) => parent.global_matrix * matrix,
+                None => matrix,
+            };
+            self.interpolated.push((entity, matrix));
+        }
+
+        // Only the global matrix is changed, which doesn't concern the `TransformSystem`.
+        locals.set_event_emission(false);
+        for (entity, matrix) in self.interpolated.drain(..) {
+            if let Some(local) = locals.get_mut(entity) {
+                local.global_matrix = matrix;
+            }
+        }
+        locals.set_event_emission(true);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::{
@@ -486,4 +552,76 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn interpolation() {
+        use crate::{
+            ecs::prelude::{Join, WriteStorage},
+            timing::Time,
+            transform::{
+                capture_transform_interpolations, TransformInterpolation,
+                TransformInterpolationSystem,
+            },
+        };
+
+        let (mut world, mut hs, mut system) = transform_world();
+        let mut interpolation_system = TransformInterpolationSystem::new();
+        interpolation_system.setup(&mut world);
+        let mut time = Time::default();
+        time.set_fixed_seconds(1.0);
+        world.insert(time);
+
+        let mut parent = Transform::default();
+        parent.set_translation_xyz(10.0, 0.0, 0.0);
+        let parent = world.create_entity().with(parent).build();
+        let child = world
+            .create_entity()
+            .with(Parent { entity: parent })
+            .with(Transform::default())
+            .with(TransformInterpolation::new())
+            .build();
+
+        // The last two frames run more than two fixed updates each.
+        let mut rendered = Vec::new();
+        for &delta in &[0.5, 0.5, 0.5, 0.5, 2.5, 2.5] {
+            {
+                let mut time = world.write_resource::<Time>();
+                time.set_delta_seconds(delta);
+                time.start_fixed_update();
+            }
+            while world.write_resource::<Time>().step_fixed_update() {
+                world.exec(
+                    |(mut locals, interpolations): (
+                        WriteStorage<'_, Transform>,
+                        WriteStorage<'_, TransformInterpolation>,
+                    )| {
+                        for (local, _) in (&mut locals, &interpolations).join() {
+                            local.prepend_translation_x(1.0);
+                        }
+                    },
+                );
+                capture_transform_interpolations(&world);
+            }
+            world.write_resource::<Time>().finish_fixed_update();
+
+            hs.run_now(&world);
+            system.run_now(&world);
+            interpolation_system.run_now(&world);
+            let transforms = world.read_storage::<Transform>();
+            let transform = transforms.get(child).unwrap();
+            rendered.push((transform.translation().x, transform.global_matrix()[(0, 3)]));
+        }
+
+        assert_eq!(
+            vec![
+                (0.0, 10.0),
+                (1.0, 11.0),
+                (1.0, 11.0),
+                (2.0, 11.0),
+                (4.0, 13.5),
+                (7.0, 16.0),
+            ],
+            rendered
+        );
+    }
 }
@@ -34,6 +34,9 @@ pub fn event_reader_derive(input: TokenStream) -> TokenStream {
 /// `amethyst:assets::{PrefabData, ProgressCounter}` and
 /// `amethyst::error::Error` are imported and visible in the current scope. This
 /// is due to how Rust macros work.
+///
+/// Adding `#[prefab(Export)]` to the type also derives `ExportPrefabData`, which additionally
+/// requires `amethyst::assets::ExportPrefabData` and `amethyst::ecs::ReadStorage` to be in scope.
 #[proc_macro_derive(PrefabData, attributes(prefab))]
 pub fn prefab_data_derive(input: TokenStream) -> TokenStream {
     let ast = parse_macro_input!(input as DeriveInput);
@@ -8,10 +8,24 @@ use syn::{
 };
 
 pub fn impl_prefab_data(ast: &DeriveInput) -> TokenStream {
-    if is_component_prefab(&ast.attrs[..]) {
+    let is_component = is_component_prefab(&ast.attrs[..]);
+    let prefab_data = if is_component {
         impl_prefab_data_component(ast)
     } else {
         impl_prefab_data_aggregate(ast)
+    };
+    if !is_export_prefab(&ast.attrs[..]) {
+        return prefab_data;
+    }
+
+    let export_prefab_data = if is_component {
+        impl_export_prefab_data_component(ast)
+    } else {
+        impl_export_prefab_data_aggregate(ast)
+    };
+    quote! {
+        #prefab_data
+        #export_prefab_data
     }
 }
 
@@ -34,6 +48,13 @@ fn impl_prefab_data_component(ast: &DeriveInput) -> TokenStream {
                 system_data.insert(entity, self.clone()).map(|_| ())?;
                 Ok(())
             }
+
+            fn remove_from_entity(&self,
+                                  entity: Entity,
+                                  system_data: &mut Self::SystemData) -> ::std::result::Result<(), Error> {
+                system_data.remove(entity);
+                Ok(())
+            }
         }
     }
 }
@@ -46,9 +67,10 @@ fn is_aggregate_prefab(attrs: &[Attribute]) -> bool {
 fn prepare_prefab_aggregate_fields(
     data_types: &mut Vec<(Type, bool)>,
     fields: &Fields,
-) -> (Vec<TokenStream>, Vec<Option<TokenStream>>) {
+) -> (Vec<TokenStream>, Vec<Option<TokenStream>>, Vec<TokenStream>) {
     let mut subs = Vec::new();
     let mut add_to_entity = Vec::new();
+    let mut remove_from_entity = Vec::new();
     for (field_number, field) in fields.iter().enumerate() {
         let is_component = is_component_prefab(&field.attrs[..]);
         // Since there may be multiple fields that use the same prefab data type, we keep track of whether
@@ -73,6 +95,9 @@ fn prepare_prefab_aggregate_fields(
             add_to_entity.push(quote! {
                 system_data.#tuple_index.insert(entity, #name.clone())?;
             });
+            remove_from_entity.push(quote! {
+                system_data.#tuple_index.remove(entity);
+            });
         } else {
             subs.push(Some(quote! {
                 if #name.load_sub_assets(progress, &mut system_data.#tuple_index)? {
@@ -82,33 +107,37 @@ fn prepare_prefab_aggregate_fields(
             add_to_entity.push(quote! {
                 #name.add_to_entity(entity, &mut system_data.#tuple_index, entities, children)?;
             });
+            remove_from_entity.push(quote! {
+                #name.remove_from_entity(entity, &mut system_data.#tuple_index)?;
+            });
         }
     }
-    (add_to_entity, subs)
+    (add_to_entity, subs, remove_from_entity)
 }
 
 fn prepare_prefab_aggregate_struct(
     data: &DataStruct,
-) -> (Vec<(Type, bool)>, TokenStream, TokenStream) {
+) -> (Vec<(Type, bool)>, TokenStream, TokenStream, TokenStream) {
     let mut data_types = Vec::new();
-    let (add_to_entity, subs) = prepare_prefab_aggregate_fields(&mut data_types, &data.fields);
-    let extract_fields_add =
-        data.fields
-            .iter()
-            .enumerate()
-            .map(|(field_number, field)| match &field.ident {
-                Some(name) => quote! {
-                    let #name = &self.#name;
-                },
-                None => {
-                    let var_name =
-                        Ident::new(&format!("field_{}", field_number), Span::call_site());
-                    let number = Literal::usize_unsuffixed(field_number);
-                    quote! {
-                        let #var_name = &self.#number;
-                    }
+    let (add_to_entity, subs, remove_from_entity) =
+        prepare_prefab_aggregate_fields(&mut data_types, &data.fields);
+    let extract_fields_add: Vec<_> = data
+        .fields
+        .iter()
+        .enumerate()
+        .map(|(field_number, field)| match &field.ident {
+            Some(name) => quote! {
+                let #name = &self.#name;
+            },
+            None => {
+                let var_name = Ident::new(&format!("field_{}", field_number), Span::call_site());
+                let number = Literal::usize_unsuffixed(field_number);
+                quote! {
+                    let #var_name = &self.#number;
                 }
-            });
+            }
+        })
+        .collect();
     let extract_fields_sub = data.fields.iter().enumerate().map(|(field_number, field)| {
         if is_aggregate_prefab(&field.attrs[..]) {
             match &field.ident {
@@ -139,19 +168,24 @@ fn prepare_prefab_aggregate_struct(
             #(#extract_fields_sub)*
             #(#subs)*
         },
+        quote! {
+            #(#extract_fields_add)*
+            #(#remove_from_entity)*
+        },
     )
 }
 
 fn prepare_prefab_aggregate_enum(
     base: &Ident,
     data: &DataEnum,
-) -> (Vec<(Type, bool)>, TokenStream, TokenStream) {
+) -> (Vec<(Type, bool)>, TokenStream, TokenStream, TokenStream) {
     let mut data_types = Vec::new();
     let mut subs = Vec::new();
     let mut add_to_entity = Vec::new();
+    let mut remove_from_entity = Vec::new();
 
     for variant in &data.variants {
-        let (variant_add_to_entity, variant_subs) =
+        let (variant_add_to_entity, variant_subs, variant_remove_from_entity) =
             prepare_prefab_aggregate_fields(&mut data_types, &variant.fields);
         let field_names_add: Vec<_> = variant
             .fields
@@ -206,6 +240,21 @@ fn prepare_prefab_aggregate_enum(
                 #base::#ident => ()
             },
         });
+        remove_from_entity.push(match variant.fields {
+            Fields::Named(_) => quote! {
+                #base::#ident {#(#field_names_add,)*} => {
+                    #(#variant_remove_from_entity)*
+                }
+            },
+            Fields::Unnamed(_) => quote! {
+                #base::#ident (#(#field_names_add,)*) => {
+                    #(#variant_remove_from_entity)*
+                }
+            },
+            Fields::Unit => quote! {
+                #base::#ident => ()
+            },
+        });
         subs.push(match variant.fields {
             Fields::Named(_) => quote! {
                 #base::#ident {#(#field_names_sub,)*} => {
@@ -235,12 +284,17 @@ fn prepare_prefab_aggregate_enum(
                 #(#subs,)*
             }
         },
+        quote! {
+            match self {
+                #(#remove_from_entity,)*
+            }
+        },
     )
 }
 
 fn impl_prefab_data_aggregate(ast: &DeriveInput) -> TokenStream {
     let base = &ast.ident;
-    let (data_types, add_to_entity, subs) = match &ast.data {
+    let (data_types, add_to_entity, subs, remove_from_entity) = match &ast.data {
         Data::Struct(ref s) => prepare_prefab_aggregate_struct(s),
         Data::Enum(ref e) => prepare_prefab_aggregate_enum(base, e),
         _ => panic!("PrefabData aggregate derive only support structs and enums"),
@@ -284,6 +338,141 @@ fn impl_prefab_data_aggregate(ast: &DeriveInput) -> TokenStream {
                 #subs
                 Ok(ret)
             }
+
+            fn remove_from_entity(&self,
+                                  entity: Entity,
+                                  system_data: &mut Self::SystemData) -> ::std::result::Result<(), Error> {
+                #remove_from_entity
+                Ok(())
+            }
+        }
+    }
+}
+
+fn impl_export_prefab_data_component(ast: &DeriveInput) -> TokenStream {
+    let base = &ast.ident;
+    let (_, ty_generics, where_clause) = ast.generics.split_for_impl();
+    let lf_tokens = gen_def_lt_tokens(&ast.generics);
+    let ty_tokens = gen_def_ty_params(&ast.generics);
+
+    quote! {
+        impl<'pfd, #lf_tokens #ty_tokens> ExportPrefabData<'pfd> for #base #ty_generics #where_clause {
+            type SystemData = ReadStorage<'pfd, #base #ty_generics>;
+
+            fn export_from_entity(entity: Entity,
+                                  system_data: &Self::SystemData,
+                                  _: &[Entity]) -> ::std::result::Result<Option<Self>, Error> {
+                Ok(system_data.get(entity).cloned())
+            }
+        }
+    }
+}
+
+/// Generates a statement returning `constructor` if all fields could be exported from the entity.
+fn prepare_export_fields(
+    data_types: &mut Vec<(Type, bool)>,
+    fields: &Fields,
+    constructor: TokenStream,
+) -> TokenStream {
+    let mut names = Vec::new();
+    let mut exports = Vec::new();
+    for (field_number, field) in fields.iter().enumerate() {
+        let is_component = is_component_prefab(&field.attrs[..]);
+        let i = match data_types
+            .iter()
+            .position(|t| t.0 == field.ty && t.1 == is_component)
+        {
+            Some(i) => i,
+            None => {
+                data_types.push((field.ty.clone(), is_component));
+                data_types.len() - 1
+            }
+        };
+        let tuple_index = Literal::usize_unsuffixed(i);
+        let ty = &field.ty;
+        names.push(
+            field.ident.clone().unwrap_or_else(|| {
+                Ident::new(&format!("field_{}", field_number), Span::call_site())
+            }),
+        );
+        exports.push(if is_component {
+            quote! {
+                system_data.#tuple_index.get(entity).cloned()
+            }
+        } else {
+            quote! {
+                <#ty as ExportPrefabData<'pfd>>::export_from_entity(entity, &system_data.#tuple_index, entities)?
+            }
+        });
+    }
+
+    let constructor = match fields {
+        Fields::Named(_) => quote! { #constructor { #(#names,)* } },
+        Fields::Unnamed(_) => quote! { #constructor ( #(#names,)* ) },
+        Fields::Unit => quote! { #constructor },
+    };
+    if names.is_empty() {
+        quote! {
+            return Ok(Some(#constructor));
+        }
+    } else {
+        quote! {
+            if let (#(Some(#names),)*) = (#(#exports,)*) {
+                return Ok(Some(#constructor));
+            }
+        }
+    }
+}
+
+fn impl_export_prefab_data_aggregate(ast: &DeriveInput) -> TokenStream {
+    let base = &ast.ident;
+    let mut data_types = Vec::new();
+    // Enum variants are tried in declaration order, the first one which can be exported wins.
+    let exports = match &ast.data {
+        Data::Struct(ref s) => vec![prepare_export_fields(
+            &mut data_types,
+            &s.fields,
+            quote! { #base },
+        )],
+        Data::Enum(ref e) => e
+            .variants
+            .iter()
+            .map(|variant| {
+                let ident = &variant.ident;
+                prepare_export_fields(&mut data_types, &variant.fields, quote! { #base::#ident })
+            })
+            .collect(),
+        _ => panic!("PrefabData aggregate derive only support structs and enums"),
+    };
+    let system_data = data_types.iter().map(|(ty, is_component)| {
+        if *is_component {
+            quote! {
+                 ReadStorage<'pfd, #ty>
+            }
+        } else {
+            quote! {
+                <#ty as ExportPrefabData<'pfd>>::SystemData
+            }
+        }
+    });
+
+    let (_, ty_generics, where_clause) = ast.generics.split_for_impl();
+    let lf_tokens = gen_def_lt_tokens(&ast.generics);
+    let ty_tokens = gen_def_ty_params(&ast.generics);
+
+    quote! {
+        impl<'pfd, #lf_tokens #ty_tokens> ExportPrefabData<'pfd> for #base #ty_generics #where_clause {
+            type SystemData = (
+                #(#system_data,)*
+            );
+
+            #[allow(unreachable_code, unused_variables)]
+            fn export_from_entity(entity: Entity,
+                                  system_data: &Self::SystemData,
+                                  entities: &[Entity]) -> ::std::result::Result<Option<Self>, Error> {
+                #(#exports)*
+                Ok(None)
+            }
         }
     }
 }
@@ -323,6 +512,14 @@ fn gen_def_ty_params(generics: &Generics) -> TokenStream {
 }
 
 fn is_component_prefab(attrs: &[Attribute]) -> bool {
+    has_prefab_flag(attrs, "Component")
+}
+
+fn is_export_prefab(attrs: &[Attribute]) -> bool {
+    has_prefab_flag(attrs, "Export")
+}
+
+fn has_prefab_flag(attrs: &[Attribute], flag: &str) -> bool {
     for meta in attrs
         .iter()
         .filter(|attr| attr.path.segments[0].ident == "prefab")
@@ -335,7 +532,7 @@ fn is_component_prefab(attrs: &[Attribute]) -> bool {
             for nested_meta in l.nested.iter() {
                 match nested_meta {
                     NestedMeta::Meta(Meta::Path(path)) => {
-                        if let Some(true) = path.get_ident().map(|word| word == "Component") {
+                        if let Some(true) = path.get_ident().map(|word| word == flag) {
                             return true;
                         }
                     }
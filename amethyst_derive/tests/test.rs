@@ -5,9 +5,9 @@
 )]
 use amethyst_derive::{EventReader, PrefabData};
 
-use amethyst_assets::{PrefabData, ProgressCounter};
+use amethyst_assets::{ExportPrefabData, PrefabData, ProgressCounter};
 use amethyst_core::{
-    ecs::{Component, DenseVecStorage, Entity, Read, SystemData, World, WriteStorage},
+    ecs::{Component, DenseVecStorage, Entity, Read, ReadStorage, SystemData, World, WriteStorage},
     shrev::{EventChannel, ReaderId},
     EventReader,
 };
@@ -43,7 +43,7 @@ where
 }
 
 #[derive(Clone, PrefabData, Default)]
-#[prefab(Component)]
+#[prefab(Component, Export)]
 pub struct Stuff<T>
 where
     T: Default + Clone + Send + Sync + 'static,
@@ -59,6 +59,7 @@ where
 }
 
 #[derive(Clone, PrefabData)]
+#[prefab(Export)]
 pub struct OuterPrefab<T>
 where
     T: Default + Clone + Send + Sync + 'static,
@@ -76,15 +77,18 @@ impl Component for External {
 }
 
 #[derive(PrefabData, Clone)]
+#[prefab(Export)]
 pub struct Outer {
     #[prefab(Component)]
     external: External,
 }
 
 #[derive(PrefabData, Clone)]
+#[prefab(Export)]
 pub struct OuterTuple(#[prefab(Component)] External);
 
 #[derive(PrefabData, Clone)]
+#[prefab(Export)]
 pub enum EnumPrefab {
     One {
         number: Stuff<usize>,
@@ -101,7 +105,7 @@ pub enum EnumPrefab {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use amethyst_assets::{AssetStorage, Loader, Prefab, PrefabLoaderSystemDesc};
+    use amethyst_assets::{AssetStorage, Loader, Prefab, PrefabExporter, PrefabLoaderSystemDesc};
     use amethyst_core::ecs::{world::EntitiesRes, Builder, Join, WorldExt};
     use amethyst_test::prelude::*;
 
@@ -219,4 +223,46 @@ mod tests {
             }
         );
     }
+
+    #[test]
+    fn export_first_matching_variant() {
+        let mut world = World::new();
+        world.register::<Stuff<usize>>();
+        world.register::<Stuff<String>>();
+        world.register::<External>();
+        let stuff = world.create_entity().with(Stuff { inner: 1_usize }).build();
+        let external = world.create_entity().with(External { inner: 2 }).build();
+        let empty = world.create_entity().build();
+
+        world.exec(|exporter: PrefabExporter<'_, EnumPrefab>| {
+            let data = |entity| {
+                exporter
+                    .export(entity)
+                    .unwrap()
+                    .entities()
+                    .next()
+                    .unwrap()
+                    .data()
+                    .cloned()
+            };
+            match data(stuff) {
+                Some(EnumPrefab::One { number }) => assert_eq!(number.inner, 1),
+                _ => panic!("Expected `EnumPrefab::One`"),
+            }
+            match data(external) {
+                Some(EnumPrefab::Two { component }) => assert_eq!(component.inner, 2),
+                _ => panic!("Expected `EnumPrefab::Two`"),
+            }
+            match data(empty) {
+                Some(EnumPrefab::Three {}) => {}
+                _ => panic!("Expected `EnumPrefab::Three`"),
+            }
+        });
+        world.exec(|exporter: PrefabExporter<'_, OuterTuple>| {
+            let prefab = exporter.export(external).unwrap();
+            assert_eq!(2, prefab.entities().next().unwrap().data().unwrap().0.inner);
+            let prefab = exporter.export(empty).unwrap();
+            assert!(prefab.entities().next().unwrap().data().is_none());
+        });
+    }
 }
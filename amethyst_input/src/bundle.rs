@@ -1,6 +1,6 @@
 //! ECS input bundle
 
-use crate::{BindingError, BindingTypes, Bindings, InputSystemDesc};
+use crate::{BindingError, BindingTypes, Bindings, InputPlaybackSystem, InputSystemDesc};
 use amethyst_config::{Config, ConfigError};
 use amethyst_core::{
     ecs::prelude::{DispatcherBuilder, World},
@@ -93,10 +93,11 @@ impl<'a, 'b, T: BindingTypes> SystemBundle<'a, 'b> for InputBundle<T> {
                 SdlEventsSystem::<T>::new(world, self.controller_mappings).unwrap(),
             );
         }
+        builder.add(InputPlaybackSystem, "input_playback_system", &[]);
         builder.add(
             InputSystemDesc::<T>::new(self.bindings).build(world),
             "input_system",
-            &[],
+            &["input_playback_system"],
         );
         Ok(())
     }
@@ -20,6 +20,7 @@ pub use self::{
     event::InputEvent,
     input_handler::InputHandler,
     mouse::MouseAxis,
+    recording::{InputPlayback, InputPlaybackSystem, InputRecorder, InputRecording, RecordedEvent},
     scroll_direction::ScrollDirection,
     system::{InputSystem, InputSystemDesc},
     util::{
@@ -39,6 +40,7 @@ mod controller;
 mod event;
 mod input_handler;
 mod mouse;
+mod recording;
 mod scroll_direction;
 mod system;
 mod util;
@@ -0,0 +1,471 @@
+//! Recording input sessions and playing them back.
+
+use std::time::Duration;
+
+use amethyst_core::{
+    ecs::prelude::{System, Write},
+    timing::Time,
+};
+use serde::{Deserialize, Serialize};
+use winit::{
+    dpi::LogicalPosition, DeviceEvent, DeviceId, ElementState, Event, KeyboardInput,
+    ModifiersState, MouseButton, MouseScrollDelta, WindowEvent, WindowId,
+};
+
+use crate::controller::ControllerEvent;
+
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+/// An input event as stored in an `InputRecording`.
+///
+/// Only contains the events the `InputHandler` reacts to.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum RecordedEvent {
+    /// `WindowEvent::ReceivedCharacter`
+    ReceivedCharacter(char),
+    /// `WindowEvent::KeyboardInput`
+    KeyboardInput(KeyboardInput),
+    /// `WindowEvent::MouseInput`
+    MouseInput {
+        /// Whether the button was pressed or released.
+        state: ElementState,
+        /// The mouse button.
+        button: MouseButton,
+        /// The modifier keys held down.
+        modifiers: ModifiersState,
+    },
+    /// `WindowEvent::CursorMoved`
+    CursorMoved {
+        /// The new position of the cursor.
+        position: LogicalPosition,
+        /// The modifier keys held down.
+        modifiers: ModifiersState,
+    },
+    /// `WindowEvent::Focused`
+    Focused(bool),
+    /// `DeviceEvent::MouseMotion`
+    MouseMotion {
+        /// The change in position of the mouse.
+        delta: (f64, f64),
+    },
+    /// `DeviceEvent::MouseWheel`
+    MouseWheel {
+        /// The amount scrolled.
+        delta: MouseScrollDelta,
+    },
+    /// A `ControllerEvent`.
+    Controller(ControllerEvent),
+}
+
+impl RecordedEvent {
+    /// Converts a `winit` event, returns `None` for events which don't affect the input state.
+    pub fn from_event(event: &Event) -> Option<Self> {
+        match *event {
+            Event::WindowEvent { ref event, .. } => match *event {
+                WindowEvent::ReceivedCharacter(c) => Some(RecordedEvent::ReceivedCharacter(c)),
+                WindowEvent::KeyboardInput { input, .. } => {
+                    Some(RecordedEvent::KeyboardInput(input))
+                }
+                WindowEvent::MouseInput {
+                    state,
+                    button,
+                    modifiers,
+                    ..
+                } => Some(RecordedEvent::MouseInput {
+                    state,
+                    button,
+                    modifiers,
+                }),
+                WindowEvent::CursorMoved {
+                    position,
+                    modifiers,
+                    ..
+                } => Some(RecordedEvent::CursorMoved {
+                    position,
+                    modifiers,
+                }),
+                WindowEvent::Focused(focused) => Some(RecordedEvent::Focused(focused)),
+                _ => None,
+            },
+            Event::DeviceEvent { ref event, .. } => match *event {
+                DeviceEvent::MouseMotion { delta } => Some(RecordedEvent::MouseMotion { delta }),
+                DeviceEvent::MouseWheel { delta } => Some(RecordedEvent::MouseWheel { delta }),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Converts the event back into a `winit` event, returns `None` for controller events.
+    ///
+    /// The window and device ids of the returned event are dummy values, so it must not be
+    /// passed to `winit`.
+    pub fn to_event(&self) -> Option<Event> {
+        // The `InputHandler` ignores the ids, and the events never leave the input crate.
+        let window_id = unsafe { WindowId::dummy() };
+        let device_id = unsafe { DeviceId::dummy() };
+        let event = match *self {
+            RecordedEvent::ReceivedCharacter(c) => WindowEvent::ReceivedCharacter(c),
+            RecordedEvent::KeyboardInput(input) => WindowEvent::KeyboardInput { device_id, input },
+            RecordedEvent::MouseInput {
+                state,
+                button,
+                modifiers,
+            } => WindowEvent::MouseInput {
+                device_id,
+                state,
+                button,
+                modifiers,
+            },
+            RecordedEvent::CursorMoved {
+                position,
+                modifiers,
+            } => WindowEvent::CursorMoved {
+                device_id,
+                position,
+                modifiers,
+            },
+            RecordedEvent::Focused(focused) => WindowEvent::Focused(focused),
+            RecordedEvent::MouseMotion { delta } => {
+                return Some(Event::DeviceEvent {
+                    device_id,
+                    event: DeviceEvent::MouseMotion { delta },
+                });
+            }
+            RecordedEvent::MouseWheel { delta } => {
+                return Some(Event::DeviceEvent {
+                    device_id,
+                    event: DeviceEvent::MouseWheel { delta },
+                });
+            }
+            RecordedEvent::Controller(_) => return None,
+        };
+        Some(Event::WindowEvent { window_id, event })
+    }
+}
+
+/// A recorded input session.
+///
+/// Recordings can be written to and loaded from RON files with `amethyst_config::Config`.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct InputRecording {
+    /// Number of recorded frames.
+    pub frames: u64,
+    /// Recorded events, with the frame they were received in counted from the start of the
+    /// recording, in the order they were received.
+    pub events: Vec<(u64, RecordedEvent)>,
+}
+
+/// Records the input processed by the `InputSystem`.
+///
+/// Add this resource to the `World` and call `start` to record the events the `InputSystem`
+/// processes, tagged with `Time::frame_number`. Controller events are recorded by the
+/// `SdlEventsSystem`; custom controller implementations can record their events with `record`.
+#[derive(Debug, Default)]
+pub struct InputRecorder {
+    recording: Option<(Option<u64>, InputRecording)>,
+}
+
+impl InputRecorder {
+    /// Creates a recorder which isn't recording yet.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Starts a new recording with the next frame, dropping the current one.
+    pub fn start(&mut self) {
+        self.recording = Some((None, InputRecording::default()));
+    }
+
+    /// Stops recording and returns the recorded session.
+    pub fn stop(&mut self) -> Option<InputRecording> {
+        self.recording.take().map(|(_, recording)| recording)
+    }
+
+    /// Returns `true` if the recorder is recording.
+    pub fn is_recording(&self) -> bool {
+        self.recording.is_some()
+    }
+
+    /// Returns the session recorded so far.
+    pub fn recording(&self) -> Option<&InputRecording> {
+        self.recording.as_ref().map(|(_, recording)| recording)
+    }
+
+    /// Records an event received in the given frame. Does nothing if the recorder isn't
+    /// recording.
+    pub fn record(&mut self, frame_number: u64, event: RecordedEvent) {
+        if let Some((ref mut start, ref mut recording)) = self.recording {
+            let start = *start.get_or_insert(frame_number);
+            let frame = frame_number.saturating_sub(start);
+            recording.frames = recording.frames.max(frame + 1);
+            recording.events.push((frame, event));
+        }
+    }
+
+    /// Marks the given frame as recorded, even if it had no events.
+    ///
+    /// Called by the `InputSystem` every frame.
+    pub fn record_frame(&mut self, frame_number: u64) {
+        if let Some((ref mut start, ref mut recording)) = self.recording {
+            let start = *start.get_or_insert(frame_number);
+            recording.frames = recording.frames.max(frame_number.saturating_sub(start) + 1);
+        }
+    }
+}
+
+/// Plays back an `InputRecording`.
+///
+/// While playing, the `InputSystem` ignores the events from the window and processes the
+/// recorded events instead, one recorded frame per frame. The `InputPlaybackSystem` advances
+/// the playback and makes every frame last the fixed `delta` with `Time::set_virtual_delta`, so
+/// the recorded session is reproduced exactly.
+///
+/// Only the `InputHandler` and the readers of `InputEvent`s see the played back input; other
+/// readers of the `winit` events, e.g. the UI, don't.
+#[derive(Debug)]
+pub struct InputPlayback {
+    recording: InputRecording,
+    delta: Duration,
+    /// Frame of the recording which is played back, `None` before the playback started.
+    frame: Option<u64>,
+    /// Index of the first event of the current frame.
+    cursor: usize,
+    /// Index after the last event of the current frame.
+    end: usize,
+}
+
+impl InputPlayback {
+    /// Creates a playback of the recording with a frame time of 1/60 second.
+    pub fn new(recording: InputRecording) -> Self {
+        InputPlayback {
+            recording,
+            delta: Duration::new(0, 16_666_666),
+            frame: None,
+            cursor: 0,
+            end: 0,
+        }
+    }
+
+    /// Sets the time every played back frame lasts.
+    pub fn with_delta(mut self, delta: Duration) -> Self {
+        self.delta = delta;
+        self
+    }
+
+    /// Returns the time every played back frame lasts.
+    pub fn delta(&self) -> Duration {
+        self.delta
+    }
+
+    /// Returns the played back frame of the recording, if the playback is running.
+    pub fn frame(&self) -> Option<u64> {
+        self.frame.filter(|&frame| frame < self.recording.frames)
+    }
+
+    /// Returns `true` while the recording is played back.
+    pub fn is_playing(&self) -> bool {
+        self.frame().is_some()
+    }
+
+    /// Returns `true` once all frames of the recording were played back.
+    pub fn is_finished(&self) -> bool {
+        match self.frame {
+            Some(frame) => frame >= self.recording.frames,
+            None => false,
+        }
+    }
+
+    /// Returns the events of the played back frame.
+    pub fn events(&self) -> impl Iterator<Item = &RecordedEvent> {
+        let events = if self.is_playing() {
+            &self.recording.events[self.cursor..self.end]
+        } else {
+            &[]
+        };
+        events.iter().map(|(_, event)| event)
+    }
+
+    /// Advances the playback to the next frame.
+    ///
+    /// Called by the `InputPlaybackSystem` every frame.
+    pub fn advance(&mut self) {
+        let frame = self.frame.map_or(0, |frame| frame + 1);
+        self.frame = Some(frame);
+        self.cursor = self.end;
+        while self.cursor < self.recording.events.len()
+            && self.recording.events[self.cursor].0 < frame
+        {
+            self.cursor += 1;
+        }
+        self.end = self.cursor;
+        while self.end < self.recording.events.len() && self.recording.events[self.end].0 == frame {
+            self.end += 1;
+        }
+    }
+}
+
+/// Advances the `InputPlayback` resource every frame, if there is one.
+///
+/// Has to run before the `InputSystem`; the `InputBundle` adds it as "input_playback_system".
+#[derive(Debug, Default)]
+pub struct InputPlaybackSystem;
+
+impl<'a> System<'a> for InputPlaybackSystem {
+    type SystemData = (Option<Write<'a, InputPlayback>>, Write<'a, Time>);
+
+    fn run(&mut self, (playback, mut time): Self::SystemData) {
+        #[cfg(feature = "profiler")]
+        profile_scope!("input_playback_system");
+
+        let mut playback = match playback {
+            Some(playback) => playback,
+            None => return,
+        };
+        if playback.is_finished() {
+            return;
+        }
+        playback.advance();
+        if playback.is_playing() {
+            if time.virtual_delta() != Some(playback.delta) {
+                time.set_virtual_delta(Some(playback.delta));
+            }
+        } else {
+            time.set_virtual_delta(None);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use amethyst_core::{
+        ecs::prelude::{RunNow, World, WorldExt},
+        shrev::{EventChannel, ReaderId},
+        timing::Time,
+        SystemDesc,
+    };
+    use amethyst_window::ScreenDimensions;
+    use winit::{
+        DeviceId, ElementState, Event, KeyboardInput, ModifiersState, VirtualKeyCode, WindowEvent,
+        WindowId,
+    };
+
+    use super::*;
+    use crate::{InputEvent, InputHandler, InputSystem, InputSystemDesc, StringBindings};
+
+    fn key_event(virtual_keycode: VirtualKeyCode, state: ElementState) -> Event {
+        Event::WindowEvent {
+            window_id: unsafe { WindowId::dummy() },
+            event: WindowEvent::KeyboardInput {
+                device_id: unsafe { DeviceId::dummy() },
+                input: KeyboardInput {
+                    scancode: 30,
+                    state,
+                    virtual_keycode: Some(virtual_keycode),
+                    modifiers: ModifiersState::default(),
+                },
+            },
+        }
+    }
+
+    struct Frames {
+        world: World,
+        playback_system: InputPlaybackSystem,
+        input_system: InputSystem<StringBindings>,
+        reader: ReaderId<InputEvent<StringBindings>>,
+    }
+
+    impl Frames {
+        fn new() -> Self {
+            let mut world = World::new();
+            world.insert(ScreenDimensions::new(640, 480, 1.0));
+            world.insert(EventChannel::<Event>::new());
+            let input_system = InputSystemDesc::<StringBindings>::new(None).build(&mut world);
+            let reader = world
+                .write_resource::<EventChannel<InputEvent<StringBindings>>>()
+                .register_reader();
+            Frames {
+                world,
+                playback_system: InputPlaybackSystem,
+                input_system,
+                reader,
+            }
+        }
+
+        /// Runs a frame with the given live events, returns whether `A` is down and the
+        /// produced input events.
+        fn run(&mut self, events: Vec<Event>) -> (bool, Vec<InputEvent<StringBindings>>) {
+            {
+                let mut time = self.world.write_resource::<Time>();
+                time.increment_frame_number();
+                time.set_delta_seconds(0.1);
+            }
+            self.world
+                .write_resource::<EventChannel<Event>>()
+                .iter_write(events);
+            self.playback_system.run_now(&self.world);
+            self.input_system.run_now(&self.world);
+            (
+                self.world
+                    .read_resource::<InputHandler<StringBindings>>()
+                    .key_is_down(VirtualKeyCode::A),
+                self.world
+                    .read_resource::<EventChannel<InputEvent<StringBindings>>>()
+                    .read(&mut self.reader)
+                    .cloned()
+                    .collect(),
+            )
+        }
+    }
+
+    #[test]
+    fn recorded_input_is_played_back() {
+        let mut frames = Frames::new();
+        frames.world.insert(InputRecorder::new());
+        frames.world.write_resource::<InputRecorder>().start();
+        let live = vec![
+            vec![],
+            vec![key_event(VirtualKeyCode::A, ElementState::Pressed)],
+            vec![],
+            vec![key_event(VirtualKeyCode::A, ElementState::Released)],
+            vec![],
+        ];
+        let recorded = live
+            .into_iter()
+            .map(|events| frames.run(events))
+            .collect::<Vec<_>>();
+        let recording = frames
+            .world
+            .write_resource::<InputRecorder>()
+            .stop()
+            .unwrap();
+        assert_eq!(5, recording.frames);
+        assert_eq!(
+            vec![1, 3],
+            recording.events.iter().map(|e| e.0).collect::<Vec<_>>()
+        );
+
+        let mut frames = Frames::new();
+        let delta = Duration::from_millis(20);
+        frames
+            .world
+            .insert(InputPlayback::new(recording).with_delta(delta));
+        let played = (0..5)
+            .map(|_| {
+                // Live input is ignored during the playback.
+                let frame = frames.run(vec![key_event(VirtualKeyCode::B, ElementState::Pressed)]);
+                assert_eq!(delta, frames.world.read_resource::<Time>().delta_time());
+                frame
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(recorded, played);
+        assert!(frames.world.read_resource::<InputPlayback>().is_playing());
+
+        frames.run(vec![]);
+        assert!(frames.world.read_resource::<InputPlayback>().is_finished());
+        assert_eq!(None, frames.world.read_resource::<Time>().virtual_delta());
+    }
+}
@@ -10,13 +10,15 @@ use sdl2::{
 };
 
 use amethyst_core::{
-    ecs::prelude::{System, SystemData, World, Write},
+    ecs::prelude::{Read, System, SystemData, World, Write},
     shrev::EventChannel,
+    timing::Time,
     SystemDesc,
 };
 
 use super::{
     controller::{ControllerAxis, ControllerButton, ControllerEvent},
+    recording::{InputPlayback, InputRecorder, RecordedEvent},
     BindingTypes, InputEvent, InputHandler,
 };
 
@@ -92,19 +94,39 @@ pub struct SdlEventsSystem<T: BindingTypes> {
 type SdlEventsData<'a, T> = (
     Write<'a, InputHandler<T>>,
     Write<'a, EventChannel<InputEvent<T>>>,
+    Read<'a, Time>,
+    Option<Write<'a, InputRecorder>>,
+    Option<Read<'a, InputPlayback>>,
 );
 
 impl<'a, T: BindingTypes> System<'a> for SdlEventsSystem<T> {
     type SystemData = SdlEventsData<'a, T>;
 
-    fn run(&mut self, (mut handler, mut output): Self::SystemData) {
+    fn run(&mut self, (mut handler, mut output, time, mut recorder, playback): Self::SystemData) {
+        let playing = match playback {
+            Some(playback) => playback.is_playing(),
+            None => false,
+        };
         let mut event_pump = self
             .event_pump
             .take()
             .expect("Unreachable: `event_pump` is always reinserted after `take`");
         for event in event_pump.poll_iter() {
             // handle appropriate events locally
-            self.handle_sdl_event(&event, &mut handler, &mut output);
+            let event = match self.handle_sdl_event(&event) {
+                Some(event) => event,
+                None => continue,
+            };
+            // The `InputSystem` plays back the recorded controller events.
+            if playing {
+                continue;
+            }
+            handler.send_controller_event(&event, &mut output);
+            if let Some(recorder) = recorder.as_mut() {
+                // This system runs after the `InputSystem`, so the event is first seen by the
+                // other systems in the next frame.
+                recorder.record(time.frame_number() + 1, RecordedEvent::Controller(event));
+            }
         }
         self.event_pump = Some(event_pump);
     }
@@ -147,69 +169,44 @@ impl<T: BindingTypes> SdlEventsSystem<T> {
             opened_controllers: vec![],
             marker: PhantomData,
         };
-        let (mut handler, mut output) = SdlEventsData::fetch(world);
+        let (mut handler, mut output, ..) = SdlEventsData::<T>::fetch(world);
         sys.initialize_controllers(&mut handler, &mut output);
         Ok(sys)
     }
 
-    fn handle_sdl_event(
-        &mut self,
-        event: &Event,
-        handler: &mut InputHandler<T>,
-        output: &mut EventChannel<InputEvent<T>>,
-    ) {
+    fn handle_sdl_event(&mut self, event: &Event) -> Option<ControllerEvent> {
         use self::ControllerEvent::*;
 
         match *event {
             Event::ControllerAxisMotion {
                 which, axis, value, ..
-            } => {
-                handler.send_controller_event(
-                    &ControllerAxisMoved {
-                        which: which as u32,
-                        axis: axis.into(),
-                        value: if value > 0 {
-                            f32::from(value) / 32767.0
-                        } else {
-                            f32::from(value) / 32768.0
-                        },
-                    },
-                    output,
-                );
-            }
-            Event::ControllerButtonDown { which, button, .. } => {
-                handler.send_controller_event(
-                    &ControllerButtonPressed {
-                        which: which as u32,
-                        button: button.into(),
-                    },
-                    output,
-                );
-            }
-            Event::ControllerButtonUp { which, button, .. } => {
-                handler.send_controller_event(
-                    &ControllerButtonReleased {
-                        which: which as u32,
-                        button: button.into(),
-                    },
-                    output,
-                );
-            }
+            } => Some(ControllerAxisMoved {
+                which: which as u32,
+                axis: axis.into(),
+                value: if value > 0 {
+                    f32::from(value) / 32767.0
+                } else {
+                    f32::from(value) / 32768.0
+                },
+            }),
+            Event::ControllerButtonDown { which, button, .. } => Some(ControllerButtonPressed {
+                which: which as u32,
+                button: button.into(),
+            }),
+            Event::ControllerButtonUp { which, button, .. } => Some(ControllerButtonReleased {
+                which: which as u32,
+                button: button.into(),
+            }),
             Event::ControllerDeviceRemoved { which, .. } => {
                 self.close_controller(which as u32);
-                handler.send_controller_event(
-                    &ControllerDisconnected {
-                        which: which as u32,
-                    },
-                    output,
-                );
-            }
-            Event::ControllerDeviceAdded { which, .. } => {
-                if let Some(idx) = self.open_controller(which) {
-                    handler.send_controller_event(&ControllerConnected { which: idx }, output);
-                }
+                Some(ControllerDisconnected {
+                    which: which as u32,
+                })
             }
-            _ => {}
+            Event::ControllerDeviceAdded { which, .. } => self
+                .open_controller(which)
+                .map(|idx| ControllerConnected { which: idx }),
+            _ => None,
         }
     }
 
@@ -2,13 +2,17 @@
 use derive_new::new;
 use winit::Event;
 
-use crate::{BindingTypes, Bindings, InputEvent, InputHandler};
+use crate::{
+    recording::{InputPlayback, InputRecorder, RecordedEvent},
+    BindingTypes, Bindings, InputEvent, InputHandler,
+};
 use amethyst_core::{
     ecs::{
         prelude::{Read, ReadExpect, System, World, Write},
         SystemData,
     },
     shrev::{EventChannel, ReaderId},
+    timing::Time,
     SystemDesc,
 };
 use amethyst_window::ScreenDimensions;
@@ -45,6 +49,9 @@ where
 ///
 /// Will read `winit::Event` from `EventHandler<winit::Event>`, process them with `InputHandler`,
 /// and push the results in `EventHandler<InputEvent>`.
+///
+/// The processed events are recorded by the `InputRecorder` resource, if there is one. While an
+/// `InputPlayback` is playing, the recorded events are processed instead of the window events.
 #[derive(Debug)]
 pub struct InputSystem<T>
 where
@@ -76,20 +83,47 @@ impl<'a, T: BindingTypes> System<'a> for InputSystem<T> {
         Write<'a, InputHandler<T>>,
         Write<'a, EventChannel<InputEvent<T>>>,
         ReadExpect<'a, ScreenDimensions>,
+        Read<'a, Time>,
+        Option<Write<'a, InputRecorder>>,
+        Option<Read<'a, InputPlayback>>,
     );
 
-    fn run(&mut self, (input, mut handler, mut output, screen_dimensions): Self::SystemData) {
+    fn run(
+        &mut self,
+        (input, mut handler, mut output, screen_dimensions, time, mut recorder, playback): Self::SystemData,
+    ) {
         #[cfg(feature = "profiler")]
         profile_scope!("input_system");
 
         handler.send_frame_begin();
+        let hidpi = screen_dimensions.hidpi_factor() as f32;
+        if let Some(playback) = playback.as_ref().filter(|playback| playback.is_playing()) {
+            // Drop the live events, the recorded session is played back instead.
+            input.read(&mut self.reader).for_each(drop);
+            for recorded in playback.events() {
+                match (recorded.to_event(), recorded) {
+                    (Some(event), _) => {
+                        Self::process_event(&event, &mut *handler, &mut *output, hidpi)
+                    }
+                    (None, RecordedEvent::Controller(event)) => {
+                        handler.send_controller_event(event, &mut *output)
+                    }
+                    (None, _) => {}
+                }
+            }
+            return;
+        }
+
+        if let Some(recorder) = recorder.as_mut() {
+            recorder.record_frame(time.frame_number());
+        }
         for event in input.read(&mut self.reader) {
-            Self::process_event(
-                event,
-                &mut *handler,
-                &mut *output,
-                screen_dimensions.hidpi_factor() as f32,
-            );
+            Self::process_event(event, &mut *handler, &mut *output, hidpi);
+            if let Some(recorder) = recorder.as_mut() {
+                if let Some(recorded) = RecordedEvent::from_event(event) {
+                    recorder.record(time.frame_number(), recorded);
+                }
+            }
         }
     }
 }
@@ -1,8 +1,8 @@
 //! Camera type with support for perspective and orthographic projections.
 
-use amethyst_assets::PrefabData;
+use amethyst_assets::{ExportPrefabData, PrefabData};
 use amethyst_core::{
-    ecs::prelude::{Component, Entity, HashMapStorage, Write, WriteStorage},
+    ecs::prelude::{Component, Entity, HashMapStorage, ReadStorage, Write, WriteStorage},
     geometry::Ray,
     math::{Matrix4, Point2, Point3, Vector2},
     transform::components::Transform,
@@ -770,6 +770,50 @@ impl<'a> PrefabData<'a> for CameraPrefab {
         )?;
         Ok(())
     }
+
+    fn remove_from_entity(
+        &self,
+        entity: Entity,
+        storage: &mut Self::SystemData,
+    ) -> Result<(), Error> {
+        storage.remove(entity);
+        Ok(())
+    }
+}
+
+impl<'a> ExportPrefabData<'a> for CameraPrefab {
+    type SystemData = ReadStorage<'a, Camera>;
+
+    fn export_from_entity(
+        entity: Entity,
+        storage: &Self::SystemData,
+        _: &[Entity],
+    ) -> Result<Option<Self>, Error> {
+        let camera = match storage.get(entity) {
+            Some(camera) => camera,
+            None => return Ok(None),
+        };
+        match camera.inner {
+            Projection::Orthographic(ref ortho) => Ok(Some(CameraPrefab::Orthographic {
+                left: ortho.left(),
+                right: ortho.right(),
+                bottom: ortho.bottom(),
+                top: ortho.top(),
+                znear: ortho.near(),
+                zfar: ortho.far(),
+            })),
+            Projection::Perspective(ref perspective) => Ok(Some(CameraPrefab::Perspective {
+                aspect: perspective.aspect(),
+                fovy: perspective.fovy(),
+                znear: perspective.near(),
+                zfar: perspective.far(),
+            })),
+            Projection::CustomMatrix(_) => Err(Error::from_string(format!(
+                "Camera of entity {:?} has a custom projection matrix, which can't be exported",
+                entity
+            ))),
+        }
+    }
 }
 
 /// Active camera prefab
@@ -1230,4 +1274,41 @@ mod tests {
         let projected_point = mvp * far.to_homogeneous();
         assert_abs_diff_eq!(projected_point[2] / projected_point[3], 1.0);
     }
+
+    #[test]
+    fn camera_prefab_export() {
+        use amethyst_core::ecs::{Builder, World, WorldExt};
+
+        let mut world = World::new();
+        world.register::<Camera>();
+        let cameras = vec![
+            Camera::standard_2d(1280.0, 720.0),
+            Camera::standard_3d(1280.0, 720.0),
+        ];
+        for camera in cameras {
+            let entity = world.create_entity().with(camera.clone()).build();
+            let exported = world.create_entity().build();
+
+            let prefab = CameraPrefab::export_from_entity(entity, &world.system_data(), &[])
+                .unwrap()
+                .unwrap();
+            let prefab: CameraPrefab =
+                from_str(&to_string_pretty(&prefab, Default::default()).unwrap()).unwrap();
+            prefab
+                .add_to_entity(exported, &mut world.system_data(), &[], &[])
+                .unwrap();
+            let storage = world.read_storage::<Camera>();
+            assert_relative_eq!(
+                camera.projection().as_matrix(),
+                storage.get(exported).unwrap().projection().as_matrix(),
+                epsilon = 1e-4
+            );
+        }
+
+        let custom = world
+            .create_entity()
+            .with(Camera::from(Projection::custom_matrix(Matrix4::identity())))
+            .build();
+        assert!(CameraPrefab::export_from_entity(custom, &world.system_data(), &[]).is_err());
+    }
 }
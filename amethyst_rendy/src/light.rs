@@ -3,16 +3,16 @@
 //! TODO: Remove redundant padding once `#[repr(align(...))]` stabilizes.
 
 use crate::resources::AmbientColor;
-use amethyst_assets::{PrefabData, ProgressCounter};
+use amethyst_assets::{ExportPrefabData, PrefabData, ProgressCounter};
 use amethyst_core::{
-    ecs::prelude::{Component, DenseVecStorage, Entity, WriteStorage},
+    ecs::prelude::{Component, DenseVecStorage, Entity, ReadStorage, WriteStorage},
     math::Vector3,
 };
 use amethyst_error::Error;
 
 /// A light source.
 #[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize, PrefabData)]
-#[prefab(Component)]
+#[prefab(Component, Export)]
 pub enum Light {
     /// An area light.
     /// FIXME: Missing implementation!
@@ -190,6 +190,7 @@ impl Component for Light {
 /// Prefab for lighting
 #[derive(Debug, Default, Clone, serde::Serialize, serde::Deserialize, PrefabData)]
 #[serde(default)]
+#[prefab(Export)]
 pub struct LightPrefab {
     light: Option<Light>,
     ambient_color: Option<AmbientColor>,
@@ -1,7 +1,7 @@
 //! `amethyst` rendering ecs resources
 //!
 
-use amethyst_assets::PrefabData;
+use amethyst_assets::{ExportPrefabData, PrefabData};
 use amethyst_core::ecs::{Component, DenseVecStorage, Entity, Write};
 use amethyst_error::Error;
 
@@ -31,6 +31,15 @@ impl<'a> PrefabData<'a> for AmbientColor {
     }
 }
 
+/// The ambient color is a resource rather than a component, so it is never exported.
+impl<'a> ExportPrefabData<'a> for AmbientColor {
+    type SystemData = ();
+
+    fn export_from_entity(_: Entity, _: &(), _: &[Entity]) -> Result<Option<Self>, Error> {
+        Ok(None)
+    }
+}
+
 /// A single object tinting applied in multiplicative mode (modulation)
 #[derive(Clone, Copy, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct Tint(#[serde(with = "crate::serde_shim::srgba")] pub palette::Srgba);
@@ -3,7 +3,7 @@ use ron::de::from_bytes as from_ron_bytes;
 use serde::{Deserialize, Serialize};
 
 use crate::{error, types::Texture};
-use amethyst_assets::{Asset, Format, Handle};
+use amethyst_assets::{Asset, AssetId, Format, Handle};
 use amethyst_core::ecs::prelude::{Component, DenseVecStorage};
 use amethyst_error::Error;
 
@@ -487,6 +487,10 @@ impl Format<SpriteSheet> for SpriteSheetFormat {
             sprites: sprites.build_sprites(),
         })
     }
+
+    fn dependencies(&self) -> Vec<AssetId> {
+        vec![AssetId::from(&self.0)]
+    }
 }
 
 #[cfg(test)]
@@ -3,9 +3,11 @@ use crate::{
     formats::texture::TexturePrefab,
     sprite::{SpriteRender, SpriteSheet, Sprites},
 };
-use amethyst_assets::{AssetStorage, Handle, Loader, PrefabData, ProgressCounter};
+use amethyst_assets::{
+    AssetStorage, ExportPrefabData, Handle, Loader, PrefabData, ProgressCounter,
+};
 use amethyst_core::{
-    ecs::{Entity, Read, ReadExpect, WriteStorage},
+    ecs::{Entity, Read, ReadExpect, ReadStorage, WriteStorage},
     Transform,
 };
 use amethyst_error::Error;
@@ -114,6 +116,16 @@ impl SpriteSheetLoadedSet {
                 .map(|(_, handle)| handle.clone()),
         }
     }
+
+    /// Get the [SpriteSheetReference] of the given [SpriteSheet], preferring its name.
+    pub fn reference(&self, handle: &Handle<SpriteSheet>) -> Option<SpriteSheetReference> {
+        let inner = self.0.lock().unwrap();
+        let index = inner.iter().position(|(_, h)| h == handle)?;
+        Some(match inner[index].0 {
+            Some(ref name) => SpriteSheetReference::Name(name.clone()),
+            None => SpriteSheetReference::Index(index),
+        })
+    }
 }
 impl Default for SpriteSheetLoadedSet {
     fn default() -> Self {
@@ -186,6 +198,15 @@ impl<'a> PrefabData<'a> for SpriteRenderPrefab {
         }
     }
 
+    fn remove_from_entity(
+        &self,
+        entity: Entity,
+        system_data: &mut Self::SystemData,
+    ) -> Result<(), Error> {
+        system_data.0.remove(entity);
+        Ok(())
+    }
+
     fn load_sub_assets(
         &mut self,
         _: &mut ProgressCounter,
@@ -201,6 +222,39 @@ impl<'a> PrefabData<'a> for SpriteRenderPrefab {
     }
 }
 
+impl<'a> ExportPrefabData<'a> for SpriteRenderPrefab {
+    type SystemData = (
+        ReadStorage<'a, SpriteRender>,
+        Read<'a, SpriteSheetLoadedSet>,
+    );
+
+    fn export_from_entity(
+        entity: Entity,
+        system_data: &Self::SystemData,
+        _: &[Entity],
+    ) -> Result<Option<Self>, Error> {
+        let render = match system_data.0.get(entity) {
+            Some(render) => render,
+            None => return Ok(None),
+        };
+        match system_data.1.reference(&render.sprite_sheet) {
+            Some(sheet) => Ok(Some(SpriteRenderPrefab {
+                sheet: Some(sheet),
+                sprite_number: render.sprite_number,
+                handle: Some(render.sprite_sheet.clone()),
+            })),
+            None => {
+                let message = format!(
+                    "`SpriteSheet` {:?} of entity {:?} was not loaded by a prefab, so it can't be \
+                     referenced.",
+                    render.sprite_sheet, entity
+                );
+                Err(Error::from_string(message))
+            }
+        }
+    }
+}
+
 /// Prefab for loading a full scene with sprites.
 #[derive(Derivative, Clone, Debug, Deserialize, Serialize)]
 pub struct SpriteScenePrefab {
@@ -395,6 +449,38 @@ mod tests {
         assert_eq!(handle, render.sprite_sheet);
     }
 
+    #[test]
+    fn sprite_render_prefab_export() {
+        let mut world = setup_sprite_world();
+        let (_, handle) = add_sheet(&mut world);
+        let entity = world
+            .create_entity()
+            .with(SpriteRender {
+                sprite_sheet: handle,
+                sprite_number: 3,
+            })
+            .build();
+        let other = world.create_entity().build();
+
+        let prefab = SpriteRenderPrefab::export_from_entity(entity, &world.system_data(), &[])
+            .unwrap()
+            .unwrap();
+        assert_eq!(3, prefab.sprite_number);
+        let ron = ron::ser::to_string(&prefab).unwrap();
+        let mut prefab: SpriteRenderPrefab = ron::de::from_str(&ron).unwrap();
+        prefab
+            .load_sub_assets(&mut ProgressCounter::default(), &mut world.system_data())
+            .unwrap();
+        prefab
+            .add_to_entity(other, &mut world.system_data(), &[other], &[])
+            .unwrap();
+        let storage = world.read_storage::<SpriteRender>();
+        assert_eq!(
+            storage.get(entity).unwrap().sprite_sheet,
+            storage.get(other).unwrap().sprite_sheet
+        );
+    }
+
     #[test]
     fn grid_col_row() {
         let sprites = SpriteGrid {
@@ -11,7 +11,9 @@ use crate::{
     types::{Backend, Mesh, Texture},
     visibility::Visibility,
 };
-use amethyst_assets::{AssetStorage, Handle, HotReloadStrategy, ProcessingState, ThreadPool};
+use amethyst_assets::{
+    AssetStorage, CacheStats, Handle, HotReloadStrategy, ProcessingState, ThreadPool,
+};
 use amethyst_core::{
     components::Transform,
     ecs::{Read, ReadExpect, ReadStorage, RunNow, System, SystemData, World, Write, WriteExpect},
@@ -194,11 +196,12 @@ impl<'a, B: Backend> System<'a> for MeshProcessorSystem<B> {
         ReadExpect<'a, Arc<ThreadPool>>,
         Option<Read<'a, HotReloadStrategy>>,
         ReadExpect<'a, Factory<B>>,
+        Write<'a, CacheStats<Mesh>>,
     );
 
     fn run(
         &mut self,
-        (mut mesh_storage, queue_id, time, pool, strategy, factory): Self::SystemData,
+        (mut mesh_storage, queue_id, time, pool, strategy, factory, mut stats): Self::SystemData,
     ) {
         #[cfg(feature = "profiler")]
         profile_scope!("mesh_processor");
@@ -208,8 +211,9 @@ impl<'a, B: Backend> System<'a> for MeshProcessorSystem<B> {
                 #[cfg(feature = "profiler")]
                 profile_scope!("process_mesh");
 
+                let bytes = b.buffer_size();
                 b.0.build(*queue_id, &factory)
-                    .map(B::wrap_mesh)
+                    .map(|mesh| B::wrap_mesh(mesh, bytes))
                     .map(ProcessingState::Loaded)
                     .map_err(|e| e.compat().into())
             },
@@ -217,6 +221,7 @@ impl<'a, B: Backend> System<'a> for MeshProcessorSystem<B> {
             &**pool,
             strategy.as_deref(),
         );
+        *stats = mesh_storage.cache_stats();
     }
 }
 
@@ -232,11 +237,12 @@ impl<'a, B: Backend> System<'a> for TextureProcessorSystem<B> {
         ReadExpect<'a, Arc<ThreadPool>>,
         Option<Read<'a, HotReloadStrategy>>,
         WriteExpect<'a, Factory<B>>,
+        Write<'a, CacheStats<Texture>>,
     );
 
     fn run(
         &mut self,
-        (mut texture_storage, queue_id, time, pool, strategy, mut factory): Self::SystemData,
+        (mut texture_storage, queue_id, time, pool, strategy, mut factory, mut stats): Self::SystemData,
     ) {
         #[cfg(feature = "profiler")]
         profile_scope!("texture_processor");
@@ -264,6 +270,7 @@ impl<'a, B: Backend> System<'a> for TextureProcessorSystem<B> {
             &**pool,
             strategy.as_deref(),
         );
+        *stats = texture_storage.cache_stats();
     }
 }
 
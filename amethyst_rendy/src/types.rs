@@ -10,7 +10,10 @@ pub trait Backend: rendy::hal::Backend {
     /// Unwrap a Backend to a rendy `Texture`
     fn unwrap_texture(texture: &Texture) -> Option<&rendy::texture::Texture<Self>>;
     /// Wrap a rendy `Mesh` to its Backend generic.
-    fn wrap_mesh(mesh: rendy::mesh::Mesh<Self>) -> Mesh;
+    ///
+    /// `bytes` is the combined length of the vertex and index buffers of the mesh, which rendy
+    /// doesn't expose, see `MeshData::buffer_size`.
+    fn wrap_mesh(mesh: rendy::mesh::Mesh<Self>, bytes: usize) -> Mesh;
     /// Wrap a rendy `Texture` to its Backend generic.
     fn wrap_texture(texture: rendy::texture::Texture<Self>) -> Texture;
 }
@@ -41,8 +44,8 @@ macro_rules! impl_backends {
         pub enum Mesh {
             $(
                 #[cfg(feature = $feature)]
-                #[doc = "Mesh Variant"]
-                $variant(rendy::mesh::Mesh<$backend>),
+                #[doc = "Mesh Variant, with the byte size of its buffers"]
+                $variant(rendy::mesh::Mesh<$backend>, usize),
             )*
         }
 
@@ -62,7 +65,7 @@ macro_rules! impl_backends {
                 #[inline]
                 #[allow(irrefutable_let_patterns)]
                 fn unwrap_mesh(mesh: &Mesh) -> Option<&rendy::mesh::Mesh<Self>> {
-                    if let Mesh::$variant(inner) = mesh {
+                    if let Mesh::$variant(inner, _) = mesh {
                         Some(inner)
                     } else {
                         None
@@ -78,8 +81,8 @@ macro_rules! impl_backends {
                     }
                 }
                 #[inline]
-                fn wrap_mesh(mesh: rendy::mesh::Mesh<Self>) -> Mesh {
-                    Mesh::$variant(mesh)
+                fn wrap_mesh(mesh: rendy::mesh::Mesh<Self>, bytes: usize) -> Mesh {
+                    Mesh::$variant(mesh, bytes)
                 }
                 #[inline]
                 fn wrap_texture(texture: rendy::texture::Texture<Self>) -> Texture {
@@ -87,9 +90,56 @@ macro_rules! impl_backends {
                 }
             }
         )*
+
+        impl Asset for Mesh {
+            const NAME: &'static str = "Mesh";
+            type Data = MeshData;
+            type HandleStorage = DenseVecStorage<Handle<Self>>;
+
+            fn size_hint(&self) -> usize {
+                match self {
+                    $(
+                        #[cfg(feature = $feature)]
+                        Mesh::$variant(_, bytes) => *bytes,
+                    )*
+                }
+            }
+        }
+
+        impl Asset for Texture {
+            const NAME: &'static str = "Mesh";
+            type Data = TextureData;
+            type HandleStorage = DenseVecStorage<Handle<Self>>;
+
+            fn size_hint(&self) -> usize {
+                match self {
+                    $(
+                        #[cfg(feature = $feature)]
+                        Texture::$variant(inner) => texture_size(inner),
+                    )*
+                }
+            }
+        }
     };
 }
 
+/// Size of the image of a texture in bytes, including all mip levels and layers.
+fn texture_size<B: rendy::hal::Backend>(texture: &rendy::texture::Texture<B>) -> usize {
+    let image = texture.image();
+    let desc = image.format().surface_desc();
+    let (block_width, block_height) = (u32::from(desc.dim.0), u32::from(desc.dim.1));
+    let extent = image.kind().extent();
+    let blocks: usize = (0..image.levels())
+        .map(|level| {
+            let extent = extent.at_level(level);
+            let width = (extent.width + block_width - 1) / block_width;
+            let height = (extent.height + block_height - 1) / block_height;
+            width as usize * height as usize * extent.depth as usize
+        })
+        .sum();
+    blocks * usize::from(image.layers()) * usize::from(desc.bits) / 8
+}
+
 // Create `DefaultBackend` type alias only when exactly one backend is selected.
 macro_rules! impl_single_default {
     ( $([$feature:literal, $backend:ty]),* ) => {
@@ -118,18 +168,6 @@ impl_backends!(
     Empty, "empty", rendy::empty::Backend;
 );
 
-impl Asset for Mesh {
-    const NAME: &'static str = "Mesh";
-    type Data = MeshData;
-    type HandleStorage = DenseVecStorage<Handle<Self>>;
-}
-
-impl Asset for Texture {
-    const NAME: &'static str = "Mesh";
-    type Data = TextureData;
-    type HandleStorage = DenseVecStorage<Handle<Self>>;
-}
-
 /// Newtype for MeshBuilder prefab usage.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MeshData(
@@ -140,6 +178,16 @@ pub struct MeshData(
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TextureData(pub rendy::texture::TextureBuilder<'static>);
 
+impl MeshData {
+    /// Approximate combined length of the vertex and index buffers built from this data.
+    ///
+    /// The builder doesn't expose its data, so this is the serialized size of the builder, which
+    /// is dominated by the vertex and index bytes.
+    pub fn buffer_size(&self) -> usize {
+        bincode::serialized_size(&self.0).unwrap_or(0) as usize
+    }
+}
+
 impl From<rendy::mesh::MeshBuilder<'static>> for MeshData {
     fn from(builder: rendy::mesh::MeshBuilder<'static>) -> Self {
         Self(builder)
@@ -158,3 +206,23 @@ where
 {
     Ok(rendy::mesh::MeshBuilder::deserialize(deserializer)?.into_owned())
 }
+
+#[cfg(test)]
+mod tests {
+    use rendy::mesh::{MeshBuilder, Position};
+
+    use super::MeshData;
+
+    #[test]
+    fn buffer_size_counts_vertices_and_indices() {
+        let data = MeshData::from(
+            MeshBuilder::new()
+                .with_vertices(vec![Position([0.0; 3]); 100])
+                .with_indices(vec![0u16; 300])
+                .into_owned(),
+        );
+        let buffers = 100 * 12 + 300 * 2;
+        let size = data.buffer_size();
+        assert!(size >= buffers && size < buffers + 256, "{}", size);
+    }
+}
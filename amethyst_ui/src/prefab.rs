@@ -6,13 +6,13 @@ use std::{
 };
 
 use amethyst_assets::{
-    AssetPrefab, AssetStorage, Format, Handle, Loader, Prefab, PrefabData, PrefabLoaderSystem,
-    PrefabLoaderSystemDesc, Progress, ProgressCounter,
+    AssetPrefab, AssetStorage, ExportPrefabData, Format, Handle, Loader, Prefab, PrefabData,
+    PrefabLoaderSystem, PrefabLoaderSystemDesc, Progress, ProgressCounter,
 };
 use amethyst_audio::Source as Audio;
 use amethyst_core::{
     ecs::{
-        prelude::{Entities, Entity, Read, ReadExpect, World, Write, WriteStorage},
+        prelude::{Entities, Entity, Read, ReadExpect, ReadStorage, World, Write, WriteStorage},
         shred::{ResourceId, SystemData},
     },
     HiddenPropagate,
@@ -23,9 +23,9 @@ use amethyst_rendy::TexturePrefab;
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    get_default_font, Anchor, Draggable, FontAsset, Interactable, LineMode, Selectable, Stretch,
-    TextEditing, UiButton, UiButtonAction, UiButtonActionRetrigger, UiButtonActionType, UiImage,
-    UiPlaySoundAction, UiSoundRetrigger, UiText, UiTransform, WidgetId, Widgets,
+    get_default_font, Anchor, Draggable, FontAsset, Interactable, LineMode, ScaleMode, Selectable,
+    Stretch, TextEditing, UiButton, UiButtonAction, UiButtonActionRetrigger, UiButtonActionType,
+    UiImage, UiPlaySoundAction, UiSoundRetrigger, UiText, UiTransform, WidgetId, Widgets,
 };
 
 /// Loadable `UiTransform` data.
@@ -200,6 +200,89 @@ where
 
         Ok(())
     }
+
+    fn remove_from_entity(
+        &self,
+        entity: Entity,
+        system_data: &mut Self::SystemData,
+    ) -> Result<(), Error> {
+        // Only remove what `add_to_entity` inserted, components added at runtime are kept.
+        system_data.0.remove(entity);
+        if self.mouse_reactive {
+            system_data.1.remove(entity);
+        }
+
+        // Leave hidden state inserted by the `HideHierarchySystem` to that system.
+        if self.hidden {
+            match system_data.2.get(entity) {
+                Some(hidden) if !hidden.is_propagated() => {
+                    system_data.2.remove(entity);
+                }
+                _ => {}
+            }
+        }
+
+        if self.selectable.is_some() {
+            system_data.3.remove(entity);
+        }
+
+        if self.draggable {
+            system_data.4.remove(entity);
+        }
+
+        Ok(())
+    }
+}
+
+impl<'a, G> ExportPrefabData<'a> for UiTransformData<G>
+where
+    G: Send + Sync + 'static,
+{
+    type SystemData = (
+        ReadStorage<'a, UiTransform>,
+        ReadStorage<'a, Interactable>,
+        ReadStorage<'a, HiddenPropagate>,
+        ReadStorage<'a, Selectable<G>>,
+        ReadStorage<'a, Draggable>,
+    );
+
+    fn export_from_entity(
+        entity: Entity,
+        system_data: &Self::SystemData,
+        _: &[Entity],
+    ) -> Result<Option<Self>, Error> {
+        let transform = match system_data.0.get(entity) {
+            Some(transform) => transform,
+            None => return Ok(None),
+        };
+        let stretch = match transform.stretch {
+            Stretch::NoStretch => None,
+            ref stretch => Some(stretch.clone()),
+        };
+        Ok(Some(UiTransformData {
+            id: transform.id.clone(),
+            x: transform.local_x,
+            y: transform.local_y,
+            z: transform.local_z,
+            width: transform.width,
+            height: transform.height,
+            opaque: transform.opaque,
+            transparent_target: transform.transparent_target,
+            percent: transform.scale_mode == ScaleMode::Percent,
+            stretch,
+            anchor: transform.anchor,
+            pivot: transform.pivot,
+            mouse_reactive: system_data.1.contains(entity),
+            // Hidden parents hide their children again when the prefab is loaded.
+            hidden: match system_data.2.get(entity) {
+                Some(hidden) => !hidden.is_propagated(),
+                None => false,
+            },
+            selectable: system_data.3.get(entity).map(|selectable| selectable.order),
+            draggable: system_data.4.contains(entity),
+            _phantom: PhantomData,
+        }))
+    }
 }
 
 /// Loadable `UiText` data
@@ -1119,3 +1202,34 @@ fn button_text_transform<G>(mut id: String) -> UiTransformData<G> {
         })
         .transparent()
 }
+
+#[cfg(test)]
+mod tests {
+    use amethyst_assets::PrefabData;
+    use amethyst_core::ecs::prelude::{Builder, SystemData, World, WorldExt};
+
+    use super::UiTransformData;
+    use crate::Draggable;
+
+    type Data<'a> = <UiTransformData<()> as PrefabData<'a>>::SystemData;
+
+    #[test]
+    fn reinstantiation_keeps_components_added_at_runtime() {
+        let mut world = World::new();
+        Data::setup(&mut world);
+        let entity = world.create_entity().build();
+        let prefab = UiTransformData::<()>::default().reactive();
+
+        let mut data = Data::fetch(&world);
+        prefab.add_to_entity(entity, &mut data, &[], &[]).unwrap();
+        data.4.insert(entity, Draggable).unwrap();
+
+        prefab.remove_from_entity(entity, &mut data).unwrap();
+        assert!(!data.1.contains(entity));
+        prefab.add_to_entity(entity, &mut data, &[], &[]).unwrap();
+
+        assert!(data.0.contains(entity));
+        assert!(data.1.contains(entity));
+        assert!(data.4.contains(entity));
+    }
+}
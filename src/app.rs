@@ -19,13 +19,17 @@ use crate::{
     core::{
         frame_limiter::{FrameLimiter, FrameRateLimitConfig, FrameRateLimitStrategy},
         shrev::{EventChannel, ReaderId},
+        snapshot::WorldSnapshots,
         timing::{Stopwatch, Time},
+        transform::capture_transform_interpolations,
         ArcThreadPool, EventReader, Named,
     },
     ecs::prelude::{Component, Read, World, WorldExt, Write},
     error::Error,
     game_data::{DataDispose, DataInit},
-    state::{State, StateData, StateMachine, TransEvent},
+    state::{
+        State, StateData, StateMachine, StateTransitionEvent, StateTransitionHistory, TransEvent,
+    },
     state_event::{StateEvent, StateEventReader},
     ui::UiEvent,
 };
@@ -60,6 +64,9 @@ where
     trans_reader_id: ReaderId<TransEvent<T, E>>,
     states: StateMachine<'a, T, E>,
     ignore_window_close: bool,
+    headless: Option<Duration>,
+    initialized: bool,
+    shut_down: bool,
     data: T,
 }
 
@@ -237,32 +244,68 @@ where
         };
 
         self.initialize();
-        self.world.write_resource::<Stopwatch>().start();
         while self.states.is_running() {
             self.advance_frame();
-            {
+            if self.headless.is_none() {
                 #[cfg(feature = "profiler")]
                 profile_scope!("frame_limiter wait");
                 self.world.write_resource::<FrameLimiter>().wait();
             }
-            {
-                let elapsed = self.world.read_resource::<Stopwatch>().elapsed();
-                let mut time = self.world.write_resource::<Time>();
-                time.increment_frame_number();
-                time.set_delta_time(elapsed);
-            }
-            let mut stopwatch = self.world.write_resource::<Stopwatch>();
-            stopwatch.stop();
-            stopwatch.restart();
+            self.finish_frame();
         }
 
         self.shutdown();
     }
 
+    /// Runs the given number of frames, or until the last state is popped, and returns whether
+    /// the application is still running.
+    ///
+    /// The application is initialized on the first call and shut down once it stops running.
+    /// Unlike `run`, this doesn't wait for the `FrameLimiter`, so together with
+    /// `ApplicationBuilder::headless` the simulation can be advanced as fast as possible and the
+    /// `World` inspected between steps.
+    pub fn step(&mut self, frames: u64) -> bool
+    where
+        for<'b> R: EventReader<'b, Event = E>,
+    {
+        self.initialize();
+        for _ in 0..frames {
+            if !self.states.is_running() {
+                break;
+            }
+            self.advance_frame();
+            self.finish_frame();
+        }
+        if !self.states.is_running() {
+            self.shutdown();
+        }
+        self.states.is_running()
+    }
+
+    /// Returns whether the application is running, i.e. was initialized and didn't stop yet.
+    pub fn is_running(&self) -> bool {
+        self.states.is_running()
+    }
+
+    /// Returns the `World` of the application.
+    pub fn world(&self) -> &World {
+        &self.world
+    }
+
+    /// Returns the `World` of the application mutably.
+    pub fn world_mut(&mut self) -> &mut World {
+        &mut self.world
+    }
+
     /// Sets up the application.
     fn initialize(&mut self) {
+        if self.initialized {
+            return;
+        }
+        self.initialized = true;
         #[cfg(feature = "profiler")]
         profile_scope!("initialize");
+        self.world.write_resource::<Stopwatch>().start();
         self.states
             .start(StateData::new(&mut self.world, &mut self.data))
             .expect("Tried to start state machine without any states present");
@@ -365,8 +408,12 @@ where
                 self.world.write_resource::<Time>().start_fixed_update();
             }
             while { self.world.write_resource::<Time>().step_fixed_update() } {
+                if let Some(mut snapshots) = self.world.try_fetch_mut::<WorldSnapshots>() {
+                    snapshots.capture(&self.world);
+                }
                 self.states
                     .fixed_update(StateData::new(&mut self.world, &mut self.data));
+                capture_transform_interpolations(&self.world);
             }
             {
                 self.world.write_resource::<Time>().finish_fixed_update();
@@ -384,8 +431,28 @@ where
         self.world.maintain();
     }
 
+    /// Updates the frame number and measures the duration of the frame.
+    fn finish_frame(&mut self) {
+        let elapsed = match self.headless {
+            Some(delta) => delta,
+            None => self.world.read_resource::<Stopwatch>().elapsed(),
+        };
+        {
+            let mut time = self.world.write_resource::<Time>();
+            time.increment_frame_number();
+            time.set_delta_time(elapsed);
+        }
+        let mut stopwatch = self.world.write_resource::<Stopwatch>();
+        stopwatch.stop();
+        stopwatch.restart();
+    }
+
     /// Cleans up after the quit signal is received.
     fn shutdown(&mut self) {
+        if self.shut_down {
+            return;
+        }
+        self.shut_down = true;
         info!("Engine is shutting down");
         self.data.dispose(&mut self.world);
     }
@@ -417,6 +484,7 @@ pub struct ApplicationBuilder<S, T, E, R> {
     /// Used by bundles to access the world directly
     pub world: World,
     ignore_window_close: bool,
+    headless: Option<Duration>,
     phantom: PhantomData<(T, E, R)>,
 }
 
@@ -546,6 +614,8 @@ where
         world.insert(EventChannel::<Event>::with_capacity(2000));
         world.insert(EventChannel::<UiEvent>::with_capacity(40));
         world.insert(EventChannel::<TransEvent<T, StateEvent>>::with_capacity(2));
+        world.insert(EventChannel::<StateTransitionEvent>::with_capacity(16));
+        world.insert(StateTransitionHistory::default());
         world.insert(FrameLimiter::default());
         world.insert(Stopwatch::default());
         world.insert(Time::default());
@@ -557,6 +627,7 @@ where
             initial_state,
             world,
             ignore_window_close: false,
+            headless: None,
             phantom: PhantomData,
         })
     }
@@ -854,6 +925,28 @@ where
         self
     }
 
+    /// Runs the application headless, with every frame lasting exactly `delta`.
+    ///
+    /// The frame time is not measured and the `FrameLimiter` is not waited for, so the
+    /// application runs as fast as possible and every run of the same input is simulated the
+    /// same way. Use this for servers, tests and training simulations, which don't add a window
+    /// or rendering bundle, and drive the application with `CoreApplication::step`.
+    ///
+    /// # Parameters
+    ///
+    /// `delta`: The duration of every frame.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the ApplicationBuilder after modifying it.
+    pub fn headless(mut self, delta: Duration) -> Self {
+        self.world
+            .write_resource::<Time>()
+            .set_virtual_delta(Some(delta));
+        self.headless = Some(delta);
+        self
+    }
+
     /// Build an `Application` object using the `ApplicationBuilder` as configured.
     ///
     /// # Returns
@@ -906,9 +999,107 @@ where
             reader,
             events: Vec::new(),
             ignore_window_close: self.ignore_window_close,
+            headless: self.headless,
+            initialized: false,
+            shut_down: false,
             data,
             event_reader_id,
             trans_reader_id,
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::{
+        ecs::prelude::{System, Write},
+        game_data::{GameData, GameDataBuilder},
+        state::{SimpleState, SimpleTrans, Trans},
+        Application,
+    };
+
+    #[derive(Default)]
+    struct Counter {
+        fixed_updates: u32,
+        fixed_systems: u32,
+        updates: u32,
+    }
+
+    struct FixedSystem;
+
+    impl<'a> System<'a> for FixedSystem {
+        type SystemData = Write<'a, Counter>;
+
+        fn run(&mut self, mut counter: Self::SystemData) {
+            counter.fixed_systems += 1;
+        }
+    }
+
+    struct CountingState;
+
+    impl SimpleState for CountingState {
+        fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+            data.world.insert(Counter::default());
+        }
+
+        fn fixed_update(&mut self, data: StateData<'_, GameData<'_, '_>>) -> SimpleTrans {
+            data.world.write_resource::<Counter>().fixed_updates += 1;
+            Trans::None
+        }
+
+        fn update(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) -> SimpleTrans {
+            let mut counter = data.world.write_resource::<Counter>();
+            counter.updates += 1;
+            if counter.updates == 25 {
+                Trans::Quit
+            } else {
+                Trans::None
+            }
+        }
+    }
+
+    #[test]
+    fn headless_steps_are_deterministic() {
+        let mut game = Application::build(".", CountingState)
+            .unwrap()
+            .headless(Duration::from_millis(10))
+            .with_fixed_step_length(Duration::from_millis(20))
+            .build(GameDataBuilder::default())
+            .unwrap();
+        assert!(!game.is_running());
+
+        assert!(game.step(10));
+        {
+            let world = game.world();
+            let time = world.read_resource::<Time>();
+            assert_eq!(10, time.frame_number());
+            // Includes the upcoming frame.
+            assert_eq!(Duration::from_millis(110), time.absolute_time());
+            let counter = world.read_resource::<Counter>();
+            assert_eq!(10, counter.updates);
+            assert_eq!(5, counter.fixed_updates);
+        }
+
+        assert!(!game.step(100));
+        assert_eq!(25, game.world().read_resource::<Counter>().updates);
+        assert!(!game.step(1));
+    }
+
+    #[test]
+    fn fixed_systems_run_per_fixed_update() {
+        let mut game = Application::build(".", CountingState)
+            .unwrap()
+            .headless(Duration::from_millis(10))
+            .with_fixed_step_length(Duration::from_millis(4))
+            .build(GameDataBuilder::default().with_fixed(FixedSystem, "fixed", &[]))
+            .unwrap();
+
+        assert!(game.step(3));
+        let counter = game.world().read_resource::<Counter>();
+        assert_eq!(7, counter.fixed_updates);
+        assert_eq!(counter.fixed_updates, counter.fixed_systems);
+    }
+}
@@ -6,10 +6,14 @@ use crate::{
             AddBarrier, AddBundle, AddSystem, AddSystemDesc, AddThreadLocal, AddThreadLocalDesc,
             DispatcherOperation,
         },
-        ecs::prelude::{Dispatcher, DispatcherBuilder, RunNow, System, World, WorldExt},
-        ArcThreadPool, RunNowDesc, SystemBundle, SystemDesc,
+        ecs::prelude::{
+            Dispatcher, DispatcherBuilder, RunNow, System, SystemData, World, WorldExt,
+        },
+        ArcThreadPool, RunNowDesc, SystemBundle, SystemDesc, Time,
     },
     error::Error,
+    system_profiler::{Profiled, ProfiledDesc, SystemProfiler},
+    system_set::{InSet, RunCriteria, Skip, SystemSets},
 };
 
 /// Initialise trait for game data
@@ -31,6 +35,8 @@ pub trait DataDispose {
 #[allow(missing_debug_implementations)]
 pub struct GameData<'a, 'b> {
     dispatcher: Option<Dispatcher<'a, 'b>>,
+    fixed_dispatcher: Option<Dispatcher<'a, 'b>>,
+    system_sets: Vec<(String, RunCriteria)>,
 }
 
 impl<'a, 'b> GameData<'a, 'b> {
@@ -38,21 +44,66 @@ impl<'a, 'b> GameData<'a, 'b> {
     pub fn new(dispatcher: Dispatcher<'a, 'b>) -> Self {
         GameData {
             dispatcher: Some(dispatcher),
+            fixed_dispatcher: None,
+            system_sets: Vec::new(),
+        }
+    }
+
+    /// Create new game data with a dispatcher that is run once per fixed update
+    pub fn with_fixed(
+        dispatcher: Dispatcher<'a, 'b>,
+        fixed_dispatcher: Dispatcher<'a, 'b>,
+    ) -> Self {
+        GameData {
+            dispatcher: Some(dispatcher),
+            fixed_dispatcher: Some(fixed_dispatcher),
+            system_sets: Vec::new(),
         }
     }
 
     /// Update game data
     pub fn update(&mut self, world: &World) {
+        if !self.system_sets.is_empty() {
+            let running = self
+                .system_sets
+                .iter_mut()
+                .map(|(set, criteria)| (set.as_str(), criteria.check(world)))
+                .collect::<Vec<_>>();
+            let mut sets = world.write_resource::<SystemSets>();
+            for (set, running) in running {
+                sets.set_running(set, running);
+            }
+        }
+        let frame_number = match world.try_fetch::<Time>() {
+            Some(time) => time.frame_number(),
+            None => 0,
+        };
+        if let Some(mut profiler) = world.try_fetch_mut::<SystemProfiler>() {
+            profiler.begin_frame(frame_number);
+        }
         if let Some(dispatcher) = &mut self.dispatcher {
             dispatcher.dispatch(&world);
         }
+        if let Some(mut profiler) = world.try_fetch_mut::<SystemProfiler>() {
+            profiler.end_frame();
+        }
     }
 
-    /// Dispose game data, dropping the dispatcher
+    /// Run the fixed update systems once
+    pub fn fixed_update(&mut self, world: &World) {
+        if let Some(dispatcher) = &mut self.fixed_dispatcher {
+            dispatcher.dispatch(&world);
+        }
+    }
+
+    /// Dispose game data, dropping the dispatchers
     pub fn dispose(&mut self, mut world: &mut World) {
         if let Some(dispatcher) = self.dispatcher.take() {
             dispatcher.dispose(&mut world);
         }
+        if let Some(dispatcher) = self.fixed_dispatcher.take() {
+            dispatcher.dispose(&mut world);
+        }
     }
 }
 
@@ -70,6 +121,8 @@ impl DataDispose for GameData<'_, '_> {
 #[allow(missing_debug_implementations)]
 pub struct GameDataBuilder<'a, 'b> {
     dispatcher_operations: Vec<Box<dyn DispatcherOperation<'a, 'b>>>,
+    fixed_dispatcher_operations: Vec<Box<dyn DispatcherOperation<'a, 'b>>>,
+    system_sets: Vec<(String, RunCriteria)>,
     disp_builder: DispatcherBuilder<'a, 'b>,
 }
 
@@ -84,6 +137,8 @@ impl<'a, 'b> GameDataBuilder<'a, 'b> {
     pub fn new() -> Self {
         GameDataBuilder {
             dispatcher_operations: Vec::new(),
+            fixed_dispatcher_operations: Vec::new(),
+            system_sets: Vec::new(),
             disp_builder: DispatcherBuilder::new(),
         }
     }
@@ -194,7 +249,7 @@ impl<'a, 'b> GameDataBuilder<'a, 'b> {
             .map(Into::<String>::into)
             .collect::<Vec<String>>();
         let dispatcher_operation = Box::new(AddSystem {
-            system,
+            system: Profiled::new(&name, system),
             name,
             dependencies,
         }) as Box<dyn DispatcherOperation<'a, 'b> + 'static>;
@@ -279,10 +334,13 @@ impl<'a, 'b> GameDataBuilder<'a, 'b> {
             .map(Into::<String>::into)
             .collect::<Vec<String>>();
         let dispatcher_operation = Box::new(AddSystemDesc {
-            system_desc,
+            system_desc: ProfiledDesc {
+                name: name.clone(),
+                system_desc,
+            },
             name,
             dependencies,
-            marker: PhantomData::<S>,
+            marker: PhantomData::<Profiled<S>>,
         }) as Box<dyn DispatcherOperation<'a, 'b> + 'static>;
         self.dispatcher_operations.push(dispatcher_operation);
         self
@@ -421,6 +479,146 @@ impl<'a, 'b> GameDataBuilder<'a, 'b> {
         Ok(self)
     }
 
+    /// Declares a named set of systems, which only run in frames `criteria` is met in.
+    ///
+    /// The criteria of all sets are checked once per frame, before any system runs. Add systems
+    /// to the set with `with_in_set`.
+    ///
+    /// # Panics
+    ///
+    /// If a set with the same name was already declared, this function will panic.
+    ///
+    /// # Examples
+    ///
+    /// ~~~no_run
+    /// use amethyst::core::SystemDesc;
+    /// use amethyst::derive::SystemDesc;
+    /// use amethyst::prelude::*;
+    /// use amethyst::ecs::prelude::{System, SystemData, World};
+    /// use amethyst::{RunCriteria, Skip};
+    ///
+    /// #[derive(SystemDesc)]
+    /// struct NopSystem;
+    /// impl<'a> System<'a> for NopSystem {
+    ///     type SystemData = ();
+    ///     fn run(&mut self, _: Self::SystemData) {}
+    /// }
+    /// impl<'a> Skip<'a> for NopSystem {
+    ///     fn skip(&mut self, _: Self::SystemData) {}
+    /// }
+    ///
+    /// GameDataBuilder::default()
+    ///     // The "ai" systems only run every tenth frame.
+    ///     .with_system_set("ai", RunCriteria::every_n_frames(10))
+    ///     .with_in_set("ai", NopSystem, "pathfinding", &[])
+    ///     .with_in_set("ai", NopSystem, "decisions", &["pathfinding"]);
+    /// ~~~
+    pub fn with_system_set<N>(mut self, name: N, criteria: RunCriteria) -> Self
+    where
+        N: Into<String>,
+    {
+        let name = name.into();
+        if self.system_sets.iter().any(|(set, _)| *set == name) {
+            panic!("System set {} was declared twice", name);
+        }
+        self.system_sets.push((name, criteria));
+        self
+    }
+
+    /// Adds a given system to a system set, `Skip::skip` is called instead of `run` in frames
+    /// the set is skipped.
+    ///
+    /// Other than that, this behaves like `with`. Requiring `Skip` makes sure systems reading
+    /// from an `EventChannel` drain their readers while the set is skipped, instead of seeing
+    /// all the old events once the set runs again.
+    ///
+    /// # Panics
+    ///
+    /// If the set wasn't declared with `with_system_set` before, and in the same cases as
+    /// `with`, this function will panic.
+    pub fn with_in_set<S, N>(self, set: &str, system: S, name: N, dependencies: &[N]) -> Self
+    where
+        S: for<'c> Skip<'c> + 'static + Send,
+        for<'c> <S as System<'c>>::SystemData: SystemData<'c>,
+        N: Into<String> + Clone,
+    {
+        let set = self.system_set_name(set);
+        self.with(InSet { set, system }, name, dependencies)
+    }
+
+    fn system_set_name(&self, set: &str) -> String {
+        if !self.system_sets.iter().any(|(name, _)| name == set) {
+            panic!("System set {} was not declared", set);
+        }
+        set.to_string()
+    }
+
+    /// Adds a given system to the fixed update dispatcher.
+    ///
+    /// The systems of the fixed update dispatcher run once per fixed update, as many times per
+    /// frame as `Time::step_fixed_update` dictates, right after `SimpleState::fixed_update`.
+    /// Use it for simulation systems, which should advance by `Time::fixed_seconds` each run.
+    ///
+    /// The fixed update dispatcher is separate from the one `with` adds to, so `dependencies`
+    /// may only name other systems added with `with_fixed` or `with_fixed_bundle`.
+    ///
+    /// # Panics
+    ///
+    /// Same as for `with`.
+    ///
+    /// # Examples
+    ///
+    /// ~~~no_run
+    /// use amethyst::core::SystemDesc;
+    /// use amethyst::derive::SystemDesc;
+    /// use amethyst::prelude::*;
+    /// use amethyst::ecs::prelude::{System, SystemData, World};
+    ///
+    /// #[derive(SystemDesc)]
+    /// struct NopSystem;
+    /// impl<'a> System<'a> for NopSystem {
+    ///     type SystemData = ();
+    ///     fn run(&mut self, _: Self::SystemData) {}
+    /// }
+    ///
+    /// GameDataBuilder::default()
+    ///     // "physics" runs once per fixed update, "animation" once per frame.
+    ///     .with_fixed(NopSystem, "physics", &[])
+    ///     .with(NopSystem, "animation", &[]);
+    /// ~~~
+    pub fn with_fixed<S, N>(mut self, system: S, name: N, dependencies: &[N]) -> Self
+    where
+        S: for<'c> System<'c> + 'static + Send,
+        N: Into<String> + Clone,
+    {
+        let name = Into::<String>::into(name);
+        let dependencies = dependencies
+            .iter()
+            .map(Clone::clone)
+            .map(Into::<String>::into)
+            .collect::<Vec<String>>();
+        self.fixed_dispatcher_operations.push(Box::new(AddSystem {
+            system,
+            name,
+            dependencies,
+        }));
+        self
+    }
+
+    /// Add a given ECS bundle to the fixed update dispatcher, see `with_fixed`.
+    ///
+    /// # Errors
+    ///
+    /// Same as for `with_bundle`.
+    pub fn with_fixed_bundle<B>(mut self, bundle: B) -> Result<Self, Error>
+    where
+        B: SystemBundle<'a, 'b> + 'static,
+    {
+        self.fixed_dispatcher_operations
+            .push(Box::new(AddBundle { bundle }));
+        Ok(self)
+    }
+
     // /// Create a basic renderer with a single given `Pass`, and optional support for the `DrawUi` pass.
     // ///
     // /// Will set the clear color to black.
@@ -467,6 +665,13 @@ impl<'a, 'b> DataInit<GameData<'a, 'b>> for GameDataBuilder<'a, 'b> {
 
         let mut dispatcher_builder = self.disp_builder;
 
+        if !self.system_sets.is_empty() {
+            world.insert(SystemSets::default());
+        }
+        world
+            .entry::<SystemProfiler>()
+            .or_insert_with(SystemProfiler::default);
+
         self.dispatcher_operations
             .into_iter()
             .try_for_each(|dispatcher_operation| {
@@ -475,11 +680,34 @@ impl<'a, 'b> DataInit<GameData<'a, 'b>> for GameDataBuilder<'a, 'b> {
             .unwrap_or_else(|e| panic!("Failed to set up dispatcher: {}", e));
 
         #[cfg(not(no_threading))]
-        let mut dispatcher = dispatcher_builder.with_pool(pool).build();
+        let mut dispatcher = dispatcher_builder.with_pool(pool.clone()).build();
         #[cfg(no_threading)]
         let mut dispatcher = dispatcher_builder.build();
         dispatcher.setup(&mut world);
-        GameData::new(dispatcher)
+
+        if self.fixed_dispatcher_operations.is_empty() {
+            let mut game_data = GameData::new(dispatcher);
+            game_data.system_sets = self.system_sets;
+            return game_data;
+        }
+
+        let mut fixed_dispatcher_builder = DispatcherBuilder::new();
+
+        self.fixed_dispatcher_operations
+            .into_iter()
+            .try_for_each(|dispatcher_operation| {
+                dispatcher_operation.exec(world, &mut fixed_dispatcher_builder)
+            })
+            .unwrap_or_else(|e| panic!("Failed to set up fixed update dispatcher: {}", e));
+
+        #[cfg(not(no_threading))]
+        let mut fixed_dispatcher = fixed_dispatcher_builder.with_pool(pool).build();
+        #[cfg(no_threading)]
+        let mut fixed_dispatcher = fixed_dispatcher_builder.build();
+        fixed_dispatcher.setup(&mut world);
+        let mut game_data = GameData::with_fixed(dispatcher, fixed_dispatcher);
+        game_data.system_sets = self.system_sets;
+        game_data
     }
 }
 
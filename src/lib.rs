@@ -99,10 +99,13 @@ pub use self::{
     game_data::{DataDispose, DataInit, GameData, GameDataBuilder},
     logger::{start_logger, LevelFilter as LogLevelFilter, Logger, LoggerConfig, StdoutLog},
     state::{
-        EmptyState, EmptyTrans, SimpleState, SimpleTrans, State, StateData, StateMachine, Trans,
-        TransEvent,
+        EmptyState, EmptyTrans, LoadingProgress, LoadingState, SimpleState, SimpleTrans, State,
+        StateData, StateError, StateMachine, StateRegions, StateTransitionEvent,
+        StateTransitionHistory, Trans, TransEvent, TransKind,
     },
     state_event::{StateEvent, StateEventReader},
+    system_profiler::{FrameProfile, SystemProfiler, SystemTiming},
+    system_set::{RunCriteria, Skip, SystemSets},
 };
 
 /// Convenience alias for use in main functions that uses Amethyst.
@@ -116,3 +119,5 @@ mod game_data;
 mod logger;
 mod state;
 mod state_event;
+mod system_profiler;
+mod system_set;
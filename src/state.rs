@@ -3,10 +3,19 @@
 use amethyst_input::is_close_requested;
 
 use derivative::Derivative;
+use log::error;
 
-use crate::{ecs::World, GameData, StateEvent};
+use crate::{
+    assets::{AssetErrorMeta, Completion, ProgressCounter},
+    core::{shrev::EventChannel, Time},
+    ecs::World,
+    DataDispose, DataInit, GameData, StateEvent,
+};
 
-use std::fmt::{Debug, Display, Formatter, Result as FmtResult};
+use std::{
+    collections::VecDeque,
+    fmt::{Debug, Display, Formatter, Result as FmtResult},
+};
 
 #[cfg(feature = "profiler")]
 use thread_profiler::profile_scope;
@@ -14,7 +23,10 @@ use thread_profiler::profile_scope;
 /// Error type for errors occurring in `StateMachine`
 #[derive(Debug)]
 pub enum StateError {
+    /// The state machine was started without any states.
     NoStatesPresent,
+    /// Assets preloaded by a `LoadingState` failed to load.
+    LoadingFailed(Vec<AssetErrorMeta>),
 }
 
 impl Display for StateError {
@@ -24,6 +36,17 @@ impl Display for StateError {
                 fmt,
                 "Tried to start state machine without any states present"
             ),
+            StateError::LoadingFailed(ref errors) => {
+                write!(fmt, "Failed to load {} asset(s):", errors.len())?;
+                for error in errors {
+                    write!(
+                        fmt,
+                        " {} {:?}: {};",
+                        error.asset_type_name, error.asset_name, error.error
+                    )?;
+                }
+                Ok(())
+            }
         }
     }
 }
@@ -142,6 +165,11 @@ pub trait State<T, E: Send + Sync + 'static> {
     /// even when this is not the active state,
     /// as long as this state is on the [StateMachine](struct.StateMachine.html)'s state-stack.
     fn shadow_update(&mut self, _data: StateData<'_, T>) {}
+
+    /// Name of the state in `StateTransitionEvent`s, defaults to the name of its type.
+    fn name(&self) -> &'static str {
+        std::any::type_name::<Self>()
+    }
 }
 
 /// An empty `State` trait. It contains no `StateData` or custom `StateEvent`.
@@ -279,7 +307,7 @@ pub trait SimpleState {
     }
 
     /// Executed repeatedly at stable, predictable intervals (1/60th of a second
-    /// by default).
+    /// by default). The systems added with `GameDataBuilder::with_fixed` run right after it.
     fn fixed_update(&mut self, _data: StateData<'_, GameData<'_, '_>>) -> SimpleTrans {
         Trans::None
     }
@@ -336,7 +364,10 @@ impl<T: SimpleState> State<GameData<'static, 'static>, StateEvent> for T {
     /// Executed repeatedly at stable, predictable intervals (1/60th of a second
     /// by default).
     fn fixed_update(&mut self, data: StateData<'_, GameData<'_, '_>>) -> SimpleTrans {
-        self.fixed_update(data)
+        let StateData { world, data } = data;
+        let r = self.fixed_update(StateData { world, data });
+        data.fixed_update(&world);
+        r
     }
 
     /// Executed on every frame immediately, as fast as the engine will allow (taking into account the frame rate limit).
@@ -362,11 +393,115 @@ impl<T: SimpleState> State<GameData<'static, 'static>, StateEvent> for T {
     }
 }
 
+/// Kind of a state transition, see `StateTransitionEvent`.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum TransKind {
+    /// The state machine was started with its initial state.
+    Start,
+    /// `Trans::Pop`
+    Pop,
+    /// `Trans::Push`, which pauses the previously active state.
+    Push,
+    /// `Trans::Switch`
+    Switch,
+    /// `Trans::Replace`
+    Replace,
+    /// `Trans::NewStack`
+    NewStack,
+    /// `Trans::Quit`, or the state machine was stopped because the window was closed.
+    Quit,
+}
+
+/// Event published by the `StateMachine` for every state transition it performed.
+///
+/// The events are written to the `EventChannel<StateTransitionEvent>` and appended to the
+/// `StateTransitionHistory` resources, if they exist; the `ApplicationBuilder` adds both. Every
+/// `Trans` of a `Trans::Sequence` results in its own event.
+#[derive(Clone, Debug, PartialEq)]
+pub struct StateTransitionEvent {
+    /// The kind of the transition.
+    pub kind: TransKind,
+    /// Name of the active state before the transition, if there was one.
+    pub from: Option<&'static str>,
+    /// Name of the active state after the transition, `None` if the state machine stopped.
+    pub to: Option<&'static str>,
+    /// Names of all states on the stack after the transition, bottom first.
+    pub stack: Vec<&'static str>,
+    /// `Time::frame_number` of the frame the transition happened in.
+    pub frame_number: u64,
+}
+
+/// The most recent `StateTransitionEvent`s, oldest first.
+#[derive(Clone, Debug)]
+pub struct StateTransitionHistory {
+    capacity: usize,
+    events: VecDeque<StateTransitionEvent>,
+}
+
+impl StateTransitionHistory {
+    /// Creates a history keeping the given number of most recent transitions.
+    pub fn new(capacity: usize) -> Self {
+        StateTransitionHistory {
+            capacity,
+            events: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    /// Returns the maximum number of transitions kept.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns the kept transitions, oldest first.
+    pub fn iter(&self) -> impl Iterator<Item = &StateTransitionEvent> {
+        self.events.iter()
+    }
+
+    /// Returns the most recent transition.
+    pub fn last(&self) -> Option<&StateTransitionEvent> {
+        self.events.back()
+    }
+
+    /// Returns the number of kept transitions.
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    /// Returns `true` if no transition happened yet.
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    /// Adds a transition, dropping the oldest one if the history is full.
+    pub fn push(&mut self, event: StateTransitionEvent) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.events.len() == self.capacity {
+            self.events.pop_front();
+        }
+        self.events.push_back(event);
+    }
+
+    /// Removes all transitions.
+    pub fn clear(&mut self) {
+        self.events.clear();
+    }
+}
+
+impl Default for StateTransitionHistory {
+    fn default() -> Self {
+        StateTransitionHistory::new(64)
+    }
+}
+
 /// A simple stack-based state machine (pushdown automaton).
 #[derive(Derivative)]
 #[derivative(Debug)]
 pub struct StateMachine<'a, T, E> {
     running: bool,
+    /// Whether the machine was stopped by `Trans::Quit`, as opposed to popping the last state.
+    quit: bool,
     #[derivative(Debug = "ignore")]
     state_stack: Vec<Box<dyn State<T, E> + 'a>>,
 }
@@ -376,6 +511,7 @@ impl<'a, T, E: Send + Sync + 'static> StateMachine<'a, T, E> {
     pub fn new<S: State<T, E> + 'a>(initial_state: S) -> StateMachine<'a, T, E> {
         StateMachine {
             running: false,
+            quit: false,
             state_stack: vec![Box::new(initial_state)],
         }
     }
@@ -388,16 +524,42 @@ impl<'a, T, E: Send + Sync + 'static> StateMachine<'a, T, E> {
     /// Initializes the state machine.
     pub fn start(&mut self, data: StateData<'_, T>) -> Result<(), StateError> {
         if !self.running {
+            let StateData { world, data } = data;
             let state = self
                 .state_stack
                 .last_mut()
                 .ok_or(StateError::NoStatesPresent)?;
-            state.on_start(data);
+            state.on_start(StateData { world, data });
             self.running = true;
+            self.publish(world, TransKind::Start, None);
         }
         Ok(())
     }
 
+    /// Returns the name of the active state.
+    fn active_name(&self) -> Option<&'static str> {
+        self.state_stack.last().map(|state| state.name())
+    }
+
+    /// Publishes the transition from the state `from` to the now active state.
+    fn publish(&self, world: &World, kind: TransKind, from: Option<&'static str>) {
+        let event = StateTransitionEvent {
+            kind,
+            from,
+            to: self.active_name(),
+            stack: self.state_stack.iter().map(|state| state.name()).collect(),
+            frame_number: world
+                .try_fetch::<Time>()
+                .map_or(0, |time| time.frame_number()),
+        };
+        if let Some(mut history) = world.try_fetch_mut::<StateTransitionHistory>() {
+            history.push(event.clone());
+        }
+        if let Some(mut channel) = world.try_fetch_mut::<EventChannel<StateTransitionEvent>>() {
+            channel.single_write(event);
+        }
+    }
+
     /// Passes a single event to the active state to handle.
     pub fn handle_event(&mut self, data: StateData<'_, T>, event: E) {
         let StateData { world, data } = data;
@@ -469,24 +631,43 @@ impl<'a, T, E: Send + Sync + 'static> StateMachine<'a, T, E> {
     /// sequentially in the order of insertion.
     pub fn transition(&mut self, request: Trans<T, E>, data: StateData<'_, T>) {
         if self.running {
-            match request {
-                Trans::None => (),
-                Trans::Pop => self.pop(data),
-                Trans::Push(state) => self.push(state, data),
-                Trans::Switch(state) => self.switch(state, data),
-                Trans::Replace(state) => self.replace(state, data),
-                Trans::NewStack(states) => self.new_stack(states, data),
+            let StateData { world, data } = data;
+            let from = self.active_name();
+            let kind = match request {
+                Trans::None => return,
+                Trans::Pop => {
+                    self.pop(StateData { world, data });
+                    TransKind::Pop
+                }
+                Trans::Push(state) => {
+                    self.push(state, StateData { world, data });
+                    TransKind::Push
+                }
+                Trans::Switch(state) => {
+                    self.switch(state, StateData { world, data });
+                    TransKind::Switch
+                }
+                Trans::Replace(state) => {
+                    self.replace(state, StateData { world, data });
+                    TransKind::Replace
+                }
+                Trans::NewStack(states) => {
+                    self.new_stack(states, StateData { world, data });
+                    TransKind::NewStack
+                }
                 Trans::Sequence(sequence) => {
                     for trans in sequence {
-                        let temp_data = StateData {
-                            world: data.world,
-                            data: data.data,
-                        };
-                        self.transition(trans, temp_data);
+                        self.transition(trans, StateData { world, data });
                     }
+                    return;
                 }
-                Trans::Quit => self.stop(data),
-            }
+                Trans::Quit => {
+                    self.quit = true;
+                    self.stop(StateData { world, data });
+                    return;
+                }
+            };
+            self.publish(world, kind, from);
         }
     }
 
@@ -586,15 +767,397 @@ impl<'a, T, E: Send + Sync + 'static> StateMachine<'a, T, E> {
     pub(crate) fn stop(&mut self, data: StateData<'_, T>) {
         if self.running {
             let StateData { world, data } = data;
+            let from = self.active_name();
             while let Some(mut state) = self.state_stack.pop() {
                 state.on_stop(StateData { world, data });
             }
 
             self.running = false;
+            self.publish(world, TransKind::Quit, from);
+        }
+    }
+}
+
+/// A region of `StateRegions`, with its own state stack and data.
+struct Region<'a, T, E> {
+    name: String,
+    machine: StateMachine<'a, T, E>,
+    init: Option<Box<dyn FnOnce(&mut World) -> T + 'a>>,
+    data: Option<T>,
+}
+
+/// A state running several state stacks in parallel.
+///
+/// The main region shares the data of the state machine `StateRegions` is pushed on, every other
+/// region builds its own data when `StateRegions` starts, e.g. a `GameData` with its own
+/// dispatcher. All active states of the regions receive every event and are updated every
+/// frame, main region first, and the `Trans` they return are applied to the stack of their own
+/// region. To transition the outer state machine, e.g. to leave the regions altogether, use the
+/// `TransEvent` channel.
+///
+/// The regions are stopped and their data disposed when the main region pops its last state.
+/// Other regions end on their own when their stack is empty, `Trans::Quit` in any region stops
+/// the whole application.
+///
+/// # Example
+///
+/// ```rust, no_run
+/// use amethyst::{prelude::*, StateRegions};
+///
+/// struct Gameplay;
+/// impl SimpleState for Gameplay {}
+///
+/// struct Dialogue;
+/// impl SimpleState for Dialogue {}
+///
+/// let gameplay = StateRegions::new(Gameplay)
+///     .with_region("dialogue", Dialogue, GameDataBuilder::default());
+/// ```
+#[derive(Derivative)]
+#[derivative(Debug)]
+pub struct StateRegions<'a, T, E> {
+    main: StateMachine<'a, T, E>,
+    #[derivative(Debug = "ignore")]
+    regions: Vec<Region<'a, T, E>>,
+}
+
+impl<'a, T, E> StateRegions<'a, T, E>
+where
+    T: DataDispose + 'a,
+    E: Send + Sync + 'static,
+{
+    /// Creates the regions with the initial state of the main region.
+    pub fn new<S: State<T, E> + 'a>(initial_state: S) -> Self {
+        StateRegions {
+            main: StateMachine::new(initial_state),
+            regions: Vec::new(),
+        }
+    }
+
+    /// Adds a region with the given initial state, running with its own data built by `init`.
+    pub fn with_region<S, I>(mut self, name: &str, initial_state: S, init: I) -> Self
+    where
+        S: State<T, E> + 'a,
+        I: DataInit<T> + 'a,
+    {
+        self.regions.push(Region {
+            name: name.to_owned(),
+            machine: StateMachine::new(initial_state),
+            init: Some(Box::new(move |world| init.build(world))),
+            data: None,
+        });
+        self
+    }
+
+    /// Returns the names of the regions which are still running, excluding the main region.
+    pub fn running_regions(&self) -> impl Iterator<Item = &str> {
+        self.regions
+            .iter()
+            .filter(|region| region.machine.is_running())
+            .map(|region| region.name.as_str())
+    }
+
+    /// Calls `f` with the machine and data of every running region, main region first, and
+    /// returns the `Trans` for the outer state machine.
+    fn each<F>(&mut self, data: StateData<'_, T>, mut f: F) -> Trans<T, E>
+    where
+        F: FnMut(&mut StateMachine<'a, T, E>, StateData<'_, T>),
+    {
+        let StateData { world, data } = data;
+        if self.main.is_running() {
+            f(&mut self.main, StateData { world, data });
+        }
+        for region in &mut self.regions {
+            if let Some(region_data) = region.data.as_mut() {
+                f(&mut region.machine, StateData::new(world, region_data));
+                if !region.machine.is_running() {
+                    region_data.dispose(world);
+                    region.data = None;
+                }
+            }
+        }
+
+        if self.main.quit || self.regions.iter().any(|region| region.machine.quit) {
+            Trans::Quit
+        } else if !self.main.is_running() {
+            Trans::Pop
+        } else {
+            Trans::None
+        }
+    }
+}
+
+impl<'a, T, E> State<T, E> for StateRegions<'a, T, E>
+where
+    T: DataDispose + 'a,
+    E: Clone + Send + Sync + 'static,
+{
+    fn on_start(&mut self, data: StateData<'_, T>) {
+        let StateData { world, data } = data;
+        self.main
+            .start(StateData { world, data })
+            .expect("Unreachable: Regions are created with an initial state");
+        for region in &mut self.regions {
+            if let Some(init) = region.init.take() {
+                let mut region_data = init(world);
+                region
+                    .machine
+                    .start(StateData::new(world, &mut region_data))
+                    .expect("Unreachable: Regions are created with an initial state");
+                region.data = Some(region_data);
+            }
+        }
+    }
+
+    fn on_stop(&mut self, data: StateData<'_, T>) {
+        let StateData { world, data } = data;
+        for region in &mut self.regions {
+            if let Some(mut region_data) = region.data.take() {
+                region.machine.stop(StateData::new(world, &mut region_data));
+                region_data.dispose(world);
+            }
+        }
+        self.main.stop(StateData { world, data });
+    }
+
+    fn on_pause(&mut self, data: StateData<'_, T>) {
+        self.each(data, |machine, data| {
+            if let Some(state) = machine.state_stack.last_mut() {
+                state.on_pause(data);
+            }
+        });
+    }
+
+    fn on_resume(&mut self, data: StateData<'_, T>) {
+        self.each(data, |machine, data| {
+            if let Some(state) = machine.state_stack.last_mut() {
+                state.on_resume(data);
+            }
+        });
+    }
+
+    fn handle_event(&mut self, data: StateData<'_, T>, event: E) -> Trans<T, E> {
+        self.each(data, |machine, data| {
+            machine.handle_event(data, event.clone())
+        })
+    }
+
+    fn fixed_update(&mut self, data: StateData<'_, T>) -> Trans<T, E> {
+        self.each(data, |machine, data| machine.fixed_update(data))
+    }
+
+    fn update(&mut self, data: StateData<'_, T>) -> Trans<T, E> {
+        self.each(data, |machine, data| machine.update(data))
+    }
+
+    fn shadow_fixed_update(&mut self, data: StateData<'_, T>) {
+        self.each(data, |machine, data| {
+            let StateData { world, data } = data;
+            for state in &mut machine.state_stack {
+                state.shadow_fixed_update(StateData { world, data });
+            }
+        });
+    }
+
+    fn shadow_update(&mut self, data: StateData<'_, T>) {
+        self.each(data, |machine, data| {
+            let StateData { world, data } = data;
+            for state in &mut machine.state_stack {
+                state.shadow_update(StateData { world, data });
+            }
+        });
+    }
+}
+
+/// Progress of the assets preloaded by the running `LoadingState`.
+///
+/// The resource is inserted while a `LoadingState` waits for its assets, so loading screens can
+/// display it.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct LoadingProgress {
+    /// Number of assets which finished loading.
+    pub finished: usize,
+    /// Number of assets which failed to load.
+    pub failed: usize,
+    /// Number of preloaded assets.
+    pub total: usize,
+    /// Number of bytes read so far.
+    pub bytes_read: usize,
+}
+
+impl LoadingProgress {
+    fn new(progress: &ProgressCounter) -> Self {
+        LoadingProgress {
+            finished: progress.num_finished(),
+            failed: progress.num_failed(),
+            total: progress.num_assets(),
+            bytes_read: progress.bytes_read(),
+        }
+    }
+
+    /// Returns the finished fraction of the preloaded assets, `1.0` if there are none.
+    pub fn fraction(&self) -> f32 {
+        if self.total == 0 {
+            1.0
+        } else {
+            self.finished as f32 / self.total as f32
+        }
+    }
+}
+
+type ErrorHandler<T, E> = Box<dyn FnMut(StateData<'_, T>, StateError) -> Trans<T, E>>;
+
+/// A state which preloads assets before transitioning to the target state.
+///
+/// On start the `preload` closure is called to start loading the assets, tracked by the
+/// `ProgressCounter` it returns. Until they are loaded the loading state runs in its place,
+/// receiving all events and updates, and the `LoadingProgress` resource is kept up to date.
+/// Once all assets are loaded, the `LoadingState` switches to the target state: push the
+/// `LoadingState` to push the target state, or switch to it to switch to the target state.
+///
+/// If an asset fails to load, the error handler is called with `StateError::LoadingFailed`; by
+/// default the error is logged and the `LoadingState` is popped.
+///
+/// Assets are only processed while the dispatcher runs, so the loading state has to run it in
+/// its `update`, which `SimpleState`s do.
+///
+/// # Example
+///
+/// ```rust, no_run
+/// use amethyst::{assets::ProgressCounter, prelude::*, LoadingState};
+///
+/// struct LoadingScreen;
+/// impl SimpleState for LoadingScreen {}
+///
+/// struct Level;
+/// impl SimpleState for Level {}
+///
+/// let trans: SimpleTrans = Trans::Switch(Box::new(LoadingState::new(
+///     LoadingScreen,
+///     Level,
+///     |_world| {
+///         let progress = ProgressCounter::new();
+///         // Load the assets of the level with `&mut progress`.
+///         progress
+///     },
+/// )));
+/// ```
+#[derive(Derivative)]
+#[derivative(Debug)]
+pub struct LoadingState<T, E> {
+    #[derivative(Debug = "ignore")]
+    loading: Box<dyn State<T, E>>,
+    #[derivative(Debug = "ignore")]
+    target: Option<Box<dyn State<T, E>>>,
+    #[derivative(Debug = "ignore")]
+    preload: Option<Box<dyn FnOnce(&mut World) -> ProgressCounter>>,
+    progress: ProgressCounter,
+    #[derivative(Debug = "ignore")]
+    on_error: ErrorHandler<T, E>,
+}
+
+impl<T: 'static, E: Send + Sync + 'static> LoadingState<T, E> {
+    /// Creates a state which runs `loading` until the assets loaded by `preload` are loaded,
+    /// then switches to `target`.
+    pub fn new<L, S, F>(loading: L, target: S, preload: F) -> Self
+    where
+        L: State<T, E> + 'static,
+        S: State<T, E> + 'static,
+        F: FnOnce(&mut World) -> ProgressCounter + 'static,
+    {
+        LoadingState {
+            loading: Box::new(loading),
+            target: Some(Box::new(target)),
+            preload: Some(Box::new(preload)),
+            progress: ProgressCounter::new(),
+            on_error: Box::new(|_, err| {
+                error!("{}", err);
+                Trans::Pop
+            }),
+        }
+    }
+
+    /// Sets the handler called if the preloaded assets fail to load, which returns the
+    /// transition to perform instead of switching to the target state.
+    pub fn with_error_handler<F>(mut self, on_error: F) -> Self
+    where
+        F: FnMut(StateData<'_, T>, StateError) -> Trans<T, E> + 'static,
+    {
+        self.on_error = Box::new(on_error);
+        self
+    }
+
+    /// Checks the loading progress, returns the transition to the target state once the
+    /// assets are loaded.
+    fn poll(&mut self, data: StateData<'_, T>) -> Trans<T, E> {
+        if self.target.is_none() {
+            return Trans::None;
+        }
+        data.world.insert(LoadingProgress::new(&self.progress));
+        match self.progress.complete() {
+            Completion::Loading => Trans::None,
+            Completion::Complete => {
+                Trans::Switch(self.target.take().expect("Unreachable: Checked above"))
+            }
+            Completion::Failed => {
+                self.target = None;
+                let err = StateError::LoadingFailed(self.progress.errors());
+                (self.on_error)(data, err)
+            }
         }
     }
 }
 
+impl<T: 'static, E: Send + Sync + 'static> State<T, E> for LoadingState<T, E> {
+    fn on_start(&mut self, data: StateData<'_, T>) {
+        if let Some(preload) = self.preload.take() {
+            self.progress = preload(data.world);
+        }
+        data.world.insert(LoadingProgress::new(&self.progress));
+        self.loading.on_start(data);
+    }
+
+    fn on_stop(&mut self, data: StateData<'_, T>) {
+        self.loading.on_stop(StateData {
+            world: data.world,
+            data: data.data,
+        });
+        data.world.remove::<LoadingProgress>();
+    }
+
+    fn on_pause(&mut self, data: StateData<'_, T>) {
+        self.loading.on_pause(data);
+    }
+
+    fn on_resume(&mut self, data: StateData<'_, T>) {
+        self.loading.on_resume(data);
+    }
+
+    fn handle_event(&mut self, data: StateData<'_, T>, event: E) -> Trans<T, E> {
+        self.loading.handle_event(data, event)
+    }
+
+    fn fixed_update(&mut self, data: StateData<'_, T>) -> Trans<T, E> {
+        self.loading.fixed_update(data)
+    }
+
+    fn update(&mut self, data: StateData<'_, T>) -> Trans<T, E> {
+        let StateData { world, data } = data;
+        match self.loading.update(StateData { world, data }) {
+            Trans::None => self.poll(StateData { world, data }),
+            trans => trans,
+        }
+    }
+
+    fn shadow_fixed_update(&mut self, data: StateData<'_, T>) {
+        self.loading.shadow_fixed_update(data);
+    }
+
+    fn shadow_update(&mut self, data: StateData<'_, T>) {
+        self.loading.shadow_update(data);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -724,4 +1287,226 @@ mod tests {
         sm.update(StateData::new(&mut world, &mut ()));
         assert_eq!(sm.state_stack.len(), 1);
     }
+
+    #[derive(Default)]
+    struct Updates(Vec<&'static str>);
+
+    /// Records its updates and returns the given transition after `frames` updates.
+    struct Region(&'static str, u8, Option<Trans<(), ()>>);
+
+    impl State<(), ()> for Region {
+        fn update(&mut self, data: StateData<'_, ()>) -> Trans<(), ()> {
+            use crate::ecs::prelude::WorldExt;
+
+            data.world.write_resource::<Updates>().0.push(self.0);
+            if self.1 > 0 {
+                self.1 -= 1;
+                Trans::None
+            } else {
+                self.2.take().unwrap_or(Trans::None)
+            }
+        }
+    }
+
+    #[test]
+    fn parallel_regions() {
+        use crate::ecs::prelude::{World, WorldExt};
+
+        let mut world = World::new();
+        world.insert(Updates::default());
+
+        let regions = StateRegions::new(Region("main", 2, Some(Trans::Pop)))
+            .with_region("overlay", Region("overlay", 0, Some(Trans::Pop)), ())
+            .with_region(
+                "dialogue",
+                Region(
+                    "dialogue",
+                    0,
+                    Some(Trans::Switch(Box::new(Region("reply", 5, None)))),
+                ),
+                (),
+            );
+        let mut sm = StateMachine::new(regions);
+        sm.start(StateData::new(&mut world, &mut ())).unwrap();
+
+        for _ in 0..3 {
+            sm.update(StateData::new(&mut world, &mut ()));
+        }
+        assert!(!sm.is_running());
+        assert_eq!(
+            vec!["main", "overlay", "dialogue", "main", "reply", "main", "reply"],
+            world.read_resource::<Updates>().0
+        );
+    }
+
+    #[test]
+    fn quit_in_region_stops_machine() {
+        use crate::ecs::prelude::{World, WorldExt};
+
+        let mut world = World::new();
+        world.insert(Updates::default());
+
+        let regions = StateRegions::new(Region("main", 5, None)).with_region(
+            "menu",
+            Region("menu", 1, Some(Trans::Quit)),
+            (),
+        );
+        let mut sm = StateMachine::new(regions);
+        sm.start(StateData::new(&mut world, &mut ())).unwrap();
+
+        sm.update(StateData::new(&mut world, &mut ()));
+        assert!(sm.is_running());
+        sm.update(StateData::new(&mut world, &mut ()));
+        assert!(!sm.is_running());
+    }
+
+    #[test]
+    fn loading_state_waits_for_assets() {
+        use crate::{
+            assets::{Progress, Tracker},
+            ecs::prelude::{World, WorldExt},
+        };
+
+        struct Trackers(Vec<Box<dyn Tracker + Sync>>);
+
+        let mut world = World::new();
+        world.insert(Updates::default());
+        let loading = LoadingState::new(Region("loading", 10, None), Region("level", 10, None), {
+            |world: &mut World| {
+                let mut progress = ProgressCounter::new();
+                (&mut progress).add_assets(2);
+                let trackers = vec![
+                    Box::new((&mut progress).create_tracker()) as Box<dyn Tracker + Sync>,
+                    Box::new((&mut progress).create_tracker()),
+                ];
+                world.insert(Trackers(trackers));
+                progress
+            }
+        });
+        let mut sm = StateMachine::new(State0);
+        sm.start(StateData::new(&mut world, &mut ())).unwrap();
+        sm.transition(
+            Trans::Push(Box::new(loading)),
+            StateData::new(&mut world, &mut ()),
+        );
+
+        sm.update(StateData::new(&mut world, &mut ()));
+        let tracker = world.write_resource::<Trackers>().0.pop().unwrap();
+        tracker.success();
+        sm.update(StateData::new(&mut world, &mut ()));
+        assert_eq!(0.5, world.read_resource::<LoadingProgress>().fraction());
+
+        let tracker = world.write_resource::<Trackers>().0.pop().unwrap();
+        tracker.success();
+        sm.update(StateData::new(&mut world, &mut ()));
+        sm.update(StateData::new(&mut world, &mut ()));
+        assert_eq!(
+            vec!["loading", "loading", "loading", "level"],
+            world.read_resource::<Updates>().0
+        );
+        assert!(!world.has_value::<LoadingProgress>());
+        assert_eq!(2, sm.state_stack.len());
+    }
+
+    #[test]
+    fn loading_errors_are_handled() {
+        use crate::{
+            assets::{Progress, Tracker},
+            ecs::prelude::{World, WorldExt},
+            error::format_err,
+        };
+
+        #[derive(Default)]
+        struct Failures(usize);
+
+        let mut world = World::new();
+        world.insert(Updates::default());
+        world.insert(Failures::default());
+        let loading = LoadingState::new(Region("loading", 10, None), Region("level", 10, None), {
+            |_: &mut World| {
+                let mut progress = ProgressCounter::new();
+                (&mut progress).add_assets(1);
+                Box::new((&mut progress).create_tracker()).fail(
+                    0,
+                    "Mesh",
+                    "level.obj".to_owned(),
+                    format_err!("Not found"),
+                );
+                progress
+            }
+        })
+        .with_error_handler(|data, err| {
+            use crate::ecs::prelude::WorldExt;
+
+            match err {
+                StateError::LoadingFailed(errors) => {
+                    data.world.write_resource::<Failures>().0 += errors.len()
+                }
+                err => panic!("Unexpected error: {}", err),
+            }
+            Trans::Pop
+        });
+        let mut sm = StateMachine::new(loading);
+        sm.start(StateData::new(&mut world, &mut ())).unwrap();
+
+        sm.update(StateData::new(&mut world, &mut ()));
+        assert!(!sm.is_running());
+        assert_eq!(1, world.read_resource::<Failures>().0);
+    }
+
+    #[test]
+    fn transitions_are_published() {
+        use crate::ecs::prelude::{World, WorldExt};
+
+        let mut world = World::new();
+        let mut time = Time::default();
+        world.insert(StateTransitionHistory::new(3));
+        world.insert(EventChannel::<StateTransitionEvent>::new());
+        let mut reader = world
+            .write_resource::<EventChannel<StateTransitionEvent>>()
+            .register_reader();
+
+        let mut sm = StateMachine::new(State1(0));
+        sm.start(StateData::new(&mut world, &mut ())).unwrap();
+        sm.transition(
+            Trans::Sequence(vec![Trans::Push(Box::new(State0)), Trans::None]),
+            StateData::new(&mut world, &mut ()),
+        );
+        time.increment_frame_number();
+        world.insert(time);
+        sm.transition(Trans::Pop, StateData::new(&mut world, &mut ()));
+        sm.update(StateData::new(&mut world, &mut ()));
+        sm.update(StateData::new(&mut world, &mut ()));
+        assert!(!sm.is_running());
+
+        let name = |state: &'static str| Some(state);
+        let (state0, state1, state2) = (
+            std::any::type_name::<State0>(),
+            std::any::type_name::<State1>(),
+            std::any::type_name::<State2>(),
+        );
+        let events = world
+            .read_resource::<EventChannel<StateTransitionEvent>>()
+            .read(&mut reader)
+            .cloned()
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                (TransKind::Start, None, name(state1), 0),
+                (TransKind::Push, name(state1), name(state0), 0),
+                (TransKind::Pop, name(state0), name(state1), 1),
+                (TransKind::Switch, name(state1), name(state2), 1),
+                (TransKind::Pop, name(state2), None, 1),
+            ],
+            events
+                .iter()
+                .map(|e| (e.kind, e.from, e.to, e.frame_number))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(vec![state1, state0], events[1].stack);
+
+        let history = world.read_resource::<StateTransitionHistory>();
+        assert_eq!(3, history.len());
+        assert_eq!(events[2..], history.iter().cloned().collect::<Vec<_>>()[..]);
+    }
 }
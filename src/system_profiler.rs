@@ -0,0 +1,525 @@
+//! Lightweight, always available profiling of the systems added through `GameDataBuilder`.
+//!
+//! Every system added with `GameDataBuilder::with` or `GameDataBuilder::with_system_desc` is
+//! timed each time it runs. The timings of the most recent frames are kept in the
+//! `SystemProfiler` resource, which can be queried for percentiles at runtime or exported as a
+//! Chrome trace, to be opened in `chrome://tracing`.
+
+use std::{
+    collections::VecDeque,
+    io::{self, Write},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread::{self, ThreadId},
+    time::{Duration, Instant},
+};
+
+use crate::core::{
+    ecs::prelude::{System, World},
+    shred::{AccessorCow, RunningTime},
+    SystemDesc,
+};
+
+/// How long a system ran in a frame.
+#[derive(Clone, Debug)]
+pub struct SystemTiming {
+    name: Arc<str>,
+    start: Duration,
+    duration: Duration,
+    thread: usize,
+}
+
+impl SystemTiming {
+    /// The name the system was added with.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// When the system started running, relative to the creation of the `SystemProfiler`.
+    pub fn start(&self) -> Duration {
+        self.start
+    }
+
+    /// How long the system ran.
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+
+    /// Index of the thread the system ran on, in the order the threads were first seen.
+    pub fn thread(&self) -> usize {
+        self.thread
+    }
+}
+
+/// The timings of all profiled systems in one frame.
+#[derive(Clone, Debug)]
+pub struct FrameProfile {
+    frame_number: u64,
+    start: Duration,
+    duration: Duration,
+    systems: Vec<SystemTiming>,
+}
+
+impl FrameProfile {
+    /// `Time::frame_number` of the frame.
+    pub fn frame_number(&self) -> u64 {
+        self.frame_number
+    }
+
+    /// When the dispatcher started running, relative to the creation of the `SystemProfiler`.
+    pub fn start(&self) -> Duration {
+        self.start
+    }
+
+    /// How long the dispatcher ran, including systems that aren't profiled.
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+
+    /// The timings of the systems, in the order they finished running.
+    pub fn systems(&self) -> &[SystemTiming] {
+        &self.systems
+    }
+
+    /// How long the systems with the given name ran in total, `None` if none of them ran.
+    pub fn system(&self, name: &str) -> Option<Duration> {
+        self.systems
+            .iter()
+            .filter(|timing| timing.name() == name)
+            .fold(None, |total, timing| {
+                Some(total.unwrap_or_default() + timing.duration)
+            })
+    }
+}
+
+#[derive(Default)]
+struct Recorded {
+    timings: Vec<SystemTiming>,
+    threads: Vec<ThreadId>,
+}
+
+/// Shared between the `SystemProfiler` and the profiled systems, which may run in parallel.
+struct Recorder {
+    epoch: Instant,
+    enabled: AtomicBool,
+    recorded: Mutex<Recorded>,
+}
+
+impl Recorder {
+    fn record(&self, name: &Arc<str>, start: Instant, duration: Duration) {
+        let id = thread::current().id();
+        let mut recorded = self.recorded.lock().expect("Profiler mutex was poisoned");
+        let thread = match recorded.threads.iter().position(|thread| *thread == id) {
+            Some(thread) => thread,
+            None => {
+                recorded.threads.push(id);
+                recorded.threads.len() - 1
+            }
+        };
+        recorded.timings.push(SystemTiming {
+            name: Arc::clone(name),
+            start: start - self.epoch,
+            duration,
+            thread,
+        });
+    }
+}
+
+/// Ring buffer resource with the system timings of the most recent frames.
+///
+/// It is inserted by `GameDataBuilder` with a capacity of 120 frames, unless there already is
+/// one in the `World`.
+pub struct SystemProfiler {
+    capacity: usize,
+    recorder: Arc<Recorder>,
+    frame: Option<(u64, Instant)>,
+    frames: VecDeque<FrameProfile>,
+}
+
+impl Default for SystemProfiler {
+    fn default() -> Self {
+        SystemProfiler::new(120)
+    }
+}
+
+impl SystemProfiler {
+    /// Creates a profiler keeping the timings of the last `capacity` frames.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if `capacity` is zero.
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "Profiler capacity must be greater than zero");
+        SystemProfiler {
+            capacity,
+            recorder: Arc::new(Recorder {
+                epoch: Instant::now(),
+                enabled: AtomicBool::new(true),
+                recorded: Mutex::new(Recorded::default()),
+            }),
+            frame: None,
+            frames: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    /// Returns the maximum number of frames kept.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns `true` if the systems are timed, which is the default.
+    pub fn is_enabled(&self) -> bool {
+        self.recorder.enabled.load(Ordering::Relaxed)
+    }
+
+    /// Enables or disables timing the systems. The kept frames are not affected.
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.recorder.enabled.store(enabled, Ordering::Relaxed);
+    }
+
+    /// Returns the kept frames, from oldest to newest.
+    pub fn frames(&self) -> impl Iterator<Item = &FrameProfile> {
+        self.frames.iter()
+    }
+
+    /// Returns the most recent frame.
+    pub fn last_frame(&self) -> Option<&FrameProfile> {
+        self.frames.back()
+    }
+
+    /// Returns the number of frames kept.
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Returns `true` if no frame was profiled yet.
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// Drops all kept frames.
+    pub fn clear(&mut self) {
+        self.frames.clear();
+    }
+
+    /// Returns the run time of the systems with the given name which `percentile` percent of
+    /// the kept frames don't exceed, e.g. `50.0` for the median.
+    ///
+    /// Frames in which the system didn't run are ignored, `None` is returned if it didn't run
+    /// in any of the kept frames.
+    pub fn percentile(&self, system: &str, percentile: f32) -> Option<Duration> {
+        let durations = self
+            .frames
+            .iter()
+            .filter_map(|frame| frame.system(system))
+            .collect();
+        nearest_rank(durations, percentile)
+    }
+
+    /// Returns the dispatcher run time which `percentile` percent of the kept frames don't
+    /// exceed.
+    pub fn frame_percentile(&self, percentile: f32) -> Option<Duration> {
+        let durations = self.frames.iter().map(FrameProfile::duration).collect();
+        nearest_rank(durations, percentile)
+    }
+
+    /// Writes the kept frames in the Chrome trace event format.
+    ///
+    /// Frames are on thread `0`, systems on the thread they ran on, starting with `1`.
+    pub fn write_chrome_trace<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        write!(writer, "[")?;
+        let mut first = true;
+        for frame in &self.frames {
+            let name = format!("frame {}", frame.frame_number);
+            write_event(
+                &mut writer,
+                &mut first,
+                &name,
+                "frame",
+                frame.start,
+                frame.duration,
+                0,
+            )?;
+            for timing in &frame.systems {
+                write_event(
+                    &mut writer,
+                    &mut first,
+                    &timing.name,
+                    "system",
+                    timing.start,
+                    timing.duration,
+                    timing.thread + 1,
+                )?;
+            }
+        }
+        writeln!(writer, "]")
+    }
+
+    pub(crate) fn begin_frame(&mut self, frame_number: u64) {
+        self.frame = Some((frame_number, Instant::now()));
+    }
+
+    pub(crate) fn end_frame(&mut self) {
+        let (frame_number, start) = match self.frame.take() {
+            Some(frame) => frame,
+            None => return,
+        };
+        let duration = start.elapsed();
+        let systems = std::mem::replace(
+            &mut self
+                .recorder
+                .recorded
+                .lock()
+                .expect("Profiler mutex was poisoned")
+                .timings,
+            Vec::new(),
+        );
+        if !self.is_enabled() {
+            return;
+        }
+        if self.frames.len() == self.capacity {
+            self.frames.pop_front();
+        }
+        self.frames.push_back(FrameProfile {
+            frame_number,
+            start: start - self.recorder.epoch,
+            duration,
+            systems,
+        });
+    }
+}
+
+impl std::fmt::Debug for SystemProfiler {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SystemProfiler")
+            .field("capacity", &self.capacity)
+            .field("enabled", &self.is_enabled())
+            .field("frames", &self.frames.len())
+            .finish()
+    }
+}
+
+fn nearest_rank(mut durations: Vec<Duration>, percentile: f32) -> Option<Duration> {
+    if durations.is_empty() {
+        return None;
+    }
+    durations.sort();
+    let rank = (percentile.max(0.0).min(100.0) / 100.0 * durations.len() as f32).ceil() as usize;
+    Some(durations[rank.max(1) - 1])
+}
+
+fn write_event<W: Write>(
+    writer: &mut W,
+    first: &mut bool,
+    name: &str,
+    category: &str,
+    start: Duration,
+    duration: Duration,
+    thread: usize,
+) -> io::Result<()> {
+    if !*first {
+        write!(writer, ",")?;
+    }
+    *first = false;
+    write!(writer, "{{\"name\":\"")?;
+    for c in name.chars() {
+        match c {
+            '"' => write!(writer, "\\\"")?,
+            '\\' => write!(writer, "\\\\")?,
+            c if c.is_control() => write!(writer, "\\u{:04x}", c as u32)?,
+            c => write!(writer, "{}", c)?,
+        }
+    }
+    write!(
+        writer,
+        "\",\"cat\":\"{}\",\"ph\":\"X\",\"ts\":{},\"dur\":{},\"pid\":0,\"tid\":{}}}",
+        category,
+        start.as_secs_f64() * 1_000_000.0,
+        duration.as_secs_f64() * 1_000_000.0,
+        thread
+    )
+}
+
+/// Times the wrapped system, created by `GameDataBuilder::with` and `with_system_desc`.
+pub(crate) struct Profiled<S> {
+    name: Arc<str>,
+    system: S,
+    recorder: Option<Arc<Recorder>>,
+}
+
+impl<S> Profiled<S> {
+    pub(crate) fn new(name: &str, system: S) -> Self {
+        Profiled {
+            name: name.into(),
+            system,
+            recorder: None,
+        }
+    }
+}
+
+impl<'s, S> System<'s> for Profiled<S>
+where
+    S: System<'s>,
+{
+    type SystemData = S::SystemData;
+
+    fn run(&mut self, data: Self::SystemData) {
+        match &self.recorder {
+            Some(recorder) if recorder.enabled.load(Ordering::Relaxed) => {
+                let start = Instant::now();
+                self.system.run(data);
+                recorder.record(&self.name, start, start.elapsed());
+            }
+            _ => self.system.run(data),
+        }
+    }
+
+    fn running_time(&self) -> RunningTime {
+        self.system.running_time()
+    }
+
+    fn accessor<'b>(&'b self) -> AccessorCow<'s, 'b, Self> {
+        match self.system.accessor() {
+            AccessorCow::Ref(accessor) => AccessorCow::Ref(accessor),
+            AccessorCow::Owned(accessor) => AccessorCow::Owned(accessor),
+        }
+    }
+
+    fn setup(&mut self, world: &mut World) {
+        let profiler = world
+            .entry::<SystemProfiler>()
+            .or_insert_with(SystemProfiler::default);
+        self.recorder = Some(Arc::clone(&profiler.recorder));
+        drop(profiler);
+        self.system.setup(world);
+    }
+
+    fn dispose(self, world: &mut World) {
+        self.system.dispose(world);
+    }
+}
+
+/// Builds a `Profiled` system, created by `GameDataBuilder::with_system_desc`.
+pub(crate) struct ProfiledDesc<SD> {
+    pub(crate) name: String,
+    pub(crate) system_desc: SD,
+}
+
+impl<'a, 'b, SD, S> SystemDesc<'a, 'b, Profiled<S>> for ProfiledDesc<SD>
+where
+    SD: SystemDesc<'a, 'b, S>,
+    S: System<'a>,
+{
+    fn build(self, world: &mut World) -> Profiled<S> {
+        Profiled::new(&self.name, self.system_desc.build(world))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use rayon::ThreadPoolBuilder;
+
+    use super::*;
+    use crate::{
+        core::{
+            ecs::prelude::{WorldExt, Write},
+            ArcThreadPool, Time,
+        },
+        game_data::{DataInit, GameDataBuilder},
+    };
+
+    struct Increment;
+
+    impl<'s> System<'s> for Increment {
+        type SystemData = Write<'s, u32>;
+
+        fn run(&mut self, mut number: Self::SystemData) {
+            *number += 1;
+        }
+    }
+
+    fn frame(frame_number: u64, durations: &[(&str, u64)]) -> FrameProfile {
+        FrameProfile {
+            frame_number,
+            start: Duration::from_millis(frame_number * 20),
+            duration: Duration::from_millis(10),
+            systems: durations
+                .iter()
+                .map(|(name, millis)| SystemTiming {
+                    name: (*name).into(),
+                    start: Duration::from_millis(frame_number * 20),
+                    duration: Duration::from_millis(*millis),
+                    thread: 0,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn systems_are_profiled() {
+        let mut world = World::new();
+        world.insert::<ArcThreadPool>(Arc::new(ThreadPoolBuilder::new().build().unwrap()));
+        world.insert(Time::default());
+        world.insert(SystemProfiler::new(3));
+        let mut game_data = GameDataBuilder::default()
+            .with(Increment, "first", &[])
+            .with(Increment, "second", &["first"])
+            .build(&mut world);
+
+        for _ in 0..5 {
+            world.write_resource::<Time>().increment_frame_number();
+            game_data.update(&world);
+        }
+
+        assert_eq!(10, *world.read_resource::<u32>());
+        let profiler = world.read_resource::<SystemProfiler>();
+        assert_eq!(
+            vec![3, 4, 5],
+            profiler
+                .frames()
+                .map(FrameProfile::frame_number)
+                .collect::<Vec<_>>()
+        );
+        for frame in profiler.frames() {
+            let names = frame.systems().iter().map(|t| t.name()).collect::<Vec<_>>();
+            assert_eq!(vec!["first", "second"], names);
+        }
+        assert!(profiler.percentile("first", 50.0).is_some());
+        assert!(profiler.percentile("third", 50.0).is_none());
+    }
+
+    #[test]
+    fn percentiles_and_trace() {
+        let mut profiler = SystemProfiler::new(10);
+        for (i, millis) in [4, 1, 3, 2].iter().enumerate() {
+            profiler
+                .frames
+                .push_back(frame(i as u64, &[("physics", *millis), ("ai", 1)]));
+        }
+        profiler.frames.push_back(frame(4, &[("ai", 1)]));
+
+        let ms = Duration::from_millis;
+        assert_eq!(Some(ms(1)), profiler.percentile("physics", 0.0));
+        assert_eq!(Some(ms(2)), profiler.percentile("physics", 50.0));
+        assert_eq!(Some(ms(3)), profiler.percentile("physics", 75.0));
+        assert_eq!(Some(ms(4)), profiler.percentile("physics", 100.0));
+        assert_eq!(Some(ms(10)), profiler.frame_percentile(99.0));
+
+        profiler.frames.truncate(1);
+        let mut trace = Vec::new();
+        profiler.write_chrome_trace(&mut trace).unwrap();
+        assert_eq!(
+            concat!(
+                r#"[{"name":"frame 0","cat":"frame","ph":"X","ts":0,"dur":10000,"pid":0,"tid":0},"#,
+                r#"{"name":"physics","cat":"system","ph":"X","ts":0,"dur":4000,"pid":0,"tid":1},"#,
+                r#"{"name":"ai","cat":"system","ph":"X","ts":0,"dur":1000,"pid":0,"tid":1}]"#,
+                "\n"
+            ),
+            String::from_utf8(trace).unwrap()
+        );
+    }
+}
@@ -0,0 +1,284 @@
+//! Named groups of systems that only run while their run criteria are met.
+//!
+//! A system set is declared with `GameDataBuilder::with_system_set` and systems are added to it
+//! with `GameDataBuilder::with_in_set`. The run criteria of all sets are checked once per frame,
+//! before the dispatcher is run, and the systems of a set whose criteria aren't met are skipped
+//! for that frame.
+
+use std::{any::type_name, collections::HashMap, fmt};
+
+use crate::{
+    core::{
+        ecs::prelude::{Read, System, World},
+        shred::{Resource, RunningTime, SystemData},
+        Time,
+    },
+    state::StateTransitionHistory,
+};
+
+/// Decides whether the systems of a system set run in the current frame.
+pub struct RunCriteria {
+    name: String,
+    check: Box<dyn FnMut(&World) -> bool>,
+}
+
+impl RunCriteria {
+    /// Creates run criteria from a closure, the set runs whenever it returns `true`.
+    pub fn new<F>(check: F) -> Self
+    where
+        F: FnMut(&World) -> bool + 'static,
+    {
+        RunCriteria {
+            name: "custom".to_string(),
+            check: Box::new(check),
+        }
+    }
+
+    /// Runs the set every `n`th frame, according to `Time::frame_number`.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if `n` is zero.
+    pub fn every_n_frames(n: u64) -> Self {
+        assert!(n > 0, "Frame interval must be greater than zero");
+        RunCriteria {
+            name: format!("every {} frames", n),
+            check: Box::new(move |world| match world.try_fetch::<Time>() {
+                Some(time) => time.frame_number() % n == 0,
+                None => false,
+            }),
+        }
+    }
+
+    /// Runs the set while the resource `R` is in the `World`.
+    pub fn resource_exists<R: Resource>() -> Self {
+        RunCriteria {
+            name: format!("resource {} exists", type_name::<R>()),
+            check: Box::new(|world| world.has_value::<R>()),
+        }
+    }
+
+    /// Runs the set while the resource `R` is in the `World` and equal to `value`.
+    pub fn resource_equals<R: Resource + PartialEq>(value: R) -> Self {
+        RunCriteria {
+            name: format!("resource {} equals value", type_name::<R>()),
+            check: Box::new(move |world| match world.try_fetch::<R>() {
+                Some(resource) => *resource == value,
+                None => false,
+            }),
+        }
+    }
+
+    /// Runs the set while the state with the given `State::name`, which defaults to the type
+    /// name of the state, is the active state.
+    ///
+    /// The active state is taken from the `StateTransitionHistory`, so the set doesn't run if it
+    /// was removed from the `World` or cleared.
+    pub fn in_state(name: &'static str) -> Self {
+        RunCriteria {
+            name: format!("in state {}", name),
+            check: Box::new(move |world| {
+                match world
+                    .try_fetch::<StateTransitionHistory>()
+                    .and_then(|history| history.last().and_then(|event| event.to))
+                {
+                    Some(active) => active == name,
+                    None => false,
+                }
+            }),
+        }
+    }
+
+    pub(crate) fn check(&mut self, world: &World) -> bool {
+        (self.check)(world)
+    }
+}
+
+impl fmt::Debug for RunCriteria {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("RunCriteria").field(&self.name).finish()
+    }
+}
+
+/// Resource telling which system sets run in the current frame.
+#[derive(Clone, Debug, Default)]
+pub struct SystemSets {
+    running: HashMap<String, bool>,
+}
+
+impl SystemSets {
+    /// Returns `true` if the systems of the given set run in the current frame.
+    ///
+    /// Sets which weren't declared with `GameDataBuilder::with_system_set` always run.
+    pub fn is_running(&self, set: &str) -> bool {
+        self.running.get(set).cloned().unwrap_or(true)
+    }
+
+    pub(crate) fn set_running(&mut self, set: &str, running: bool) {
+        match self.running.get_mut(set) {
+            Some(current) => *current = running,
+            None => {
+                self.running.insert(set.to_string(), running);
+            }
+        }
+    }
+}
+
+/// A system which is skipped together with its system set can catch up on the data it missed.
+///
+/// `skip` is called instead of `run` while the set is skipped. Systems reading from an
+/// `EventChannel` must drain their readers here, otherwise the channel grows while the set is
+/// skipped and the system sees all the old events once the set runs again. Systems without
+/// readers can leave it empty.
+///
+/// ```
+/// use amethyst::{
+///     ecs::{Read, ReaderId, System},
+///     shrev::EventChannel,
+///     Skip,
+/// };
+///
+/// struct CountEvents(ReaderId<u32>);
+///
+/// impl<'s> System<'s> for CountEvents {
+///     type SystemData = Read<'s, EventChannel<u32>>;
+///
+///     fn run(&mut self, channel: Self::SystemData) {
+///         println!("{} events", channel.read(&mut self.0).count());
+///     }
+/// }
+///
+/// impl<'s> Skip<'s> for CountEvents {
+///     fn skip(&mut self, channel: Self::SystemData) {
+///         channel.read(&mut self.0).for_each(drop);
+///     }
+/// }
+/// ```
+pub trait Skip<'s>: System<'s> {
+    /// Called instead of `run` in frames the system set is skipped.
+    fn skip(&mut self, data: Self::SystemData);
+}
+
+/// Runs the system while its set runs and calls `Skip::skip` otherwise, created by
+/// `GameDataBuilder::with_in_set`.
+pub(crate) struct InSet<S> {
+    pub(crate) set: String,
+    pub(crate) system: S,
+}
+
+impl<'s, S> System<'s> for InSet<S>
+where
+    S::SystemData: SystemData<'s>,
+    S: Skip<'s>,
+{
+    type SystemData = (Read<'s, SystemSets>, S::SystemData);
+
+    fn run(&mut self, (sets, data): Self::SystemData) {
+        if sets.is_running(&self.set) {
+            self.system.run(data);
+        } else {
+            self.system.skip(data);
+        }
+    }
+
+    fn running_time(&self) -> RunningTime {
+        self.system.running_time()
+    }
+
+    fn setup(&mut self, world: &mut World) {
+        Self::SystemData::setup(world);
+        self.system.setup(world);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use rayon::ThreadPoolBuilder;
+
+    use super::*;
+    use crate::{
+        core::{
+            ecs::prelude::{ReaderId, WorldExt, Write},
+            shrev::EventChannel,
+            ArcThreadPool,
+        },
+        game_data::{DataInit, GameDataBuilder},
+    };
+
+    #[derive(Default)]
+    struct Counts {
+        every_second: u32,
+        events: Vec<u32>,
+    }
+
+    struct CountFrames;
+
+    impl<'s> System<'s> for CountFrames {
+        type SystemData = Write<'s, Counts>;
+
+        fn run(&mut self, mut counts: Self::SystemData) {
+            counts.every_second += 1;
+        }
+    }
+
+    impl<'s> Skip<'s> for CountFrames {
+        fn skip(&mut self, _: Self::SystemData) {}
+    }
+
+    #[derive(Default)]
+    struct ReadEvents(Option<ReaderId<u32>>);
+
+    impl<'s> System<'s> for ReadEvents {
+        type SystemData = (Read<'s, EventChannel<u32>>, Write<'s, Counts>);
+
+        fn run(&mut self, (channel, mut counts): Self::SystemData) {
+            let reader = self.0.as_mut().unwrap();
+            counts.events.extend(channel.read(reader));
+        }
+
+        fn setup(&mut self, world: &mut World) {
+            Self::SystemData::setup(world);
+            self.0 = Some(world.fetch_mut::<EventChannel<u32>>().register_reader());
+        }
+    }
+
+    impl<'s> Skip<'s> for ReadEvents {
+        fn skip(&mut self, (channel, _): Self::SystemData) {
+            channel.read(self.0.as_mut().unwrap()).for_each(drop);
+        }
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Enabled;
+
+    #[test]
+    fn sets_run_according_to_criteria() {
+        let mut world = World::new();
+        world.insert::<ArcThreadPool>(Arc::new(ThreadPoolBuilder::new().build().unwrap()));
+        world.insert(Time::default());
+        let mut game_data = GameDataBuilder::default()
+            .with_system_set("every_second", RunCriteria::every_n_frames(2))
+            .with_system_set("enabled", RunCriteria::resource_exists::<Enabled>())
+            .with_in_set("every_second", CountFrames, "count_frames", &[])
+            .with_in_set("enabled", ReadEvents::default(), "read_events", &[])
+            .build(&mut world);
+
+        for frame in 0..6 {
+            world.write_resource::<Time>().increment_frame_number();
+            world
+                .write_resource::<EventChannel<u32>>()
+                .single_write(frame);
+            if frame == 3 {
+                world.insert(Enabled);
+            }
+            game_data.update(&world);
+        }
+
+        let counts = world.read_resource::<Counts>();
+        assert_eq!(3, counts.every_second);
+        assert_eq!(vec![3, 4, 5], counts.events);
+        assert!(world.read_resource::<SystemSets>().is_running("enabled"));
+    }
+}